@@ -0,0 +1,53 @@
+//! Compare all built-in heuristics on a matrix of lengths and error rates.
+//!
+//! Criterion reports the wall time. The number of expanded states is printed
+//! once per configuration, since it does not vary between runs.
+use astarpa::{make_aligner, AstarStatsAligner};
+use criterion::{criterion_group, criterion_main, Criterion};
+use pa_generate::uniform_fixed;
+use pa_heuristic::{HeuristicParams, HeuristicType, Prune};
+use std::time::Duration;
+
+fn bench(c: &mut Criterion) {
+    for (n, e) in [(1000, 0.05), (1000, 0.2), (10000, 0.05), (10000, 0.2)] {
+        let mut c = c.benchmark_group(format!("{n}/{e}"));
+        let (a, b) = &uniform_fixed(n, e);
+        for heuristic in [
+            HeuristicType::None,
+            HeuristicType::Gap,
+            HeuristicType::SH,
+            HeuristicType::CSH,
+            HeuristicType::GCSH,
+        ] {
+            // Dijkstra is quadratic, so only run it on short inputs.
+            if heuristic == HeuristicType::None && n > 1000 {
+                continue;
+            }
+            let params = HeuristicParams {
+                heuristic,
+                r: if e < 0.1 { 1 } else { 2 },
+                k: if e < 0.1 { 15 } else { 8 },
+                prune: Prune::Start,
+                ..Default::default()
+            };
+            let aligner = make_aligner(true, &params);
+            let align = || AstarStatsAligner::align(&*aligner, a, b);
+            let (_, stats) = align();
+            eprintln!(
+                "{n:>6} {e:>4} {heuristic:>5?}: expanded {:>9}",
+                stats.expanded
+            );
+            c.bench_function(format!("{heuristic:?}"), |bb| bb.iter(align));
+        }
+    }
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_millis(1000))
+        .warm_up_time(Duration::from_millis(500))
+        .sample_size(10);
+    targets = bench
+);
+criterion_main!(benches);
@@ -38,6 +38,7 @@ pub fn astar<'a, H: Heuristic>(
     h: &H,
     v: &impl VisualizerT,
 ) -> ((Cost, Cigar), AstarStats) {
+    pa_heuristic::util::assert_input_len(a, b);
     let mut v = v.build(a, b);
     astar_with_vis(a, b, h, &mut v)
 }
@@ -55,6 +56,7 @@ pub fn astar_with_vis<'a, H: Heuristic>(
     let ref graph = EditGraph::new(a, b, true);
     let ref mut h = h.build(a, b);
     stats.timing.precomp = start.elapsed().as_secs_f64();
+    stats.count_matches(h);
 
     // f -> (pos, g)
     let mut queue = ShiftQueue::<(Pos, Cost), <H::Instance<'a> as HeuristicInstance>::Order>::new(
@@ -231,6 +233,8 @@ pub fn astar_with_vis<'a, H: Heuristic>(
     let end = instant::Instant::now();
 
     stats.h = h.stats();
+    stats.matches_pruned = stats.h.num_pruned;
+    stats.contours = h.contour_stats();
     stats.h.h_duration -= double_timed;
     stats.timing.total = (end - start).as_secs_f64();
     stats.timing.traceback = (end - traceback_start).as_secs_f64();
@@ -37,12 +37,14 @@ pub fn astar_dt<'a, H: Heuristic>(
     h: &H,
     v: &impl VisualizerT,
 ) -> ((Cost, Cigar), AstarStats) {
+    pa_heuristic::util::assert_input_len(a, b);
     let mut stats = AstarStats::init(a, b);
 
     let start = instant::Instant::now();
     let ref graph = EditGraph::new(a, b, true);
     let ref mut h = h.build(a, b);
     stats.timing.precomp = start.elapsed().as_secs_f64();
+    stats.count_matches(h);
 
     let ref mut v = v.build(a, b);
 
@@ -239,6 +241,8 @@ pub fn astar_dt<'a, H: Heuristic>(
     let end = instant::Instant::now();
 
     stats.h = h.stats();
+    stats.matches_pruned = stats.h.num_pruned;
+    stats.contours = h.contour_stats();
     stats.h.h_duration -= double_timed;
     stats.timing.total = (end - start).as_secs_f64();
     stats.timing.traceback = (end - traceback_start).as_secs_f64();
@@ -35,6 +35,8 @@ pub mod stats;
 
 mod prelude {
     pub use pa_types::*;
+    /// `FxHashMap` has no random seed. The maps of A* states are only used
+    /// for lookups, so the expansion order is given by the queue alone.
     pub use rustc_hash::FxHashMap as HashMap;
 
     pub use crate::config::*;
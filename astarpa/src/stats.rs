@@ -6,7 +6,7 @@ use std::{
 use derive_more::AddAssign;
 use pa_types::{Cost, Seq};
 
-use pa_heuristic::HeuristicStats;
+use pa_heuristic::{ContourStats, HeuristicInstance, HeuristicStats};
 
 #[derive(Default, Clone, Copy, AddAssign, Debug)]
 pub struct Timing {
@@ -40,7 +40,16 @@ pub struct AstarStats {
     /// Number of states allocated in the DiagonalMap
     pub hashmap_capacity: usize,
 
+    /// Number of seeds placed by the heuristic.
+    pub seeds: usize,
+    /// Number of matches used by the heuristic when it is built.
+    pub matches_found: usize,
+    /// Number of matches pruned during the search.
+    pub matches_pruned: usize,
+
     pub h: HeuristicStats,
+    /// The contours at the end of the search.
+    pub contours: ContourStats,
 
     pub timing: Timing,
     pub sample_size: usize,
@@ -68,6 +77,13 @@ impl AstarStats {
             ..Default::default()
         }
     }
+
+    /// Count the seeds and matches of a newly built heuristic.
+    pub fn count_matches<'a>(&mut self, h: &impl HeuristicInstance<'a>) {
+        self.seeds = h.seeds().map_or(0, |s| s.seeds.len());
+        self.matches_found = h.matches().map_or(0, |m| m.len());
+    }
+
     pub fn print(&self) {
         self.print_internal(true);
     }
@@ -126,7 +142,8 @@ impl AstarStats {
             self.format_raw('>', 7, "nr", self.sample_size),
             self.format_avg('>', 10, "|a|", self.len_a),
             self.format_avg('>', 10, "|b|", self.len_b),
-            self.format_avg('>', 7, "seeds", self.h.num_seeds),
+            self.format_avg('>', 7, "seeds", self.seeds),
+            self.format_avg('>', 8, "matches", self.matches_found),
             self.format_flt(
                 '>',
                 7,
@@ -137,7 +154,7 @@ impl AstarStats {
             self.format_avg('>', 9, "explored", self.explored),
             self.format_avg('>', 9, "extended", self.extended),
             self.format_avg('>', 9, "reorders", self.reordered),
-            self.format_avg('>', 7, "pruned", self.h.num_pruned),
+            self.format_avg('>', 7, "pruned", self.matches_pruned),
             self.format_avg('>', 7, "shift", self.pq_shifts),
             self.format_flt('>', 8, "band", self.expanded as f32 / self.len_a as f32),
             self.format_avg('>', 8, "t", 1000. * self.timing.total),
@@ -102,6 +102,57 @@ mod dijkstra {
     }
 }
 
+/// Assert that `h(pos)` is at most the edit distance from `pos` to the end, for all `pos`.
+/// The true distances are computed by a DP on the reversed sequences.
+fn assert_admissible(h: impl Heuristic, a: Seq, b: Seq) {
+    let (n, m) = (a.len(), b.len());
+    let rev = |s: Seq| s.iter().rev().copied().collect::<Vec<_>>();
+    // rev_dist[n - i][m - j]: the edit distance between `a[i..]` and `b[j..]`.
+    let rev_dist = dp_matrix(&rev(a), &rev(b), &CostModel::unit());
+    let hi = h.build(a, b);
+    for i in 0..=n {
+        for j in 0..=m {
+            let pos = Pos(i as I, j as I);
+            let dist = rev_dist[n - i][m - j];
+            assert!(
+                hi.h(pos) <= dist,
+                "{} is not admissible: h({pos}) = {} > {dist}",
+                h.name(),
+                hi.h(pos),
+            );
+        }
+    }
+}
+
+mod admissible {
+    use super::*;
+
+    fn test_admissible(h: impl Heuristic) {
+        for ((a, b), _params) in gen_seqs().filter(|((a, _), _)| a.len() <= 100) {
+            assert_admissible(h, &a, &b);
+        }
+    }
+
+    #[test]
+    fn distances() {
+        test_admissible(NoCost);
+        test_admissible(ZeroCost);
+        test_admissible(GapCost);
+        test_admissible(CountCost);
+        test_admissible(BiCountCost);
+    }
+
+    #[test]
+    fn seed_heuristics() {
+        for (k, r) in [(3, 1), (5, 1), (4, 2), (7, 2)] {
+            let config = MatchConfig::new(k, r);
+            test_admissible(SH::new(config, Pruning::disabled()));
+            test_admissible(DefaultCSH::new(config, Pruning::disabled()));
+            test_admissible(GCSH::new(config, Pruning::disabled()));
+        }
+    }
+}
+
 type CSH = DefaultCSH;
 make_test!(sh, SH, false, |h| h);
 make_test!(csh, CSH, false, |h| h);
@@ -169,3 +220,27 @@ mod edge_cases {
         test_aligner_on_input(a, b, aligner, "");
     }
 }
+
+/// Matches and the search itself must be fully deterministic for
+/// benchmarks to be reproducible: no randomized hashing is used anywhere.
+#[test]
+fn deterministic() {
+    use crate::AstarStatsAligner;
+    let (a, b) = pa_generate::generate_model(2000, 0.1, pa_generate::ErrorModel::Uniform, 31415);
+    for r in [1, 2] {
+        let h = GCSH::new(MatchConfig::new(10, r), Pruning::both());
+        let m1 = h.build(&a, &b).matches();
+        let m2 = h.build(&a, &b).matches();
+        assert!(m1.is_some());
+        assert_eq!(m1, m2);
+
+        for dt in [false, true] {
+            let aligner = AstarPa { dt, h, v: NoVis };
+            let ((c1, _), s1) = AstarStatsAligner::align(&aligner, &a, &b);
+            let ((c2, _), s2) = AstarStatsAligner::align(&aligner, &a, &b);
+            assert_eq!(c1, c2);
+            assert_eq!(s1.expanded, s2.expanded);
+            assert_eq!(s1.explored, s2.explored);
+        }
+    }
+}
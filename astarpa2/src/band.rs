@@ -1,4 +1,5 @@
 use super::*;
+use pa_affine_types::check_search_bound;
 use std::cmp::*;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
@@ -97,30 +98,41 @@ impl Default for DoublingType {
 /// 2ns
 /// in case all errors are at the end and runtime is O(ng) per guess:
 /// 4.8 ns, only slightly worse than 4ns.
+///
+/// Returns `None` as soon as `is_cancelled` returns true after an iteration.
+///
+/// `s_max` is an upper bound on the cost, such as `AffineCost::max_possible_cost`.
+/// Panics when no alignment is found for `s_max`, instead of looping forever.
+/// `aligner` names the aligner in that message.
 pub fn exponential_search<T>(
+    aligner: &str,
     offset: Cost,
     s0: Cost,
     factor: f32,
+    s_max: Cost,
+    is_cancelled: impl Fn() -> bool,
     mut f: impl FnMut(Cost) -> Option<(Cost, T)>,
-) -> (Cost, T) {
+) -> Option<(Cost, T)> {
     let mut last_s = -1;
-    let mut s = offset + s0;
+    let mut s = min(offset + s0, s_max);
     let mut maxs = Cost::MAX;
-    // TODO: Fix the potential infinite loop here.
-    //
     // Sanity checks:
     // - Once the answer is found, this should be larger than all previous thresholds.
     // - Once a value for maxs has been found, all subsequent larger values of s
     //   should return a value that is smaller.
     loop {
-        if let Some((cost, t)) = f(s) {
+        let r = f(s);
+        if is_cancelled() {
+            return None;
+        }
+        if let Some((cost, t)) = r {
             assert!(
                 cost <= maxs,
                 "A solution {maxs} was found for a previous s<={last_s}, but s={s} gives {cost}"
             );
             if cost <= s {
                 assert!(cost > last_s, "Cost {cost} was found at s {s} but should already have been found at last_s {last_s}");
-                return (cost, t);
+                return Some((cost, t));
             } else {
                 // If some value was returned this is an upper bound on the answer.
                 maxs = min(maxs, cost);
@@ -131,35 +143,42 @@ pub fn exponential_search<T>(
                 "A solution {maxs} was found for a previous s<={last_s}, but not for current s={s}"
             );
         }
+        check_search_bound(aligner, s, s_max);
         last_s = s;
         s = max((factor * (s - offset) as f32).ceil() as Cost, 1) + offset;
-        s = min(s, maxs);
+        s = min(s, min(maxs, s_max));
     }
 }
 
+/// Returns `None` as soon as `is_cancelled` returns true after an iteration.
 pub fn linear_search<T>(
+    aligner: &str,
     s0: Cost,
     delta: Cost,
+    s_max: Cost,
+    is_cancelled: impl Fn() -> bool,
     mut f: impl FnMut(Cost) -> Option<(Cost, T)>,
-) -> (Cost, T) {
+) -> Option<(Cost, T)> {
     let mut last_s = -1;
-    let mut s = s0;
+    let mut s = min(s0, s_max);
     let mut maxs = Cost::MAX;
-    // TODO: Fix the potential infinite loop here.
-    //
     // Sanity checks:
     // - Once the answer is found, this should be larger than all previous thresholds.
     // - Once a value for maxs has been found, all subsequent larger values of s
     //   should return a value that is smaller.
     loop {
-        if let Some((cost, t)) = f(s) {
+        let r = f(s);
+        if is_cancelled() {
+            return None;
+        }
+        if let Some((cost, t)) = r {
             assert!(
                 cost <= maxs,
                 "A solution {maxs} was found for a previous s<={last_s}, but s={s} gives {cost}"
             );
             if cost <= s {
                 assert!(cost > last_s, "Cost {cost} was found at s {s} but should already have been found at last_s {last_s}");
-                return (cost, t);
+                return Some((cost, t));
             } else {
                 // If some value was returned this is an upper bound on the answer.
                 maxs = min(maxs, cost);
@@ -170,7 +189,8 @@ pub fn linear_search<T>(
                 "A solution {maxs} was found for a previous s<={last_s}, but not for current s={s}"
             );
         }
+        check_search_bound(aligner, s, s_max);
         last_s = s;
-        s = min(s + delta, maxs);
+        s = min(s + delta, min(maxs, s_max));
     }
 }
@@ -15,6 +15,7 @@ use std::{
 };
 
 use itertools::{izip, Itertools};
+use pa_affine_types::{AffineCigarOp, IndelTieBreak, State};
 use pa_bitpacking::{BitProfile, HEncoding, Profile, B, V};
 use pa_types::*;
 use pa_vis::VisualizerInstance;
@@ -23,8 +24,8 @@ use serde::{Deserialize, Serialize};
 use super::*;
 use crate::block::*;
 
-type PA = <BitProfile as Profile>::A;
-type PB = <BitProfile as Profile>::B;
+pub(crate) type PA = <BitProfile as Profile>::A;
+pub(crate) type PB = <BitProfile as Profile>::B;
 type H = (B, B);
 
 /// Parameters for BitBlock.
@@ -57,6 +58,32 @@ pub struct BlockParams {
     /// X-drop for dt-trace.
     #[serde(default)]
     pub fr_drop: I,
+
+    /// Which of multiple optimal paths to return.
+    #[serde(default)]
+    pub traceback: TracebackPolicy,
+
+    /// Whether traceback prefers insertions or deletions.
+    #[serde(default)]
+    pub indel_tie_break: IndelTieBreak,
+}
+
+/// The order in which traceback tries the parents of a state, which decides
+/// where indels are placed when multiple paths are optimal.
+/// Whether insertions or deletions are tried first is set separately by
+/// `IndelTieBreak`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TracebackPolicy {
+    /// Greedily follow matches, and then prefer indels over substitutions.
+    /// This is the only policy that uses `dt_trace`.
+    #[default]
+    Diagonal,
+    /// Prefer matches and substitutions over indels, so that indels are
+    /// placed as far left as possible, as in VCF normalization.
+    LeftAlign,
+    /// Prefer indels over matches and substitutions, so that indels are
+    /// placed as far right as possible.
+    RightAlign,
 }
 
 impl Default for BlockParams {
@@ -69,6 +96,8 @@ impl Default for BlockParams {
             dt_trace: false,
             max_g: 40,
             fr_drop: 20,
+            traceback: TracebackPolicy::Diagonal,
+            indel_tie_break: IndelTieBreak::Ins,
         }
     }
 }
@@ -78,12 +107,37 @@ pub struct BlockStats {
     pub num_blocks: usize,
     pub num_incremental_blocks: usize,
     pub computed_lanes: usize,
+    /// The number of computed states, `W` per lane per column.
+    pub computed_states: usize,
     pub unique_lanes: usize,
 
     pub t_compute: Duration,
 }
 
 /// The main data for bitblocks.
+///
+/// Blocks can be driven with a custom outer loop, like `align_for_bounded_dist`
+/// does: `init` the first column, and then `compute_next_block` (or
+/// `reuse_next_block`) for consecutive `i_range`s. This computes the full edit
+/// distance matrix, one block of 64 columns at a time:
+/// ```
+/// use astarpa2::{BlockParams, IRange, JRange};
+/// let a = b"ACGTACGTACGTACGTACGT";
+/// let b = b"ACGTTCGTACGACGTACGT";
+/// let (n, m) = (a.len() as i32, b.len() as i32);
+///
+/// let params = BlockParams {
+///     incremental_doubling: false,
+///     ..Default::default()
+/// };
+/// let mut blocks = params.new(false, a, b);
+/// blocks.init(JRange(0, m));
+/// for i in (0..n).step_by(64) {
+///     let i_range = IRange(i, (i + 64).min(n));
+///     blocks.compute_next_block(i_range, JRange(0, m), &mut pa_vis::NoVis);
+/// }
+/// assert_eq!(blocks.last_block().index(m), 2);
+/// ```
 pub struct Blocks {
     // Input/parameters.
     params: BlockParams,
@@ -104,12 +158,20 @@ pub struct Blocks {
     /// This allows for incremental band doubling.
     h: Vec<H>,
 
+    /// Whether the first column was set by `init_with_boundary`.
+    boundary: bool,
+
     pub stats: BlockStats,
 }
 
 impl BlockParams {
     pub fn new<'a>(&self, trace: bool, a: Seq<'a>, b: Seq<'a>) -> Blocks {
         let (a, b) = BitProfile::build(a, b);
+        self.new_with_profile(trace, a, b)
+    }
+
+    /// As `new`, for sequences that were already packed by `BitProfile`.
+    pub fn new_with_profile(&self, trace: bool, a: Vec<PA>, b: Vec<PB>) -> Blocks {
         Blocks {
             params: *self,
             blocks: vec![],
@@ -123,6 +185,7 @@ impl BlockParams {
             },
             a,
             b,
+            boundary: false,
             stats: BlockStats::default(),
         }
     }
@@ -143,10 +206,14 @@ impl Index<usize> for Blocks {
 }
 
 impl Blocks {
+    /// Initialize the first column, `i = 0`, for rows `initial_j_range`,
+    /// which must start at `0`. Blocks from a previous iteration are kept
+    /// around to be reused.
     pub fn init(&mut self, mut initial_j_range: JRange) {
         assert_eq!(initial_j_range.0, 0);
         self.last_block_idx = 0;
         self.i_range = IRange(-1, 0);
+        self.boundary = false;
 
         let fixed_j_range = initial_j_range;
         if let Some(block) = self.blocks.get(0) {
@@ -178,6 +245,33 @@ impl Blocks {
         }
     }
 
+    /// As `init`, but with the given values in the first column instead of
+    /// `0, 1, 2, ...`, e.g. the costs of entering a rectangle of a larger DP
+    /// matrix through its left column. Consecutive values must differ by at most 1.
+    ///
+    /// Traceback then stops at the first column, at any row.
+    pub fn init_with_boundary(&mut self, boundary: &[Cost]) {
+        assert!(
+            boundary.windows(2).all(|w| (w[1] - w[0]).abs() <= 1),
+            "Consecutive boundary values must differ by at most 1"
+        );
+        let j_range = JRange(0, boundary.len() as I - 1);
+        self.init(j_range);
+        self.boundary = true;
+
+        let block = &mut self.blocks[0];
+        for (j, w) in boundary.windows(2).enumerate() {
+            let bit = 1 << (j % W);
+            let (p, m) = block.v[j / W].pm();
+            let p = (p & !bit) | if w[1] - w[0] == 1 { bit } else { 0 };
+            let m = (m & !bit) | if w[1] - w[0] == -1 { bit } else { 0 };
+            block.v[j / W] = V::from(p, m);
+        }
+        block.top_val = boundary[0];
+        block.bot_val = boundary[0] + V::value_to(&block.v, block.j_range.1);
+        block.check_top_bot_val();
+    }
+
     /// Remove the last block and update the i_range.
     pub fn pop_last_block(&mut self) {
         self.i_range.pop(self.blocks[self.last_block_idx].i_range);
@@ -544,15 +638,89 @@ impl Blocks {
         self.stats.t_compute += start.elapsed();
     }
 
+    /// The block at the end of the last computed or reused `i_range`.
     pub fn last_block(&self) -> &Block {
         &self.blocks[self.last_block_idx]
     }
 
+    /// The value at `pos`, when column `pos.0` is stored and `pos.1` is in its `j_range`.
+    ///
+    /// With `sparse` blocks, only the last column of each block is stored.
+    /// Traceback pops blocks without removing them, so this still works afterwards.
+    pub fn get(&self, pos: Pos) -> Option<Cost> {
+        self.blocks
+            .iter()
+            .find(|block| block.i_range.1 == pos.0)?
+            .get(pos.1)
+    }
+
+    /// Materialize the values of all stored blocks as a `g[i][j]` matrix, for
+    /// comparing against a naive DP fill in tests.
+    ///
+    /// Only the columns at the end of each block are stored, so only those are `Some`.
+    /// With `trace` enabled and `sparse` disabled, each column is its own block.
+    /// Within a column, rows in the rounded-out `j_range` (capped at `target.1`) are returned.
+    /// Traceback pops blocks without removing them, so this also includes
+    /// blocks that were popped since.
+    #[cfg(any(test, debug_assertions))]
+    pub fn g_matrix(&self, target: Pos) -> Vec<Vec<Option<Cost>>> {
+        let mut g = vec![vec![None; target.1 as usize + 1]; target.0 as usize + 1];
+        for block in &self.blocks {
+            let i = block.i_range.1;
+            if i < 0 || i > target.0 {
+                continue;
+            }
+            for j in block.j_range.0..=min(block.j_range.1, target.1) {
+                g[i as usize][j as usize] = Some(block.index(j));
+            }
+        }
+        g
+    }
+
+    /// The parent of `st` in a shortest path tree over the computed blocks,
+    /// used by the visualizer to draw the tree.
+    ///
+    /// This requires `trace` without `sparse` blocks, so that every column is
+    /// stored. Traceback only pops blocks, so they can still be used afterwards.
+    /// Returns `None` for the start and for states outside the computed area.
+    pub fn tree_parent(&self, st: State) -> Option<(State, [Option<AffineCigarOp>; 2])> {
+        assert!(self.trace && !self.params.sparse);
+        let get = |i: I, j: I| {
+            self.blocks
+                .get(i as usize)
+                .filter(|block| block.i_range.1 == i)?
+                .get(j)
+        };
+        let (i, j) = (st.i, st.j);
+        let g = get(i, j)?;
+        let parent = |i, j, op| Some((State::new(i, j, None), [Some(op), None]));
+        if i > 0 && j > 0 {
+            let (op, cost) = if BitProfile::is_match(&self.a, &self.b, i - 1, j - 1) {
+                (AffineCigarOp::Match, 0)
+            } else {
+                (AffineCigarOp::Sub, 1)
+            };
+            if get(i - 1, j - 1) == Some(g - cost) {
+                return parent(i - 1, j - 1, op);
+            }
+        }
+        if i > 0 && get(i - 1, j) == Some(g - 1) {
+            return parent(i - 1, j, AffineCigarOp::Del);
+        }
+        if j > 0 && get(i, j - 1) == Some(g - 1) {
+            return parent(i, j - 1, AffineCigarOp::Ins);
+        }
+        None
+    }
+
+    /// The `j_range` of the next block, when it was computed in a previous iteration.
     pub fn next_block_j_range(&self) -> Option<JRange> {
         self.blocks.get(self.last_block_idx + 1).map(|f| *f.j_range)
     }
 
-    // Update the fixed range, and make sure it only grows.
+    /// Update the range of rows of the last block whose value is known to be
+    /// optimal, and make sure it only grows. This enables incremental doubling
+    /// for the next block.
     pub fn set_last_block_fixed_j_range(&mut self, fixed_j_range: Option<JRange>) {
         if let Some(old) = self.blocks[self.last_block_idx].fixed_j_range
             && let Some(new) = fixed_j_range
@@ -628,7 +796,7 @@ impl Blocks {
 
         // 3.
         if self.params.simd {
-            pa_bitpacking::simd::fill::<2, H, 4>(
+            pa_bitpacking::fill::<H>(
                 &self.a[i_range.0 as usize..i_range.1 as usize],
                 &self.b[v_range],
                 h,
@@ -708,21 +876,22 @@ fn compute_block(
         }
 
         stats.computed_lanes += v_range.len();
+        stats.computed_states += v_range.len() * W * i_range.len() as usize;
         stats.num_incremental_blocks += 1;
     }
 
     let run = |h: &mut [H], exact_end| {
         let a = &a[i_range.0 as usize..i_range.1 as usize];
         let b = &b[v_range];
+        #[cfg(not(feature = "no_simd"))]
         if params.simd {
-            if params.no_ilp {
+            return if params.no_ilp {
                 pa_bitpacking::simd::compute::<1, H, 4>(a, b, h, v, exact_end) as I
             } else {
                 pa_bitpacking::simd::compute::<2, H, 4>(a, b, h, v, exact_end) as I
-            }
-        } else {
-            pa_bitpacking::scalar::row::<BitProfile, H>(a, b, h, v) as I
+            };
         }
+        pa_bitpacking::scalar::row::<BitProfile, H>(a, b, h, v) as I
     };
     let i_slice = i_range.0 as usize..i_range.1 as usize;
 
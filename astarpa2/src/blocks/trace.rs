@@ -18,6 +18,9 @@ impl Blocks {
     ///
     /// This requires `self.trace` to be `true`. In case of sparse blocks, this
     /// recomputes blocks when needed (when dt-trace fails).
+    ///
+    /// After `init_with_boundary`, the path ends where it first reaches the first
+    /// column, instead of at `from`.
     pub fn trace(
         &mut self,
         a: Seq,
@@ -40,14 +43,21 @@ impl Blocks {
         // Some allocated memory that can be reused.
         let dt_cache = &mut vec![BlockElem::default(); (self.params.max_g + 1).pow(2) as usize];
 
-        while to != from {
+        while to != from && !(self.boundary && to.0 == 0) {
             // Remove blocks to the right of `to`.
             while self.last_block_idx > 0 && self.blocks[self.last_block_idx].i_range.0 >= to.0 {
                 self.pop_last_block();
             }
 
             // Try a Diagonal Transition based traceback first which should be faster for small distances.
-            if self.params.dt_trace && to.0 > 0 {
+            // It does not follow the traceback policy, so is only used for the default one.
+            // It also assumes the first column starts at 0, so is not used with a boundary.
+            if self.params.dt_trace
+                && self.params.traceback == TracebackPolicy::Diagonal
+                && self.params.indel_tie_break == IndelTieBreak::Ins
+                && !self.boundary
+                && to.0 > 0
+            {
                 let prev_block = &self.blocks[self.last_block_idx - 1];
                 if prev_block.i_range.1 < to.0 - 1 {
                     stats.dt_trace_tries += 1;
@@ -128,7 +138,11 @@ impl Blocks {
             to = parent;
             cigar.push_elem(cigar_elem);
         }
-        assert_eq!(g, 0);
+        if self.boundary {
+            assert_eq!(g, self.blocks[0].index(to.1));
+        } else {
+            assert_eq!(g, 0);
+        }
         cigar.reverse();
 
         (cigar, stats)
@@ -142,6 +156,9 @@ impl Blocks {
     /// computed cells reached this way have the same score). But note that this
     /// may end up outside the computed area. In that case we use insertions or
     /// deletions as needed to get back.
+    ///
+    /// The order in which the other parents are tried depends on the `TracebackPolicy`,
+    /// with insertions and deletions swapped when `IndelTieBreak::Del` is set.
     fn parent(&self, mut st: Pos, g: &mut Cost) -> (Pos, CigarElem) {
         let block = &self.blocks[self.last_block_idx];
         assert!(
@@ -151,77 +168,94 @@ impl Blocks {
         );
 
         // Greedy matching.
-        let mut cnt = 0;
-        // TODO: SIMD using raw A and B.
-        while st.0 > 0 && st.1 > 0 && BitProfile::is_match(&self.a, &self.b, st.0 - 1, st.1 - 1) {
-            cnt += 1;
-            st.0 -= 1;
-            st.1 -= 1;
-        }
-        if cnt > 0 {
-            return (
-                st,
-                CigarElem {
-                    op: CigarOp::Match,
-                    cnt,
-                },
-            );
-        }
-
-        // Vertical delta (insert).
-        // (This is first since it only needs a single delta bit, instead of an index() call.)
-        let vd = block.get_diff(st.1 - 1);
-        if vd == Some(1) {
-            *g -= 1;
-            return (
-                Pos(st.0, st.1 - 1),
-                CigarElem {
-                    op: CigarOp::Ins,
-                    cnt: 1,
-                },
-            );
+        // With `RightAlign`, indels are preferred, so matches are taken one at a time below.
+        if self.params.traceback != TracebackPolicy::RightAlign {
+            let mut cnt = 0;
+            // TODO: SIMD using raw A and B.
+            while st.0 > 0 && st.1 > 0 && BitProfile::is_match(&self.a, &self.b, st.0 - 1, st.1 - 1)
+            {
+                cnt += 1;
+                st.0 -= 1;
+                st.1 -= 1;
+            }
+            if cnt > 0 {
+                return (
+                    st,
+                    CigarElem {
+                        op: CigarOp::Match,
+                        cnt,
+                    },
+                );
+            }
         }
 
-        let prev_block = &self.blocks[self.last_block_idx - 1];
-        assert!(prev_block.i_range.1 == st.0 - 1);
+        let prev_block = || {
+            let prev_block = &self.blocks[self.last_block_idx - 1];
+            assert!(prev_block.i_range.1 == st.0 - 1);
+            prev_block
+        };
 
         // Horizontal delta (delete).
         // Edge case: if we are above the start of the previous block (because of greedy matching),
         // always go left, since there must have been a lower path as well.
-        let hd = if st.1 < prev_block.j_range.0 {
-            1
-        } else {
-            *g - prev_block.index(st.1)
+        // `RightAlign` does not match greedily, so there this is not a deletion.
+        let hd = |g: Cost| {
+            let prev_block = prev_block();
+            if st.1 < prev_block.j_range.0 {
+                (self.params.traceback != TracebackPolicy::RightAlign).then_some(1)
+            } else {
+                Some(g - prev_block.index(st.1))
+            }
         };
-        if hd == 1 {
-            *g -= 1;
-            return (
-                Pos(st.0 - 1, st.1),
-                CigarElem {
-                    op: CigarOp::Del,
-                    cnt: 1,
-                },
-            );
-        }
 
-        // Diagonal delta (substitution).
-        // This edge case happens when entering the previous block exactly in
-        // the bottom-most row, where no vertical delta is available.
-        let dd = if st.1 > prev_block.j_range.1 {
-            assert_eq!(st.1, prev_block.j_range.1 + 1);
-            1
-        } else {
-            prev_block.get_diff(st.1 - 1).unwrap() + hd
+        use CigarOp::*;
+        let order: &[CigarOp] = match self.params.traceback {
+            // Insert is first since it only needs a single delta bit, instead of an index() call.
+            TracebackPolicy::Diagonal => &[Ins, Del, Sub],
+            TracebackPolicy::LeftAlign => &[Sub, Ins, Del],
+            TracebackPolicy::RightAlign => &[Ins, Del, Match, Sub],
         };
-        if dd == 1 {
-            *g -= 1;
-            return (
-                Pos(st.0 - 1, st.1 - 1),
-                CigarElem {
-                    op: CigarOp::Sub,
-                    cnt: 1,
-                },
-            );
+        for &op in order {
+            let op = match (self.params.indel_tie_break, op) {
+                (IndelTieBreak::Del, Ins) => Del,
+                (IndelTieBreak::Del, Del) => Ins,
+                (_, op) => op,
+            };
+            let (parent, valid) = match op {
+                Match => (
+                    Pos(st.0 - 1, st.1 - 1),
+                    st.0 > 0
+                        && st.1 > 0
+                        && BitProfile::is_match(&self.a, &self.b, st.0 - 1, st.1 - 1),
+                ),
+                // Vertical delta (insert).
+                Ins => (Pos(st.0, st.1 - 1), block.get_diff(st.1 - 1) == Some(1)),
+                Del => (Pos(st.0 - 1, st.1), st.0 > 0 && hd(*g) == Some(1)),
+                // Diagonal delta (substitution).
+                // This edge case happens when entering the previous block exactly in
+                // the bottom-most row, where no vertical delta is available.
+                Sub => (
+                    Pos(st.0 - 1, st.1 - 1),
+                    st.0 > 0 && st.1 > 0 && {
+                        let prev_block = prev_block();
+                        if st.1 > prev_block.j_range.1 {
+                            assert_eq!(st.1, prev_block.j_range.1 + 1);
+                            *g - prev_block.index(st.1 - 1) == 1
+                        } else {
+                            prev_block
+                                .get_diff(st.1 - 1)
+                                .zip(hd(*g))
+                                .is_some_and(|(vd, hd)| vd + hd == 1)
+                        }
+                    },
+                ),
+            };
+            if valid {
+                if op != Match {
+                    *g -= 1;
+                }
+                return (parent, CigarElem { op, cnt: 1 });
+            }
         }
 
         panic!("ERROR: PARENT OF {st:?} NOT FOUND IN TRACEBACK");
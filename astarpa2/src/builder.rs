@@ -0,0 +1,100 @@
+//! A builder for `AstarPa2` with the defaults of A*PA2-full.
+use super::*;
+use pa_heuristic::{DefaultCSH, MatchConfig, Pruning, GCSH};
+use pa_vis::NoVis;
+
+/// Builder for `AstarPa2`, created by `AstarPa2::builder()`.
+///
+/// Starts from the A*PA2-full parameters: no visualizer, the gap-chaining seed
+/// heuristic with exact 12-mers, band doubling, and pruning.
+/// ```
+/// use astarpa2::AstarPa2;
+/// use pa_heuristic::{MatchConfig, Pruning, GCSH};
+///
+/// let aligner = AstarPa2::builder()
+///     .heuristic(GCSH::new(MatchConfig::exact(10), Pruning::both()))
+///     .block_width(64)
+///     .build();
+/// let (cost, cigar) = aligner.align(b"ACGTACGTACGT", b"ACGTCGTACGT");
+/// assert_eq!(cost, 1);
+/// assert!(cigar.is_some());
+/// ```
+#[derive(Debug)]
+pub struct AstarPa2Builder<V: VisualizerT, H: Heuristic> {
+    params: AstarPa2<V, H>,
+}
+
+impl AstarPa2<NoVis, DefaultCSH> {
+    pub fn builder() -> AstarPa2Builder<NoVis, DefaultCSH> {
+        let params = AstarPa2Params::full();
+        AstarPa2Builder {
+            params: AstarPa2 {
+                domain: Domain::Astar(GCSH::new(MatchConfig::exact(12), Pruning::start())),
+                doubling: params.doubling,
+                block_width: params.block_width,
+                v: NoVis,
+                block: params.front,
+                trace: true,
+                sparse_h: params.sparse_h,
+                prune: params.prune,
+            },
+        }
+    }
+}
+
+impl<V: VisualizerT, H: Heuristic> AstarPa2Builder<V, H> {
+    /// Use A* with the given heuristic.
+    pub fn heuristic<H2: Heuristic>(self, h: H2) -> AstarPa2Builder<V, H2> {
+        self.domain(Domain::Astar(h))
+    }
+
+    /// Compute the given domain, e.g. `Domain::gap_gap()`.
+    pub fn domain<H2: Heuristic>(self, domain: Domain<H2>) -> AstarPa2Builder<V, H2> {
+        AstarPa2Builder {
+            params: AstarPa2 {
+                domain,
+                ..self.params
+            },
+        }
+    }
+
+    pub fn visualizer<V2: VisualizerT>(self, v: V2) -> AstarPa2Builder<V2, H> {
+        AstarPa2Builder {
+            params: AstarPa2 { v, ..self.params },
+        }
+    }
+
+    pub fn doubling(mut self, doubling: DoublingType) -> Self {
+        self.params.doubling = doubling;
+        self
+    }
+
+    pub fn block_width(mut self, block_width: I) -> Self {
+        self.params.block_width = block_width;
+        self
+    }
+
+    pub fn block(mut self, block: BlockParams) -> Self {
+        self.params.block = block;
+        self
+    }
+
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.params.trace = trace;
+        self
+    }
+
+    pub fn sparse_h(mut self, sparse_h: bool) -> Self {
+        self.params.sparse_h = sparse_h;
+        self
+    }
+
+    pub fn prune(mut self, prune: bool) -> Self {
+        self.params.prune = prune;
+        self
+    }
+
+    pub fn build(self) -> AstarPa2<V, H> {
+        self.params
+    }
+}
@@ -0,0 +1,114 @@
+//! Coarse checkpoints for band doubling.
+//!
+//! After each band doubling iteration that does not find the final alignment,
+//! its threshold `f_max` is written to disk. A restarted run can then skip all
+//! iterations up to that threshold, since they are known to fail.
+//! The `Blocks` themselves are not stored, so the first resumed iteration
+//! recomputes them from scratch.
+use pa_types::{Cost, Seq};
+use std::cell::Cell;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+/// Bump this whenever the format changes.
+const CHECKPOINT_VERSION: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Checkpoint {
+    pub a_len: usize,
+    pub b_len: usize,
+    /// The offset of the exponential search.
+    pub start_f: Cost,
+    /// The largest `f_max` for which the search is known to fail.
+    pub last_f: Cost,
+}
+
+impl Checkpoint {
+    pub fn new(a: Seq, b: Seq, start_f: Cost, last_f: Cost) -> Self {
+        Self {
+            a_len: a.len(),
+            b_len: b.len(),
+            start_f,
+            last_f,
+        }
+    }
+
+    /// Whether this checkpoint was written for the same input and search parameters.
+    pub fn matches(&self, a: Seq, b: Seq, start_f: Cost) -> bool {
+        self.a_len == a.len() && self.b_len == b.len() && self.start_f == start_f
+    }
+
+    /// Read a checkpoint. Returns `Ok(None)` when the file does not exist.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let s = match std::fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = || Error::new(ErrorKind::InvalidData, "Bad A*PA2 checkpoint file");
+        let mut lines = s.lines();
+        if lines.next() != Some(&format!("astarpa2-checkpoint {CHECKPOINT_VERSION}")) {
+            return Err(invalid());
+        }
+        let v = lines
+            .next()
+            .ok_or_else(invalid)?
+            .split_whitespace()
+            .map(|x| x.parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        let &[a_len, b_len, start_f, last_f] = &v[..] else {
+            return Err(invalid());
+        };
+        Ok(Some(Self {
+            a_len: a_len as usize,
+            b_len: b_len as usize,
+            start_f: start_f as Cost,
+            last_f: last_f as Cost,
+        }))
+    }
+
+    /// Write the checkpoint to a temporary file first, so that a crash never
+    /// leaves a partially written checkpoint behind.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(
+            &tmp,
+            format!(
+                "astarpa2-checkpoint {CHECKPOINT_VERSION}\n{} {} {} {}\n",
+                self.a_len, self.b_len, self.start_f, self.last_f
+            ),
+        )?;
+        std::fs::rename(tmp, path)
+    }
+}
+
+/// The checkpoint file of one alignment.
+/// Failed writes are kept in `error`, so they can be returned once the search stops.
+pub(crate) struct CheckpointFile<'p> {
+    pub path: &'p Path,
+    /// The checkpoint in `path` before the alignment started, if any.
+    pub prev: Option<Checkpoint>,
+    pub error: Cell<Option<Error>>,
+}
+
+impl<'p> CheckpointFile<'p> {
+    /// Fails when `path` exists but cannot be read as a checkpoint.
+    pub fn open(path: &'p Path) -> Result<Self> {
+        Ok(Self {
+            path,
+            prev: Checkpoint::read(path)?,
+            error: Cell::new(None),
+        })
+    }
+
+    /// Returns `false` when writing failed.
+    pub fn write(&self, checkpoint: Checkpoint) -> bool {
+        match checkpoint.write(self.path) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error.set(Some(e));
+                false
+            }
+        }
+    }
+}
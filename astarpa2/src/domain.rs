@@ -17,7 +17,10 @@ mod local_doubling;
 use self::blocks::{trace::TraceStats, BlockStats};
 
 use super::*;
-use crate::{block::Block, blocks::Blocks};
+use crate::{
+    block::Block,
+    blocks::{Blocks, PA},
+};
 use pa_affine_types::AffineCost;
 use pa_heuristic::*;
 use pa_types::*;
@@ -34,14 +37,56 @@ pub struct AstarPa2Stats {
     pub trace_stats: TraceStats,
 
     pub f_max_tries: usize,
-
+    /// The value of the heuristic at the start, or 0 without a heuristic.
+    pub h0: Cost,
+    /// The maximal number of rows in the `j_range` of any block, over all tries.
+    pub max_band_width: I,
+    /// True when the alignment was stopped early by a `CancelToken`.
+    pub cancelled: bool,
+    /// True when the alignment was stopped because it exceeded `max_computed_states`.
+    pub budget_exceeded: bool,
+    /// With `collect_band_trace`, the `(i, j_range, fixed_j_range)` of each
+    /// computed column `i`, where `i` is the last column of its block.
+    /// Each try starts again at column 0.
+    #[serde(skip)]
+    pub band_trace: Vec<(I, JRange, Option<JRange>)>,
+
+    /// Time spent building the heuristic.
     pub t_precomp: Duration,
+    /// Time spent in `align_for_bounded_dist`, excluding the traceback.
+    pub t_search: Duration,
+    /// Time spent tracing back the alignment.
+    pub t_trace: Duration,
     pub t_j_range: Duration,
     pub t_fixed_j_range: Duration,
     pub t_pruning: Duration,
     pub t_contours_update: Duration,
 }
 
+impl AstarPa2Stats {
+    /// Column headers and values for the number of tries, the maximal band
+    /// width, and the time in ms spent on building the heuristic, the search,
+    /// and the traceback.
+    pub fn values(&self) -> (Vec<String>, Vec<String>) {
+        let ms = |t: Duration| format!("{:>8.2}", 1000. * t.as_secs_f64());
+        [
+            (
+                format!("{:>5}", "tries"),
+                format!("{:>5}", self.f_max_tries),
+            ),
+            (
+                format!("{:>7}", "band"),
+                format!("{:>7}", self.max_band_width),
+            ),
+            (format!("{:>8}", "pre"), ms(self.t_precomp)),
+            (format!("{:>8}", "search"), ms(self.t_search)),
+            (format!("{:>8}", "trace"), ms(self.t_trace)),
+        ]
+        .into_iter()
+        .unzip()
+    }
+}
+
 pub struct AstarPa2Instance<'a, V: VisualizerT, H: Heuristic> {
     // NOTE: `a` and `b` are padded sequences and hence owned.
     pub a: Seq<'a>,
@@ -58,6 +103,27 @@ pub struct AstarPa2Instance<'a, V: VisualizerT, H: Heuristic> {
     /// The instantiated visualizer to use.
     pub v: V::Instance,
 
+    /// Checked once per block; when cancelled, `align_for_bounded_dist` returns `None`.
+    /// Owned by the caller, and never cancelled by the aligner itself.
+    pub cancel: Option<CancelToken>,
+
+    /// Cancelled by the aligner to stop band doubling, e.g. when
+    /// `max_computed_states` is exceeded. Not shared with the caller.
+    pub stop: CancelToken,
+
+    /// When the number of computed states exceeds this, `stop` is cancelled
+    /// and `align_for_bounded_dist` returns `None`.
+    pub max_computed_states: Option<usize>,
+
+    /// Whether pruning is enabled. Defaults to `params.prune`.
+    pub prune: bool,
+
+    /// Whether to record the ranges of each block in `stats.band_trace`.
+    pub collect_band_trace: bool,
+
+    /// The profile of `a` when aligning to a `Reference`, so that it is not built again.
+    pub a_profile: Option<&'a [PA]>,
+
     pub stats: AstarPa2Stats,
 }
 
@@ -91,24 +157,22 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         // Inclusive end column of the new block.
         let ie = i_range.1;
 
-        let unit_cost = AffineCost::unit();
+        // Blocks compute unit costs.
+        let cm = AffineCost::unit();
 
         let mut range = match &self.domain {
             Full => JRange(0, self.b.len() as I),
             GapStart => {
                 // range: the max number of diagonals we can move up/down from the start with cost f.
                 JRange(
-                    is + 1 + -(unit_cost.max_del_for_cost(f_max) as I),
-                    ie + unit_cost.max_ins_for_cost(f_max) as I,
+                    is + 1 + -(cm.max_del_for_cost(f_max) as I),
+                    ie + cm.max_ins_for_cost(f_max) as I,
                 )
             }
             GapGap => {
                 let d = self.b.len() as I - self.a.len() as I;
-                // We subtract the cost needed to bridge the gap from the start to the end.
-                let s = f_max - unit_cost.gap_cost(Pos(0, 0), Pos::target(&self.a, &self.b));
-                // Each extra diagonal costs one insertion and one deletion.
-                let extra_diagonals = s / (unit_cost.min_ins_extend + unit_cost.min_del_extend);
-                // NOTE: The range could be reduced slightly further by considering gap open costs.
+                let extra_diagonals =
+                    cm.extra_diagonals_for_cost(f_max, Pos::target(&self.a, &self.b));
                 JRange(
                     is + 1 + min(d, 0) - extra_diagonals as I,
                     ie + max(d, 0) + extra_diagonals as I,
@@ -126,11 +190,6 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 let JRange(fixed_start, fixed_end) = prev
                     .fixed_j_range
                     .expect("With A* Domain, fixed_j_range should always be set.");
-                if DEBUG {
-                    eprintln!("j_range for   {i_range:?}");
-                    eprintln!("\told j_range {old_range:?}");
-                    eprintln!("\told fixed   {:?} @ {is}", prev.fixed_j_range.unwrap());
-                }
                 assert!(fixed_start <= fixed_end, "Fixed range must not be empty");
 
                 // The start of the j_range we will compute for this block is the `fixed_start` of the previous column.
@@ -152,7 +211,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 // A lower bound of `f` values estimated from `gu`, valid for states `v` below the diagonal of `u`.
                 let mut f = |v: Pos| {
                     assert!(v.1 - u.1 >= v.0 - u.0);
-                    let f = gu + unit_cost.extend_cost(u, v) + h(v);
+                    let f = gu + cm.extend_cost(u, v) + h(v);
                     self.v.f_call(v, f <= f_max, false);
                     f
                 };
@@ -209,7 +268,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                         } else {
                             // By consistency of `f`, it can only change value by at most `2` per step in the unit cost setting.
                             // When `f(v) > f_max`, this means we have to make at least `ceil((fv - f_max)/2)` steps to possibly get at a cell with `f(v) <= f_max`.
-                            v.0 += (fv - f_max).div_ceil(2 * unit_cost.min_del_extend);
+                            v.0 += (fv - f_max).div_ceil(2 * cm.min_del_extend);
                             if v.0 > ie {
                                 v.0 = ie;
                                 break;
@@ -227,7 +286,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                         if fv <= f_max {
                             break;
                         } else {
-                            v.1 -= (fv - f_max).div_ceil(2 * unit_cost.min_ins_extend);
+                            v.1 -= (fv - f_max).div_ceil(2 * cm.min_ins_extend);
                         }
                     }
                 }
@@ -301,7 +360,8 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         let mut start = prev_fixed_j_range.0;
         let mut end = block.original_j_range.1.min(self.b.len() as I);
 
-        let unit_cost = AffineCost::unit();
+        // Blocks compute unit costs.
+        let cm = AffineCost::unit();
 
         while start <= end {
             let f = f(start);
@@ -309,7 +369,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 break;
             }
             start += if self.params.sparse_h {
-                (f - f_max).div_ceil(2 * unit_cost.min_ins_extend)
+                (f - f_max).div_ceil(2 * cm.min_ins_extend)
             } else {
                 1
             };
@@ -321,16 +381,12 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 break;
             }
             end -= if self.params.sparse_h {
-                (f - f_max).div_ceil(2 * unit_cost.min_ins_extend)
+                (f - f_max).div_ceil(2 * cm.min_ins_extend)
             } else {
                 1
             };
         }
         let mut fixed_j_range = JRange(start, end);
-        if DEBUG {
-            eprintln!("initial fixed_j_range for {i} {fixed_j_range:?}");
-            eprintln!("old     fixed_j_range for {i} {:?}", block.fixed_j_range);
-        }
         if let Some(old_fixed_j_range) = block.fixed_j_range {
             if fixed_j_range.is_empty() {
                 fixed_j_range = old_fixed_j_range;
@@ -338,9 +394,6 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 fixed_j_range = fixed_j_range.union(old_fixed_j_range);
             }
         }
-        if DEBUG {
-            eprintln!("updated fixed_j_range for {i} {fixed_j_range:?}");
-        }
 
         if !fixed_j_range.is_empty() {
             self.v
@@ -349,6 +402,24 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         Some(fixed_j_range)
     }
 
+    /// New `Blocks` for `a` and `b`, reusing `a_profile` when set.
+    pub fn new_blocks(&self, trace: bool) -> Blocks {
+        match self.a_profile {
+            Some(pa) => {
+                let pb = BitProfile::build(&[], self.b).1;
+                self.params.block.new_with_profile(trace, pa.to_vec(), pb)
+            }
+            None => self.params.block.new(trace, self.a, self.b),
+        }
+    }
+
+    /// Record the ranges of the block ending in column `i` when `collect_band_trace` is set.
+    fn record_band(&mut self, i: I, j_range: JRange, fixed_j_range: Option<JRange>) {
+        if self.collect_band_trace {
+            self.stats.band_trace.push((i, j_range, fixed_j_range));
+        }
+    }
+
     /// Test whether the cost is at most s.
     /// Returns None if no path was found.
     /// It may happen that a path is found, but the cost is larger than s.
@@ -358,28 +429,52 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         f_max: Option<Cost>,
         trace: bool,
         blocks: Option<&mut Blocks>,
+    ) -> Option<(Cost, Option<Cigar>)> {
+        let start = std::time::Instant::now();
+        let t_trace = self.stats.t_trace;
+        let r = self.search_for_bounded_dist(f_max, trace, blocks);
+        self.stats.t_search += start.elapsed() - (self.stats.t_trace - t_trace);
+        r
+    }
+
+    /// Query the state at `pos` after `align_for_bounded_dist` computed `blocks`.
+    /// Returns `(g, h, f)`, where `f = g + h`.
+    ///
+    /// `g` is the value stored in `blocks`, or `None` when it was not computed (see `Blocks::get`).
+    /// It is exact in the fixed range of each block, and an upper bound elsewhere.
+    /// `h` is the current, possibly pruned, heuristic: that of the A* domain,
+    /// the gap cost to the end for `GapGap`, and `0` otherwise.
+    pub fn query(&self, blocks: &Blocks, pos: Pos) -> (Option<Cost>, Cost, Option<Cost>) {
+        let g = blocks.get(pos);
+        let h = match &self.domain {
+            Astar(h) => h.h(pos),
+            GapGap => AffineCost::unit().gap_cost(pos, Pos::target(self.a, self.b)),
+            Full | GapStart => 0,
+        };
+        (g, h, g.map(|g| g + h))
+    }
+
+    /// The implementation of `align_for_bounded_dist`, without the timing of the search.
+    fn search_for_bounded_dist(
+        &mut self,
+        f_max: Option<Cost>,
+        trace: bool,
+        blocks: Option<&mut Blocks>,
     ) -> Option<(Cost, Option<Cigar>)> {
         self.stats.f_max_tries += 1;
 
         // Update contours for any pending prunes.
-        if self.params.prune
+        if self.prune
             && let Astar(h) = &mut self.domain
         {
             let start = std::time::Instant::now();
             h.update_contours(Pos(0, 0));
             self.stats.t_contours_update += start.elapsed();
-            if DEBUG {
-                eprintln!("\nTEST DIST {} h0 {}\n", f_max.unwrap_or(0), h.h(Pos(0, 0)));
-            }
-        } else {
-            if DEBUG {
-                eprintln!("\nTEST DIST {}\n", f_max.unwrap_or(0));
-            }
         }
 
         // Make a local block variable if not passed in.
         let mut local_blocks = if blocks.is_none() {
-            Some(self.params.block.new(trace, self.a, self.b))
+            Some(self.new_blocks(trace))
         } else {
             None
         };
@@ -411,6 +506,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
 
         blocks.init(initial_j_range);
         blocks.set_last_block_fixed_j_range(Some(initial_j_range));
+        self.record_band(0, initial_j_range, Some(initial_j_range));
 
         self.v.expand_block(
             Pos(-1, 0),
@@ -426,6 +522,20 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         let mut all_blocks_reused = true;
 
         for i in (0..self.a.len() as I).step_by(self.params.block_width as _) {
+            if let Some(cancel) = &self.cancel
+                && cancel.is_cancelled()
+            {
+                self.stats.cancelled = true;
+                return None;
+            }
+            if let Some(max_computed_states) = self.max_computed_states
+                && blocks.stats.computed_states > max_computed_states
+            {
+                self.stats.budget_exceeded = true;
+                self.stop.cancel();
+                return None;
+            }
+
             // The i_range of the new block.
             let i_range = IRange(i, min(i + self.params.block_width, self.a.len() as I));
             // The j_range of the new block.
@@ -445,6 +555,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 self.v.new_layer(self.domain.h());
                 return None;
             }
+            self.stats.max_band_width = max(self.stats.max_band_width, j_range.len());
 
             // If the new `j_range` is the same as the old one, and all previous
             // blocks were reused, we can also reuse this new block.
@@ -470,6 +581,10 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 blocks.reuse_next_block(i_range, j_range);
             } else {
                 blocks.compute_next_block(i_range, j_range, &mut self.v);
+                if !V::IS_DEFAULT {
+                    self.v
+                        .extend_block(&match_runs(self.a, self.b, i_range, j_range));
+                }
                 if self.params.doubling == DoublingType::None {
                     self.v.new_layer(self.domain.h());
                 }
@@ -478,12 +593,10 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
             // Compute the new range of fixed states.
             let next_fixed_j_range =
                 self.fixed_j_range(i_range.1, f_max, prev_fixed_j_range, blocks.last_block());
+            self.record_band(i_range.1, j_range, next_fixed_j_range);
 
             // If there are no fixed states, break.
             if next_fixed_j_range.is_some_and(|r| r.is_empty()) {
-                if DEBUG {
-                    eprintln!("fixed_j_range is empty! Increasing f_max!");
-                }
                 self.v.new_layer(self.domain.h());
                 return None;
             }
@@ -502,7 +615,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
             }
 
             // Prune matches in the intersection of the previous and next fixed range.
-            if self.params.prune
+            if self.prune
                 && let Astar(h) = &mut self.domain
             {
                 let start = std::time::Instant::now();
@@ -523,6 +636,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
 
         // If dist is at most the assumed bound, do a traceback.
         if trace && dist <= f_max.unwrap_or(I::MAX) {
+            let start = std::time::Instant::now();
             let (cigar, trace_stats) = blocks.trace(
                 self.a,
                 self.b,
@@ -531,6 +645,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 &mut self.v,
             );
             self.stats.trace_stats = trace_stats;
+            self.stats.t_trace += start.elapsed();
             Some((dist, Some(cigar)))
         } else {
             // NOTE: A distance is always returned, even if it is larger than
@@ -540,3 +655,23 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         }
     }
 }
+
+/// Diagonal runs of matches within the block of columns `i_range` and rows
+/// `j_range`, as `(start, end)` states, for the visualizer.
+fn match_runs(a: Seq, b: Seq, i_range: IRange, j_range: JRange) -> Vec<(Pos, Pos)> {
+    let is = max(i_range.0, 0) as usize..min(i_range.1, a.len() as I) as usize;
+    let js = max(j_range.0, 0) as usize..min(j_range.1, b.len() as I) as usize;
+    let mut runs = vec![];
+    for i in is.clone() {
+        for j in js.clone() {
+            // Only start a run where the previous cell in the block does not match.
+            if a[i] != b[j] || (i > is.start && j > js.start && a[i - 1] == b[j - 1]) {
+                continue;
+            }
+            let len = is.end.min(i + js.end - j) - i;
+            let len = (0..len).take_while(|&k| a[i + k] == b[j + k]).count();
+            runs.push((Pos(i as I, j as I), Pos((i + len) as I, (j + len) as I)));
+        }
+    }
+    runs
+}
@@ -9,7 +9,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
         // idx 0: i_range 0 .. 0
         // idx i: i_range (B-1)*i .. B*i
         // idx max: i_range (B-1)*max .. a.len()
-        let mut blocks = self.params.block.new(true, self.a, self.b);
+        let mut blocks = self.new_blocks(true);
 
         // Add the block for i_range 0..0
         {
@@ -21,6 +21,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
             );
             blocks.init(initial_j_range);
             blocks.set_last_block_fixed_j_range(Some(initial_j_range));
+            self.record_band(0, initial_j_range, Some(initial_j_range));
         }
 
         // Blocks have been computed up to this f.
@@ -109,33 +110,16 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
 
             // Grow previous block sizes as long as their f_max is not large enough.
             let mut start_idx = last_idx;
-            let mut last_grow = 0;
             while start_idx > 0 && f_max[start_idx - 1] < f_max[start_idx] {
                 start_idx -= 1;
 
                 let f_target = f_max[start_idx + 1];
-                let old_f = f_max[start_idx];
-                let old_delta = f_delta[start_idx];
                 grow_to(&mut f_max[start_idx], f_target, &mut f_delta[start_idx]);
-                if f_max[start_idx] > last_grow {
-                    if DEBUG {
-                        eprintln!(
-                            "Grow  block idx {start_idx:>5} to {:>6} by {:>6} for {old_delta:>5?} and shortage {:>6}",
-                            f_max[start_idx],
-                            f_max[start_idx] - old_f,
-                            f_target - old_f
-                        );
-                    }
-                    last_grow = f_max[start_idx];
-                }
 
                 blocks.pop_last_block();
             }
 
             if start_idx < last_idx {
-                if DEBUG {
-                    eprintln!("START block idx {start_idx:>5} to {:>6}", f_max[start_idx]);
-                }
                 let h = self.domain.h_mut().unwrap();
                 h.update_contours(Pos((start_idx as I - 1) * self.params.block_width, 0));
             }
@@ -149,6 +133,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                 );
                 blocks.init(initial_j_range);
                 blocks.set_last_block_fixed_j_range(Some(initial_j_range));
+                self.record_band(0, initial_j_range, Some(initial_j_range));
                 // eprintln!("Reset block idx 0 to {initial_j_range:?}");
 
                 start_idx += 1;
@@ -199,6 +184,7 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
                     blocks.last_block(),
                 );
                 // eprintln!("{i}: New fixed range {next_fixed_j_range:?}");
+                self.record_band(i_range.1, j_range, next_fixed_j_range);
                 blocks.set_last_block_fixed_j_range(next_fixed_j_range);
                 let next_fixed_j_range = blocks.last_block().fixed_j_range.unwrap();
 
@@ -220,16 +206,6 @@ impl<'a, V: VisualizerT, H: Heuristic> AstarPa2Instance<'a, V, H> {
             }
         } // end loop over i
 
-        if DEBUG {
-            let mut delta = 0;
-            for (idx, d) in f_delta.iter().enumerate() {
-                if delta != d.0 {
-                    delta = d.0;
-                    eprintln!("Delta {idx:>6} => {delta:>5}");
-                }
-            }
-        }
-
         // eprintln!("TRACE..");
         let dist = blocks.last_block().get(self.b.len() as I).unwrap();
         let (cigar, _stats) = blocks.trace(
@@ -9,24 +9,40 @@
 mod band;
 mod block;
 mod blocks;
+mod builder;
+mod checkpoint;
 mod domain;
 mod params;
 mod ranges;
+mod streaming;
 #[cfg(test)]
 mod tests;
+#[cfg(all(test, feature = "validate"))]
+mod validate;
 
 pub use band::{DoublingStart, DoublingType};
 use domain::AstarPa2Stats;
-use pa_bitpacking::W;
+pub use pa_bitpacking::ProfileError;
+use pa_bitpacking::{BitProfile, Profile, W};
 pub use params::*;
 
-pub use blocks::BlockParams;
-use pa_affine_types::AffineCigar;
-use pa_heuristic::{Heuristic, HeuristicInstance, NoCostI};
+pub use block::Block;
+pub use blocks::{BlockParams, BlockStats, Blocks, TracebackPolicy};
+pub use builder::AstarPa2Builder;
+pub use pa_affine_types::IndelTieBreak;
+use pa_affine_types::{AffineCigar, AffineCost, State};
+use pa_heuristic::{util::CancelToken, Heuristic, HeuristicInstance, NoCostI, SeedIndex};
 use pa_types::*;
-use pa_vis::{VisualizerInstance, VisualizerT};
+use pa_vis::{ParentFn, VisualizerInstance, VisualizerT};
 use ranges::*;
+pub use ranges::{IRange, JRange, RoundedInJRange, RoundedOutJRange};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+pub use streaming::{map_sequence, StreamingAligner};
 
+use crate::checkpoint::{Checkpoint, CheckpointFile};
 use crate::domain::AstarPa2Instance;
 
 const DEBUG: bool = false;
@@ -47,6 +63,10 @@ pub fn astarpa2_full(a: Seq, b: Seq) -> (Cost, Cigar) {
 }
 
 /// Typed parameters for A*PA2 containing heuristic and visualizer.
+///
+/// Aligning `(b, a)` gives the same cost as `(a, b)`: all domain bounds are
+/// symmetric in insertions and deletions. The returned cigars are optimal,
+/// but need not be each other's transpose when there are ties.
 #[derive(Debug)]
 pub struct AstarPa2<V: VisualizerT, H: Heuristic> {
     /// The domain to compute.
@@ -79,7 +99,18 @@ pub struct AstarPa2<V: VisualizerT, H: Heuristic> {
 
 impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
     pub fn build<'a>(&'a self, a: Seq<'a>, b: Seq<'a>) -> AstarPa2Instance<'a, V, H> {
+        self.build_with_reference(a, b, None)
+    }
+
+    /// As `build`, reusing the profile and seed index of `reference`, whose sequence is `a`.
+    fn build_with_reference<'a>(
+        &'a self,
+        a: Seq<'a>,
+        b: Seq<'a>,
+        reference: Option<&'a Reference>,
+    ) -> AstarPa2Instance<'a, V, H> {
         use Domain::*;
+        pa_heuristic::util::assert_input_len(a, b);
 
         // init V
         let v = self.v.build(a, b);
@@ -90,13 +121,10 @@ impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
             Full => Full,
             GapStart => GapStart,
             GapGap => GapGap,
-            Astar(h) => {
-                let h = h.build(a, b);
-                if DEBUG {
-                    eprintln!("h0: {}", h.h(Pos(0, 0)));
-                }
-                Astar(h)
-            }
+            Astar(h) => Astar(match reference.and_then(|r| r.seed_index.as_ref()) {
+                Some(index) => h.build_with_index(a, b, index),
+                None => h.build(a, b),
+            }),
         };
 
         AstarPa2Instance {
@@ -106,6 +134,12 @@ impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
             domain,
             hint: Default::default(),
             v,
+            cancel: None,
+            stop: CancelToken::new(),
+            max_computed_states: None,
+            prune: self.prune,
+            collect_band_trace: false,
+            a_profile: reference.map(|r| &r.profile[..]),
             stats: AstarPa2Stats {
                 t_precomp: start.elapsed(),
                 ..Default::default()
@@ -113,27 +147,82 @@ impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
         }
     }
 
-    fn cost_or_align(&self, a: Seq, b: Seq, trace: bool) -> (Cost, Option<Cigar>, AstarPa2Stats) {
-        let mut nw = self.build(a, b);
+    /// Returns `None` only when cancelled via `opts.cancel`, when
+    /// `opts.max_computed_states` is exceeded, or when the cost exceeds `opts.max_cost`.
+    fn cost_or_align(
+        &self,
+        a: Seq,
+        b: Seq,
+        trace: bool,
+        opts: AlignOptions,
+    ) -> Option<(Cost, Option<Cigar>, AstarPa2Stats)> {
+        let AlignOptions {
+            checkpoint,
+            warm,
+            cancel,
+            prune,
+            max_computed_states,
+            max_cost,
+            initial_bound,
+            collect_band_trace,
+            reference,
+        } = opts;
+        let within_max_cost = |cost: Cost| max_cost.is_none_or(|m| cost <= m);
+        if let Some((cost, cigar)) = trivial_alignment(a, b, trace) {
+            return within_max_cost(cost).then_some((cost, cigar, AstarPa2Stats::default()));
+        }
+        let mut nw = self.build_with_reference(a, b, reference);
+        nw.cancel = cancel.clone();
+        nw.max_computed_states = max_computed_states;
+        nw.prune = prune.unwrap_or(self.prune);
+        nw.collect_band_trace = collect_band_trace;
+        // The budget and maximum cost stop band doubling via the internal `stop`
+        // token, so that the caller's `cancel` token is never cancelled.
+        let stop = nw.stop.clone();
+        let is_cancelled =
+            || stop.is_cancelled() || cancel.as_ref().is_some_and(|c| c.is_cancelled());
+        let s_max = AffineCost::unit().max_possible_cost(a, b);
+        // Thresholds are capped at `max_cost`. When the capped threshold fails,
+        // the cost is known to be too large and the search is stopped.
+        let cap = |s: Cost| max_cost.map_or(s, |m| s.min(m));
+        let stop_above_max_cost = |s: Cost, r: &Option<(Cost, Option<Cigar>)>| {
+            if max_cost.is_some_and(|m| s >= m) && !matches!(r, Some((c, _)) if *c <= s) {
+                stop.cancel();
+            }
+        };
         let h0 = nw.domain.h().map_or(0, |h| h.h(Pos(0, 0)));
+        nw.stats.h0 = h0;
+        // The tree needs all columns, which are only stored for non-sparse traceback.
+        let draw_tree = trace && !self.block.sparse && !V::IS_DEFAULT;
+        // Kept outside the match, so the visualizer can draw a tree over the computed blocks.
+        // Without doubling, `align_for_bounded_dist` makes its own blocks when no tree is drawn.
+        let mut blocks = match self.doubling {
+            DoublingType::None if !draw_tree => None,
+            DoublingType::LocalDoubling => None,
+            _ => Some(nw.new_blocks(trace)),
+        };
         let (cost, cigar) = match self.doubling {
             DoublingType::None => {
                 // FIXME: Allow single-shot alignment with bounded dist.
                 assert!(matches!(self.domain, Domain::Full));
-                nw.align_for_bounded_dist(None, trace, None).unwrap()
+                nw.align_for_bounded_dist(None, trace, blocks.as_mut())?
             }
             DoublingType::LinearSearch { start, delta } => {
-                let start_f = start.initial_values(a, b, h0).0;
-                let mut blocks = self.block.new(trace, a, b);
-                band::linear_search(start_f, delta as Cost, |s| {
-                    nw.align_for_bounded_dist(Some(s), trace, Some(&mut blocks))
-                        .map(|x @ (c, _)| (c, x))
-                })
+                let mut start_f = start.initial_values(a, b, h0).0;
+                if let Some(bound) = initial_bound {
+                    start_f = start_f.max(bound);
+                }
+                band::linear_search("A*PA2", start_f, delta as Cost, s_max, is_cancelled, |s| {
+                    let s = cap(s);
+                    let r = nw.align_for_bounded_dist(Some(s), trace, blocks.as_mut());
+                    stop_above_max_cost(s, &r);
+                    r.map(|x @ (c, _)| (c, x))
+                })?
                 .1
             }
             DoublingType::BandDoubling { start, factor }
             | DoublingType::BandDoublingStartIncrement { start, factor, .. } => {
-                let (start_f, mut start_increment) = start.initial_values(a, b, h0);
+                let (mut start_f, mut start_increment) = start.initial_values(a, b, h0);
                 start_increment = start_increment.max(self.block_width as i32);
                 if let DoublingType::BandDoublingStartIncrement {
                     start_increment: si,
@@ -142,41 +231,422 @@ impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
                 {
                     start_increment = si;
                 }
-                let mut blocks = self.block.new(trace, a, b);
-                let r = band::exponential_search(start_f, start_increment, factor, |s| {
-                    nw.align_for_bounded_dist(Some(s), trace, Some(&mut blocks))
-                        .map(|x @ (c, _)| (c, x))
-                })
+                // Make the first iteration use the expected cost from the previous pair.
+                if let Some(w) = warm
+                    && let Some(expected) = w.expected_cost(a, b, h0)
+                {
+                    start_f = start_f.max(expected - start_increment);
+                }
+                if let Some(bound) = initial_bound {
+                    start_f = start_f.max(bound);
+                }
+                // Skip the iterations that are known to fail from an earlier run.
+                if let Some(file) = checkpoint
+                    && let Some(c) = file.prev
+                    && c.matches(a, b, start_f)
+                {
+                    start_increment =
+                        ((factor * (c.last_f - start_f) as f32).ceil() as Cost).max(1);
+                }
+                let r = band::exponential_search(
+                    "A*PA2",
+                    start_f,
+                    start_increment,
+                    factor,
+                    s_max,
+                    is_cancelled,
+                    |s| {
+                        let s = cap(s);
+                        let r = nw.align_for_bounded_dist(Some(s), trace, blocks.as_mut());
+                        stop_above_max_cost(s, &r);
+                        // A cancelled iteration is not known to fail.
+                        // When writing fails, stop, so that the error is returned
+                        // and the previous checkpoint can still be resumed from.
+                        if let Some(file) = checkpoint
+                            && !matches!(r, Some((c, _)) if c <= s)
+                            && !is_cancelled()
+                            && !file.write(Checkpoint::new(a, b, start_f, s))
+                        {
+                            stop.cancel();
+                        }
+                        r.map(|x @ (c, _)| (c, x))
+                    },
+                )?
                 .1;
-                nw.stats.block_stats = blocks.stats;
+                if let Some(file) = checkpoint {
+                    // The alignment is done, so the checkpoint is not needed anymore.
+                    std::fs::remove_file(file.path).ok();
+                }
+                nw.stats.block_stats = blocks.as_ref().unwrap().stats.clone();
                 r
             }
             // NOTE: This is not in the paper since it does not yet work much
             // better than (global) band doubling in practice.
             DoublingType::LocalDoubling => {
-                assert!(self.prune, "Local doubling requires pruning.");
+                assert!(nw.prune, "Local doubling requires pruning.");
                 let (cost, cigar) = nw.local_doubling();
                 (cost, Some(cigar))
             }
         };
+        if !within_max_cost(cost) {
+            return None;
+        }
+        let tree_parent = |st: State| blocks.as_ref().unwrap().tree_parent(st);
+        let parent: ParentFn = if draw_tree && blocks.is_some() {
+            Some(&tree_parent)
+        } else {
+            None
+        };
         nw.v.last_frame::<NoCostI>(
             cigar.as_ref().map(|c| AffineCigar::from(c)).as_ref(),
-            None,
+            parent,
             None,
         );
         assert!(h0 <= cost, "Heuristic at start {h0} > final cost {cost}.");
-        (cost, cigar, nw.stats)
+        Some((cost, cigar, nw.stats))
     }
 
     pub fn cost(&self, a: Seq, b: Seq) -> Cost {
-        self.cost_or_align(a, b, false).0
+        self.cost_or_align(a, b, false, AlignOptions::default())
+            .unwrap()
+            .0
     }
 
     pub fn align(&self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
-        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace);
+        let (cost, cigar, _stats) = self
+            .cost_or_align(a, b, self.trace, AlignOptions::default())
+            .unwrap();
         (cost, cigar)
     }
 
+    /// As `align`, but returns the first unsupported byte instead of panicking.
+    pub fn try_align(&self, a: Seq, b: Seq) -> Result<(Cost, Option<Cigar>), ProfileError> {
+        check_input(a, b)?;
+        Ok(self.align(a, b))
+    }
+
+    /// As `align`, but stops early and returns `None` once `cancel` is cancelled.
+    /// Cancellation is checked once per block and between band doubling iterations.
+    pub fn align_cancellable(
+        &self,
+        a: Seq,
+        b: Seq,
+        cancel: &CancelToken,
+    ) -> Option<(Cost, Option<Cigar>)> {
+        let opts = AlignOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace, opts)?;
+        Some((cost, cigar))
+    }
+
+    /// As `align`, but gives up with `BudgetExceeded` once more than
+    /// `max_computed_states` DP states have been computed, summed over all band
+    /// doubling iterations. Checked once per block.
+    pub fn align_with_budget(
+        &self,
+        a: Seq,
+        b: Seq,
+        max_computed_states: usize,
+    ) -> Result<(Cost, Option<Cigar>), BudgetExceeded> {
+        let opts = AlignOptions {
+            max_computed_states: Some(max_computed_states),
+            ..Default::default()
+        };
+        let err = BudgetExceeded {
+            max_computed_states,
+        };
+        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace, opts).ok_or(err)?;
+        Ok((cost, cigar))
+    }
+
+    /// As `align`, but gives up with `BelowThreshold` once the identity
+    /// `1 - cost / max(|a|, |b|)` is known to be below `min_identity`.
+    ///
+    /// Band doubling never tests thresholds above the largest allowed cost,
+    /// so poor matches are rejected after at most one extra iteration.
+    pub fn align_min_identity(
+        &self,
+        a: Seq,
+        b: Seq,
+        min_identity: f32,
+    ) -> Result<(Cost, Option<Cigar>), BelowThreshold> {
+        assert!(
+            (0.0..=1.0).contains(&min_identity),
+            "min_identity must be in [0, 1], not {min_identity}."
+        );
+        let max_cost = ((1.0 - min_identity) * a.len().max(b.len()) as f32).floor() as Cost;
+        let opts = AlignOptions {
+            max_cost: Some(max_cost),
+            ..Default::default()
+        };
+        let below = BelowThreshold {
+            min_identity,
+            max_cost,
+        };
+        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace, opts).ok_or(below)?;
+        Ok((cost, cigar))
+    }
+
+    /// As `align`, but reuses the seed index of the previous pair when it has
+    /// the same `a`, and uses the heuristic of this pair and how far the
+    /// heuristic of a previous, similar pair was from its cost as the initial
+    /// band doubling threshold, so that usually a single iteration suffices.
+    /// Returns the `WarmStart` to pass to the next pair.
+    ///
+    /// Falls back to a full build of the heuristic when `a` differs from the
+    /// previous pair, and to the default threshold when the lengths differ too
+    /// much. The result is exact regardless of `prev`.
+    pub fn align_warm(
+        &self,
+        a: Seq,
+        b: Seq,
+        prev: Option<&WarmStart>,
+    ) -> ((Cost, Option<Cigar>), WarmStart) {
+        let reference = match prev {
+            Some(w) if w.reference.seq() == a => w.reference.clone(),
+            _ => Arc::new(self.reference(a)),
+        };
+        let opts = AlignOptions {
+            warm: prev,
+            reference: Some(&*reference),
+            ..Default::default()
+        };
+        let (cost, cigar, stats) = self.cost_or_align(a, b, self.trace, opts).unwrap();
+        let warm = WarmStart {
+            len_a: a.len(),
+            len_b: b.len(),
+            cost,
+            h0: stats.h0,
+            reference,
+        };
+        ((cost, cigar), warm)
+    }
+
+    /// As `align`, but starts band doubling at `lower_bound` instead of at the
+    /// gap cost, skipping iterations that are known to fail, e.g. when a lower
+    /// bound is known from k-mer counts.
+    ///
+    /// The result is exact regardless of `lower_bound`; a bound above the
+    /// actual cost only makes the first iteration slower.
+    pub fn align_with_lower_bound(
+        &self,
+        a: Seq,
+        b: Seq,
+        lower_bound: Cost,
+    ) -> (Cost, Option<Cigar>) {
+        let opts = AlignOptions {
+            initial_bound: Some(lower_bound),
+            ..Default::default()
+        };
+        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace, opts).unwrap();
+        (cost, cigar)
+    }
+
+    /// As `align`, but also returns the `(i, j_range, fixed_j_range)` of each
+    /// computed block, over all tries of band doubling, to inspect how the
+    /// band evolves. `i` is the last column of the block, and each try starts
+    /// again at column 0.
+    pub fn align_with_band_trace(
+        &self,
+        a: Seq,
+        b: Seq,
+    ) -> (Cost, Option<Cigar>, Vec<(I, JRange, Option<JRange>)>) {
+        let opts = AlignOptions {
+            collect_band_trace: true,
+            ..Default::default()
+        };
+        let (cost, cigar, stats) = self.cost_or_align(a, b, self.trace, opts).unwrap();
+        (cost, cigar, stats.band_trace)
+    }
+
+    /// Prepare `a` for aligning many sequences to it with `align_to_reference`.
+    pub fn reference(&self, a: Seq) -> Reference {
+        Reference {
+            seq: a.to_vec(),
+            profile: BitProfile::build(a, &[]).0,
+            seed_index: self.domain.h().and_then(|h| h.seed_index(a)),
+        }
+    }
+
+    /// As `align`, with `a` the sequence of `reference`, whose profile and
+    /// seed index are reused. `reference` must be built by this aligner.
+    pub fn align_to_reference(&self, reference: &Reference, b: Seq) -> (Cost, Option<Cigar>) {
+        let opts = AlignOptions {
+            reference: Some(reference),
+            ..Default::default()
+        };
+        let (cost, cigar, _stats) = self
+            .cost_or_align(&reference.seq, b, self.trace, opts)
+            .unwrap();
+        (cost, cigar)
+    }
+
+    /// As `align`, but resumes band doubling from the checkpoint at `path` if
+    /// there is one, and writes a new checkpoint after each failed iteration.
+    /// The checkpoint is removed once the alignment is found.
+    ///
+    /// Only band doubling is checkpointed; other strategies ignore `path`.
+    ///
+    /// Fails before aligning when `path` cannot be read as a checkpoint, and
+    /// stops when a checkpoint cannot be written. In the latter case the
+    /// previous checkpoint is kept, so the alignment can still be resumed.
+    pub fn align_with_checkpoint(
+        &self,
+        a: Seq,
+        b: Seq,
+        path: &Path,
+    ) -> std::io::Result<(Cost, Option<Cigar>)> {
+        let file = CheckpointFile::open(path)?;
+        let opts = AlignOptions {
+            checkpoint: Some(&file),
+            ..Default::default()
+        };
+        let r = self.cost_or_align(a, b, self.trace, opts);
+        if let Some(e) = file.error.take() {
+            return Err(e);
+        }
+        let (cost, cigar, _stats) = r.unwrap();
+        Ok((cost, cigar))
+    }
+
+    /// As `align`, but forces the alignment through the given anchors, e.g.
+    /// matches found by an external seeder.
+    ///
+    /// Each anchor `(start, end)` is a diagonal segment that is aligned as-is,
+    /// using only matches and substitutions. Anchors must be sorted and may not
+    /// overlap. Only the gaps between consecutive anchors are aligned, each
+    /// with its own band doubling, so the result is optimal only among
+    /// alignments through all anchors.
+    pub fn align_with_anchors(
+        &self,
+        a: Seq,
+        b: Seq,
+        anchors: &[(Pos, Pos)],
+    ) -> (Cost, Option<Cigar>) {
+        let target = Pos::target(a, b);
+        let mut cost = 0;
+        let mut cigar = self.trace.then(Cigar::default);
+        let mut pos = Pos(0, 0);
+        for &(start, end) in anchors.iter().chain([(target, target)].iter()) {
+            assert!(
+                pos.0 <= start.0 && pos.1 <= start.1,
+                "Anchors must be sorted and non-overlapping: {start:?} after {pos:?}"
+            );
+            assert!(
+                end.0 - start.0 == end.1 - start.1 && end.0 >= start.0,
+                "Anchor {start:?} -> {end:?} is not a diagonal segment"
+            );
+            assert!(end.0 <= target.0 && end.1 <= target.1);
+
+            // The gap before the anchor.
+            let (c, gap_cigar) = self.align(
+                &a[pos.0 as usize..start.0 as usize],
+                &b[pos.1 as usize..start.1 as usize],
+            );
+            cost += c;
+            if let Some(cigar) = &mut cigar {
+                append_cigar(cigar, &gap_cigar.unwrap());
+            }
+
+            // The anchor itself.
+            for (ca, cb) in std::iter::zip(
+                &a[start.0 as usize..end.0 as usize],
+                &b[start.1 as usize..end.1 as usize],
+            ) {
+                cost += (ca != cb) as Cost;
+                if let Some(cigar) = &mut cigar {
+                    cigar.push(if ca == cb {
+                        CigarOp::Match
+                    } else {
+                        CigarOp::Sub
+                    });
+                }
+            }
+            pos = end;
+        }
+        (cost, cigar)
+    }
+
+    /// Align `a[i_range]` to `b[j_range]`, a rectangle of the full DP matrix.
+    ///
+    /// `boundary` gives the costs `g(i_range.start, j)` of the left column of the
+    /// rectangle for `j` in `j_range.start..=j_range.end`, e.g. from an earlier
+    /// alignment. Consecutive values must differ by at most 1. The path then
+    /// starts anywhere in the left column, and otherwise in the top-left corner
+    /// with cost 0. It always ends in the bottom-right corner.
+    ///
+    /// Returns the cost including the boundary, and when tracing, the start of
+    /// the path and its cigar. The rectangle is computed in full, independent of
+    /// `domain` and `doubling`.
+    pub fn align_rect(
+        &self,
+        a: Seq,
+        b: Seq,
+        i_range: Range<I>,
+        j_range: Range<I>,
+        boundary: Option<&[Cost]>,
+    ) -> (Cost, Option<(Pos, Cigar)>) {
+        let a = &a[i_range.start as usize..i_range.end as usize];
+        let b = &b[j_range.start as usize..j_range.end as usize];
+        let target = Pos::target(a, b);
+        let mut blocks = self.block.new(self.trace, a, b);
+        if let Some(boundary) = boundary {
+            assert_eq!(
+                boundary.len(),
+                b.len() + 1,
+                "The boundary must have a cost for each row of the rectangle"
+            );
+            blocks.init_with_boundary(boundary);
+        } else {
+            blocks.init(JRange(0, target.1));
+        }
+        let mut v = self.v.build(a, b);
+        for i in (0..target.0).step_by(self.block_width as usize) {
+            let i_range = IRange(i, (i + self.block_width).min(target.0));
+            blocks.compute_next_block(i_range, JRange(0, target.1), &mut v);
+        }
+        let cost = blocks.last_block().index(target.1);
+        let path = self.trace.then(|| {
+            let (cigar, _) = blocks.trace(a, b, Pos(0, 0), target, &mut v);
+            let b_len = cigar
+                .ops
+                .iter()
+                .filter(|el| el.op != CigarOp::Del)
+                .map(|el| el.cnt)
+                .sum::<I>();
+            (Pos(i_range.start, j_range.end - b_len), cigar)
+        });
+        (cost, path)
+    }
+
+    /// Align `a` and `b` both with and without pruning, to measure how much
+    /// pruning helps on this input. `self.prune` is ignored.
+    ///
+    /// Panics for local doubling, which requires pruning.
+    pub fn compare_pruning(&self, a: Seq, b: Seq) -> PruneComparison {
+        let run = |prune| {
+            let opts = AlignOptions {
+                prune: Some(prune),
+                ..Default::default()
+            };
+            let start = std::time::Instant::now();
+            let (cost, _cigar, stats) = self.cost_or_align(a, b, self.trace, opts).unwrap();
+            (cost, stats, start.elapsed())
+        };
+        let (cost, pruned, t_pruned) = run(true);
+        let (cost_unpruned, unpruned, t_unpruned) = run(false);
+        assert_eq!(cost, cost_unpruned, "Pruning changed the cost.");
+        PruneComparison {
+            cost,
+            lanes_pruned: pruned.block_stats.computed_lanes,
+            lanes_unpruned: unpruned.block_stats.computed_lanes,
+            t_pruned,
+            t_unpruned,
+        }
+    }
+
     pub fn cost_for_bounded_dist(&self, a: Seq, b: Seq, f_max: Cost) -> Option<Cost> {
         self.build(a, b)
             .align_for_bounded_dist(Some(f_max), false, None)
@@ -190,20 +660,222 @@ impl<V: VisualizerT, H: Heuristic> AstarPa2<V, H> {
     }
 }
 
+/// Fast path for inputs that need no DP: identical sequences, and an empty
+/// sequence against any other. This skips building the heuristic and blocks.
+fn trivial_alignment(a: Seq, b: Seq, trace: bool) -> Option<(Cost, Option<Cigar>)> {
+    let (op, cnt) = if a == b {
+        (CigarOp::Match, a.len())
+    } else if b.is_empty() {
+        (CigarOp::Del, a.len())
+    } else if a.is_empty() {
+        (CigarOp::Ins, b.len())
+    } else {
+        return None;
+    };
+    let cost = AffineCost::unit().gap_cost(Pos(0, 0), Pos::target(a, b));
+    let cigar = trace.then(|| Cigar {
+        ops: if cnt == 0 {
+            vec![]
+        } else {
+            vec![CigarElem { op, cnt: cnt as I }]
+        },
+    });
+    Some((cost, cigar))
+}
+
+/// Append `other` to `cigar`, merging the operations at the boundary.
+fn append_cigar(cigar: &mut Cigar, other: &Cigar) {
+    let mut ops = other.ops.iter();
+    if let (Some(last), Some(first)) = (cigar.ops.last_mut(), other.ops.first())
+        && last.op == first.op
+    {
+        last.cnt += first.cnt;
+        ops.next();
+    }
+    cigar.ops.extend(ops.cloned());
+}
+
+/// Optional behaviour of `AstarPa2::cost_or_align`.
+#[derive(Default)]
+struct AlignOptions<'o> {
+    checkpoint: Option<&'o CheckpointFile<'o>>,
+    warm: Option<&'o WarmStart>,
+    cancel: Option<CancelToken>,
+    /// Overrides `AstarPa2::prune`.
+    prune: Option<bool>,
+    /// Give up after computing this many states.
+    max_computed_states: Option<usize>,
+    /// Give up once the cost is known to be larger than this.
+    max_cost: Option<Cost>,
+    /// A known lower bound on the cost, used as the first threshold.
+    initial_bound: Option<Cost>,
+    /// Record the ranges of each block in `AstarPa2Stats::band_trace`.
+    collect_band_trace: bool,
+    /// The prepared sequence `a`.
+    reference: Option<&'o Reference>,
+}
+
+/// A sequence `a` that many sequences `b` are aligned to with
+/// `AstarPa2::align_to_reference`, e.g. reads against one reference.
+/// Its profile, and the `SeedIndex` of a seed heuristic, are built only once.
+pub struct Reference {
+    seq: Vec<u8>,
+    profile: Vec<blocks::PA>,
+    seed_index: Option<SeedIndex>,
+}
+
+impl Reference {
+    pub fn seq(&self) -> Seq {
+        &self.seq
+    }
+}
+
+/// Returned by `AstarPa2::align_with_budget` when the alignment needs more work
+/// than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub max_computed_states: usize,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Alignment exceeded the budget of {} computed states.",
+            self.max_computed_states
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Returned by `AstarPa2::align_min_identity` when the identity is below the threshold,
+/// i.e. the cost is larger than `max_cost`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BelowThreshold {
+    pub min_identity: f32,
+    pub max_cost: Cost,
+}
+
+impl std::fmt::Display for BelowThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Alignment identity is below {} (cost above {}).",
+            self.min_identity, self.max_cost
+        )
+    }
+}
+
+impl std::error::Error for BelowThreshold {}
+
+/// The result of `AstarPa2::compare_pruning`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PruneComparison {
+    pub cost: Cost,
+    /// Number of computed lanes of `W` rows, with and without pruning.
+    pub lanes_pruned: usize,
+    pub lanes_unpruned: usize,
+    pub t_pruned: Duration,
+    pub t_unpruned: Duration,
+}
+
+impl PruneComparison {
+    /// Computed lanes with pruning relative to without. Below 1 when pruning helps.
+    pub fn lanes_ratio(&self) -> f64 {
+        self.lanes_pruned as f64 / self.lanes_unpruned.max(1) as f64
+    }
+
+    /// Runtime with pruning relative to without. Below 1 when pruning helps.
+    pub fn time_ratio(&self) -> f64 {
+        self.t_pruned.as_secs_f64() / self.t_unpruned.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+/// The result of a previous alignment, used to warm-start `AstarPa2::align_warm`
+/// on a similar pair, e.g. consecutive windows of a long read.
+///
+/// The profile and seed index of `a` are reused as long as `a` does not
+/// change, e.g. for several reads against one window. The matches depend on
+/// `b` as well, so they are found again for each pair. The slack
+/// `cost - h0` of the previous heuristic is added to the value of the new one.
+#[derive(Clone)]
+pub struct WarmStart {
+    pub len_a: usize,
+    pub len_b: usize,
+    pub cost: Cost,
+    /// The value of the heuristic at the start of the previous pair.
+    pub h0: Cost,
+    /// The prepared `a` of the previous pair.
+    reference: Arc<Reference>,
+}
+
+impl WarmStart {
+    /// Only reuse the previous cost when the total length differs at most this fraction.
+    const MAX_LEN_RATIO: f32 = 0.1;
+
+    /// `h0` of the new pair plus the previous slack scaled to the length of the
+    /// new pair, or `None` when the new pair is too different to benefit from it.
+    fn expected_cost(&self, a: Seq, b: Seq, h0: Cost) -> Option<Cost> {
+        let prev_len = (self.len_a + self.len_b) as f32;
+        let len = (a.len() + b.len()) as f32;
+        if prev_len == 0. || (len - prev_len).abs() > Self::MAX_LEN_RATIO * prev_len {
+            return None;
+        }
+        let slack = (self.cost - self.h0) as f32 * len / prev_len;
+        Some(h0 + slack.ceil() as Cost)
+    }
+}
+
+/// Check that `a` and `b` only contain bytes supported by A*PA2:
+/// `ACGT` in any case, with `U` equal to `T`.
+pub fn check_input(a: Seq, b: Seq) -> Result<(), ProfileError> {
+    BitProfile::try_build(a, b).map(|_| ())
+}
+
 /// Helper trait to erase the type of the heuristic that additionally returns alignment statistics.
 pub trait AstarPa2StatsAligner: Aligner {
     fn align_with_stats(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>, AstarPa2Stats);
+    fn try_align(&mut self, a: Seq, b: Seq) -> Result<(Cost, Option<Cigar>), ProfileError>;
+    fn align_with_checkpoint(
+        &mut self,
+        a: Seq,
+        b: Seq,
+        path: &Path,
+    ) -> std::io::Result<(Cost, Option<Cigar>)>;
+    fn reference(&self, a: Seq) -> Reference;
+    fn align_to_reference(&mut self, reference: &Reference, b: Seq) -> (Cost, Option<Cigar>);
 }
 
 impl<V: VisualizerT, H: Heuristic> AstarPa2StatsAligner for AstarPa2<V, H> {
     fn align_with_stats(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>, AstarPa2Stats) {
-        self.cost_or_align(a, b, self.trace)
+        self.cost_or_align(a, b, self.trace, AlignOptions::default())
+            .unwrap()
+    }
+    fn try_align(&mut self, a: Seq, b: Seq) -> Result<(Cost, Option<Cigar>), ProfileError> {
+        AstarPa2::try_align(self, a, b)
+    }
+    fn align_with_checkpoint(
+        &mut self,
+        a: Seq,
+        b: Seq,
+        path: &Path,
+    ) -> std::io::Result<(Cost, Option<Cigar>)> {
+        AstarPa2::align_with_checkpoint(self, a, b, path)
+    }
+    fn reference(&self, a: Seq) -> Reference {
+        AstarPa2::reference(self, a)
+    }
+    fn align_to_reference(&mut self, reference: &Reference, b: Seq) -> (Cost, Option<Cigar>) {
+        AstarPa2::align_to_reference(self, reference, b)
     }
 }
 
 impl<V: VisualizerT, H: Heuristic> Aligner for AstarPa2<V, H> {
     fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
-        let (cost, cigar, _stats) = self.cost_or_align(a, b, self.trace);
+        let (cost, cigar, _stats) = self
+            .cost_or_align(a, b, self.trace, AlignOptions::default())
+            .unwrap();
         (cost, cigar)
     }
 }
@@ -63,6 +63,8 @@ impl AstarPa2Params {
                 dt_trace: true,
                 max_g: 40,
                 fr_drop: 10,
+                traceback: TracebackPolicy::Diagonal,
+                indel_tie_break: IndelTieBreak::Ins,
             },
             sparse_h: true,
             prune: false,
@@ -95,6 +97,8 @@ impl AstarPa2Params {
                 dt_trace: true,
                 max_g: 40,
                 fr_drop: 10,
+                traceback: TracebackPolicy::Diagonal,
+                indel_tie_break: IndelTieBreak::Ins,
             },
             sparse_h: true,
             prune: true,
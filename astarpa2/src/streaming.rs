@@ -0,0 +1,119 @@
+//! Cost-only alignment of sequences that do not fit in memory, such as whole chromosomes.
+//!
+//! `Blocks` packs all of `a` and `b` up front and stores a full column of
+//! vertical differences. Instead, `StreamingAligner` computes the band one
+//! window of `CHUNK` columns at a time, and only packs the part of `a` and `b`
+//! covered by the current window. Inputs can be memory-mapped using
+//! `map_sequence`, so that the OS reads `a` sequentially and the band of `b`
+//! on demand. Apart from the inputs, memory usage is linear in the band width.
+//!
+//! The band contains all states within distance `band` of the main diagonal,
+//! and is doubled until the distance is at most `band`, as in Ukkonen's band
+//! doubling. Traceback is not supported.
+use pa_bitpacking::{compute, BitProfile, HEncoding, Profile, B, V, W};
+use pa_types::{Cost, Seq, I};
+use std::{
+    cmp::{max, min},
+    fs::File,
+    io,
+    path::Path,
+};
+
+/// The number of columns that are packed and computed at a time.
+const CHUNK: usize = 1024;
+
+/// Memory-map a file containing a single sequence, without header or line breaks.
+/// Use `map.trim_ascii_end()` to drop a trailing newline.
+///
+/// The file must not be modified while it is mapped.
+pub fn map_sequence(path: &Path) -> io::Result<memmap2::Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: The caller guarantees that the file is not modified.
+    unsafe { memmap2::Mmap::map(&file) }
+}
+
+/// Unit-cost distance in memory linear in the band width.
+///
+/// ```
+/// use astarpa2::StreamingAligner;
+/// let cost = StreamingAligner::default().cost(b"ACGTACGTACGT", b"ACGTTCGTACGACGT");
+/// assert_eq!(cost, 4);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct StreamingAligner {
+    /// The band width of the first iteration, which is doubled until it is at
+    /// least the distance. Always at least the difference in length.
+    pub initial_band: I,
+}
+
+impl Default for StreamingAligner {
+    fn default() -> Self {
+        Self {
+            initial_band: W as I,
+        }
+    }
+}
+
+impl StreamingAligner {
+    /// The unit-cost edit distance between `a` and `b`, which must be `ACGT` only.
+    pub fn cost(&self, a: Seq, b: Seq) -> Cost {
+        if a.is_empty() || b.is_empty() {
+            return (a.len() + b.len()) as Cost;
+        }
+        let mut band = max(self.initial_band.max(1) as usize, a.len().abs_diff(b.len()));
+        loop {
+            let cost = banded_cost(a, b, band);
+            if cost <= band as Cost {
+                return cost;
+            }
+            band *= 2;
+        }
+    }
+}
+
+/// The distance between `a` and `b` computing only states within `band` of
+/// the diagonal, rounded out to words. Deltas outside the band are assumed to
+/// be `+1`, so this is an upper bound, and exact when it is at most `band`.
+///
+/// `band` must be at least `|a| - |b|`, so that the band contains the end.
+fn banded_cost(a: Seq, b: Seq, band: usize) -> Cost {
+    let words = b.len().div_ceil(W);
+    // The words `start..start + v.len()` of `b` in the band of the current
+    // column, and their vertical differences.
+    let mut start = 0;
+    let mut v: Vec<V> = vec![];
+    // The value at the top of the band in the current column.
+    let mut top: Cost = 0;
+
+    for i0 in (0..a.len()).step_by(CHUNK) {
+        let i1 = min(i0 + CHUNK, a.len());
+        let new_start = i0.saturating_sub(band) / W;
+        let new_end = min((i1 + band).div_ceil(W), words);
+
+        // Move the top of the band down, and extend it at the bottom with deltas +1.
+        top += v
+            .drain(..new_start - start)
+            .map(|v| v.value())
+            .sum::<Cost>();
+        v.resize(new_end - new_start, V::one());
+        start = new_start;
+
+        let (pa, pb) = BitProfile::build(&a[i0..i1], &b[start * W..min(new_end * W, b.len())]);
+        // The top of the band goes right with deltas +1.
+        let mut h = vec![<(B, B)>::one(); i1 - i0];
+        compute(&pa, &pb, &mut h, &mut v, false);
+        top += (i1 - i0) as Cost;
+    }
+
+    assert_eq!(start + v.len(), words, "The band does not contain the end");
+    let mut dist = top;
+    for (k, v) in (start..).zip(&v) {
+        let rows = b.len() - k * W;
+        dist += if rows >= W {
+            v.value()
+        } else {
+            v.value_of_prefix(rows as I)
+        };
+    }
+    dist
+}
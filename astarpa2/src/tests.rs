@@ -1,4 +1,5 @@
 use super::*;
+use pa_affine_types::AffineCigarOp;
 use pa_heuristic::*;
 use pa_test::*;
 use pa_vis::NoVis;
@@ -16,19 +17,43 @@ fn nw() -> AstarPa2<NoVis, NoCost> {
     }
 }
 
+/// Band doubling with blocks of width 64 on the given domain.
+fn band_doubling<H: Heuristic>(domain: Domain<H>) -> AstarPa2<NoVis, H> {
+    AstarPa2 {
+        doubling: DoublingType::band_doubling(),
+        domain,
+        block_width: 64,
+        ..nw()
+    }
+}
+
+/// The A* domain using GCSH with exact seeds of length `k`.
+fn gcsh(k: I) -> Domain<impl Heuristic> {
+    Domain::Astar(GCSH::new(MatchConfig::exact(k), Pruning::start()))
+}
+
 #[test]
 fn full() {
     test_aligner(nw());
 }
 
+#[test]
+fn try_align() {
+    let aligner = band_doubling(Domain::dist_gap());
+    assert_eq!(aligner.try_align(b"ACGT", b"acgu").unwrap().0, 0);
+    assert_eq!(
+        aligner.try_align(b"ACGT", b"ACNT").unwrap_err(),
+        ProfileError {
+            byte: b'N',
+            pos: 2,
+            seq: 'b'
+        }
+    );
+}
+
 #[test]
 fn band_doubling_gapgap() {
-    test_aligner(AstarPa2 {
-        doubling: DoublingType::band_doubling(),
-        domain: Domain::gap_gap(),
-        block_width: 64,
-        ..nw()
-    });
+    test_aligner(band_doubling(Domain::gap_gap()));
 }
 
 #[test]
@@ -47,22 +72,12 @@ fn dt_trace_gapgap() {
 
 #[test]
 fn band_doubling_dijkstra() {
-    test_aligner(AstarPa2 {
-        doubling: DoublingType::band_doubling(),
-        domain: Domain::dijkstra(),
-        block_width: 64,
-        ..nw()
-    });
+    test_aligner(band_doubling(Domain::dijkstra()));
 }
 
 #[test]
 fn band_doubling_edlib() {
-    test_aligner(AstarPa2 {
-        doubling: DoublingType::band_doubling(),
-        domain: Domain::dist_gap(),
-        block_width: 64,
-        ..nw()
-    });
+    test_aligner(band_doubling(Domain::dist_gap()));
 }
 
 #[test]
@@ -106,15 +121,12 @@ fn dt_trace() {
 #[test]
 fn incremental_doubling() {
     test_aligner(AstarPa2 {
-        doubling: DoublingType::band_doubling(),
-        domain: Domain::dist_gap(),
-        block_width: 64,
         block: BlockParams {
             dt_trace: true,
             incremental_doubling: true,
             ..Default::default()
         },
-        ..nw()
+        ..band_doubling(Domain::dist_gap())
     });
 }
 
@@ -128,3 +140,558 @@ fn local_doubling() {
         ..nw()
     })
 }
+
+/// Compare all stored block values against a naive DP, cell for cell.
+#[test]
+fn g_matrix() {
+    let aligner = AstarPa2 {
+        block: BlockParams {
+            sparse: false,
+            ..Default::default()
+        },
+        ..nw()
+    };
+    for ((a, b), _) in gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+        let mut blocks = aligner.block.new(true, &a, &b);
+        aligner
+            .build(&a, &b)
+            .align_for_bounded_dist(None, true, Some(&mut blocks))
+            .unwrap();
+        let g = blocks.g_matrix(Pos::target(&a, &b));
+        let naive = dp_matrix(&a, &b, &CostModel::unit());
+        for i in 0..=a.len() {
+            for j in 0..=b.len() {
+                assert_eq!(g[i][j], Some(naive[i][j]), "Mismatch at ({i}, {j})");
+            }
+        }
+    }
+}
+
+/// Checkpoint failures are returned as errors instead of panicking.
+#[test]
+fn checkpoint_errors() {
+    let (a, b) = pa_generate::uniform_fixed(2000, 0.1);
+    let aligner = band_doubling(Domain::gap_gap());
+    let dir = std::env::temp_dir().join(format!("astarpa2-checkpoint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("checkpoint");
+
+    // A finished alignment removes its checkpoint.
+    let (cost, _) = aligner.align_with_checkpoint(&a, &b, &path).unwrap();
+    assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+    assert!(!path.exists());
+
+    // A corrupt checkpoint is reported before aligning.
+    std::fs::write(&path, "not a checkpoint").unwrap();
+    let err = aligner.align_with_checkpoint(&a, &b, &path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // A checkpoint that cannot be written stops the alignment.
+    let unwritable = dir.join("missing").join("checkpoint");
+    assert!(aligner.align_with_checkpoint(&a, &b, &unwritable).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Warm starting from the previous pair must not change the cost.
+#[test]
+fn align_warm() {
+    let aligner = band_doubling(gcsh(5));
+    let mut prev = None;
+    for ((a, b), params) in gen_seqs() {
+        let ((cost, _), warm) = aligner.align_warm(&a, &b, prev.as_ref());
+        assert_eq!(
+            cost,
+            triple_accel::levenshtein_exp(&a, &b) as Cost,
+            "{params:?}"
+        );
+        prev = Some(warm);
+    }
+}
+
+/// Warm starting reuses the seed index while `a` stays the same.
+#[test]
+fn align_warm_reuses_seed_index() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let rng = &mut StdRng::seed_from_u64(31415);
+    let aligner = band_doubling(gcsh(10));
+    let mut prev: Option<WarmStart> = None;
+    for _ in 0..2 {
+        let a = generate::random_sequence(2000, rng);
+        for e in [0.01, 0.05, 0.2] {
+            let b = generate::mutate(&a, e, rng);
+            let ((cost, _), warm) = aligner.align_warm(&a, &b, prev.as_ref());
+            assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+            assert!(warm.reference.seed_index.is_some());
+            if let Some(prev) = &prev {
+                assert_eq!(
+                    Arc::ptr_eq(&prev.reference, &warm.reference),
+                    prev.reference.seq() == a.as_slice()
+                );
+            }
+            prev = Some(warm);
+        }
+    }
+}
+
+/// Aligning many sequences to one `Reference` gives the same costs as aligning each pair.
+#[test]
+fn align_to_reference() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let rng = &mut StdRng::seed_from_u64(31415);
+    let a = generate::random_sequence(2000, rng);
+    // Only exact matches are found using the seed index.
+    for (r, indexed) in [(1, true), (2, false)] {
+        let aligner = band_doubling(Domain::Astar(GCSH::new(
+            MatchConfig::new(9, r),
+            Pruning::start(),
+        )));
+        let reference = aligner.reference(&a);
+        assert_eq!(reference.seed_index.is_some(), indexed);
+        for e in [0.01, 0.05, 0.2] {
+            let b = generate::mutate(&a, e, rng);
+            let (cost, cigar) = aligner.align_to_reference(&reference, &b);
+            assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+            assert_eq!(cigar.unwrap().verify(&CostModel::unit(), &a, &b), cost);
+        }
+    }
+}
+
+/// A token that is already cancelled stops the alignment without panicking.
+#[test]
+fn cancelled() {
+    let aligner = band_doubling(Domain::gap_gap());
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.1);
+    let cancel = pa_heuristic::util::CancelToken::new();
+    assert!(aligner.align_cancellable(&a, &b, &cancel).is_some());
+    cancel.cancel();
+    assert!(aligner.align_cancellable(&a, &b, &cancel).is_none());
+}
+
+/// A small budget gives up, and a large one gives the exact cost.
+#[test]
+fn budget() {
+    let aligner = band_doubling(Domain::gap_gap());
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.1);
+    let cost = aligner.align(&a, &b).0;
+    assert_eq!(
+        aligner.align_with_budget(&a, &b, 1000),
+        Err(BudgetExceeded {
+            max_computed_states: 1000
+        })
+    );
+    assert_eq!(
+        aligner.align_with_budget(&a, &b, usize::MAX).unwrap().0,
+        cost
+    );
+}
+
+/// Pairs below the identity threshold are rejected; others get the exact cost.
+#[test]
+fn min_identity() {
+    let aligner = band_doubling(Domain::gap_gap());
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.1);
+    let cost = aligner.align(&a, &b).0;
+    let n = a.len().max(b.len()) as f32;
+    // The largest allowed cost is `cost`.
+    let identity = 1.0 - (cost as f32 + 0.5) / n;
+    assert_eq!(
+        aligner.align_min_identity(&a, &b, identity).unwrap().0,
+        cost
+    );
+    // The largest allowed cost is `cost - 1`.
+    let identity = 1.0 - (cost as f32 - 0.5) / n;
+    assert_eq!(
+        aligner.align_min_identity(&a, &b, identity),
+        Err(BelowThreshold {
+            min_identity: identity,
+            max_cost: cost - 1
+        })
+    );
+    // Identical sequences always pass.
+    assert_eq!(aligner.align_min_identity(&a, &a, 1.0).unwrap().0, 0);
+}
+
+#[test]
+#[should_panic(expected = "min_identity must be in [0, 1]")]
+fn min_identity_out_of_range() {
+    let (a, b) = pa_generate::uniform_fixed(100, 0.1);
+    let _ = nw().align_min_identity(&a, &b, 1.5);
+}
+
+/// Exceeding the budget does not cancel the caller's token.
+#[test]
+fn budget_keeps_caller_token() {
+    let aligner = band_doubling(Domain::gap_gap());
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.1);
+    let cancel = pa_heuristic::util::CancelToken::new();
+    let opts = AlignOptions {
+        cancel: Some(cancel.clone()),
+        max_computed_states: Some(1000),
+        ..Default::default()
+    };
+    assert!(aligner.cost_or_align(&a, &b, false, opts).is_none());
+    assert!(!cancel.is_cancelled());
+}
+
+/// Anchors on the optimal path do not change the cost, and the cigar is valid.
+#[test]
+fn align_with_anchors() {
+    let aligner = band_doubling(Domain::gap_gap());
+    for ((a, b), params) in gen_seqs() {
+        let (cost, cigar) = aligner.align(&a, &b);
+        // Use the first and last runs of matches of the optimal path as anchors.
+        let path = cigar.unwrap().to_path();
+        let runs = path
+            .windows(2)
+            .filter(|w| w[1] == w[0] + Pos(1, 1) && a[w[0].0 as usize] == b[w[0].1 as usize])
+            .map(|w| (w[0], w[1]))
+            .collect::<Vec<_>>();
+        let anchors = match (runs.first(), runs.last()) {
+            (Some(&f), Some(&l)) if f != l => vec![f, l],
+            (Some(&f), _) => vec![f],
+            _ => vec![],
+        };
+        let (anchored_cost, anchored_cigar) = aligner.align_with_anchors(&a, &b, &anchors);
+        assert_eq!(cost, anchored_cost, "{params:?}");
+        anchored_cigar.unwrap().verify(&CostModel::unit(), &a, &b);
+    }
+}
+
+#[test]
+fn align_rect() {
+    let aligner = AstarPa2 {
+        block_width: 64,
+        ..nw()
+    };
+    for ((a, b), params) in gen_seqs() {
+        let (n, m) = (a.len(), b.len());
+        let (i0, j0) = (n / 3, m / 4);
+
+        // Without a boundary, the rectangle is aligned like the sliced sequences.
+        let (cost, path) = aligner.align_rect(&a, &b, i0 as I..n as I, j0 as I..m as I, None);
+        assert_eq!(cost, aligner.align(&a[i0..], &b[j0..]).0, "{params:?}");
+        let (start, cigar) = path.unwrap();
+        assert_eq!(start, Pos(i0 as I, j0 as I), "{params:?}");
+        assert_eq!(cigar.verify(&CostModel::unit(), &a[i0..], &b[j0..]), cost);
+
+        // With the full-matrix column at `i0` as boundary, the cost is the full cost.
+        let boundary = (0..=m)
+            .map(|j| triple_accel::levenshtein_exp(&a[..i0], &b[..j]) as Cost)
+            .collect::<Vec<_>>();
+        let (cost, path) = aligner.align_rect(&a, &b, i0 as I..n as I, 0..m as I, Some(&boundary));
+        assert_eq!(cost, aligner.align(&a, &b).0, "{params:?}");
+        let (start, cigar) = path.unwrap();
+        assert_eq!(start.0, i0 as I);
+        let j = start.1 as usize;
+        assert_eq!(
+            boundary[j] + cigar.verify(&CostModel::unit(), &a[i0..], &b[j..]),
+            cost,
+            "{params:?}"
+        );
+    }
+}
+
+/// Pruning must not change the cost.
+#[test]
+fn compare_pruning() {
+    let aligner = band_doubling(gcsh(5));
+    let (a, b) = pa_generate::uniform_fixed(2000, 0.05);
+    let c = aligner.compare_pruning(&a, &b);
+    assert_eq!(c.cost, aligner.cost(&a, &b));
+    assert!(c.lanes_pruned > 0 && c.lanes_unpruned > 0, "{c:?}");
+}
+
+/// The domain bounds must not favour either argument order.
+#[test]
+fn symmetric() {
+    for domain in [Domain::gap_start(), Domain::gap_gap()] {
+        test_aligner_symmetric(band_doubling(domain));
+    }
+    test_aligner_symmetric(band_doubling(gcsh(5)));
+}
+
+/// On the optimal path, `query` gives the exact `g`, and `f` is at most the distance.
+#[test]
+fn query() {
+    let aligner = AstarPa2 {
+        domain: Domain::Astar(GCSH::new(MatchConfig::exact(5), Pruning::start())),
+        block_width: 64,
+        block: BlockParams {
+            sparse: false,
+            ..Default::default()
+        },
+        prune: false,
+        ..nw()
+    };
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.05);
+    let dist = triple_accel::levenshtein_exp(&a, &b) as Cost;
+    let mut nw = aligner.build(&a, &b);
+    let mut blocks = aligner.block.new(true, &a, &b);
+    let (cost, cigar) = nw
+        .align_for_bounded_dist(Some(dist), true, Some(&mut blocks))
+        .unwrap();
+    assert_eq!(cost, dist);
+
+    let mut g = 0;
+    let path = cigar.unwrap().to_path();
+    for (k, &pos) in path.iter().enumerate() {
+        if k > 0 {
+            let prev = path[k - 1];
+            let is_match = pos == prev + Pos(1, 1) && a[prev.0 as usize] == b[prev.1 as usize];
+            g += !is_match as Cost;
+        }
+        let (query_g, h, f) = nw.query(&blocks, pos);
+        assert_eq!(query_g, Some(g), "{pos:?}");
+        assert_eq!(f, Some(g + h));
+        assert!(g + h <= dist, "h is not admissible at {pos:?}");
+    }
+    // The heuristic at the start bounds the distance.
+    assert!(nw.query(&blocks, Pos(0, 0)).1 <= dist);
+}
+
+/// Following `tree_parent` from the target gives a path of the optimal cost.
+#[test]
+fn tree_parent() {
+    let aligner = AstarPa2 {
+        block: BlockParams {
+            sparse: false,
+            ..Default::default()
+        },
+        ..nw()
+    };
+    for ((a, b), params) in gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+        let mut blocks = aligner.block.new(true, &a, &b);
+        let (cost, _) = aligner
+            .build(&a, &b)
+            .align_for_bounded_dist(None, true, Some(&mut blocks))
+            .unwrap();
+        let mut st = State::new(a.len() as I, b.len() as I, None);
+        let mut path_cost = 0;
+        while let Some((p, [op, _])) = blocks.tree_parent(st) {
+            path_cost += (op != Some(AffineCigarOp::Match)) as Cost;
+            st = p;
+        }
+        assert_eq!(st.pos(), Pos(0, 0), "{params:?}");
+        assert_eq!(path_cost, cost, "{params:?}");
+    }
+}
+
+/// Empty and identical inputs take the fast path and must not panic.
+#[test]
+fn trivial_inputs() {
+    let cases: [(&[u8], &[u8], Cost); 5] = [
+        (b"", b"", 0),
+        (b"ACGT", b"ACGT", 0),
+        (b"", b"ACG", 3),
+        (b"ACG", b"", 3),
+        (b"A", b"C", 1),
+    ];
+    for (a, b, expected) in cases {
+        for (cost, cigar) in [astarpa2_simple(a, b), astarpa2_full(a, b)] {
+            assert_eq!(cost, expected);
+            assert_eq!(cigar.verify(&CostModel::unit(), a, b), expected);
+        }
+        assert_eq!(nw().cost(a, b), expected);
+    }
+}
+
+#[test]
+#[should_panic(expected = "No alignment found")]
+fn band_doubling_bounded_by_max_cost() {
+    // A buggy aligner that never finds an alignment must not loop forever.
+    band::exponential_search("test", 0, 1, 2., 100, || false, |_| None::<(Cost, ())>);
+}
+
+/// Driving `Blocks` with an explicit loop gives the edit distance.
+#[test]
+fn block_api() {
+    let params = BlockParams {
+        incremental_doubling: false,
+        ..Default::default()
+    };
+    for ((a, b), _) in gen_seqs() {
+        let (n, m) = (a.len() as I, b.len() as I);
+        let mut blocks = params.new(false, &a, &b);
+        blocks.init(JRange(0, m));
+        for i in (0..n).step_by(WI as usize) {
+            blocks.compute_next_block(IRange(i, (i + WI).min(n)), JRange(0, m), &mut NoVis);
+        }
+        assert_eq!(
+            blocks.last_block().index(m),
+            triple_accel::levenshtein_exp(&a, &b) as Cost
+        );
+    }
+}
+
+#[test]
+fn traceback_policy() {
+    use TracebackPolicy::*;
+    for traceback in [LeftAlign, RightAlign] {
+        test_aligner(AstarPa2 {
+            block_width: 64,
+            block: BlockParams {
+                traceback,
+                ..Default::default()
+            },
+            ..nw()
+        });
+        // Paths along the top edge of the band, where the previous block starts below them.
+        test_aligner(AstarPa2 {
+            block: BlockParams {
+                traceback,
+                ..Default::default()
+            },
+            ..band_doubling(gcsh(5))
+        });
+    }
+
+    // The deletion of one `A` from the homopolymer run.
+    let a = b"ACGTAAAACGT";
+    let b = b"ACGTAAACGT";
+    for (traceback, cigar) in [
+        (Diagonal, "4=1D6="),
+        (LeftAlign, "4=1D6="),
+        (RightAlign, "7=1D3="),
+    ] {
+        let aligner = AstarPa2 {
+            block: BlockParams {
+                traceback,
+                ..Default::default()
+            },
+            ..nw()
+        };
+        assert_eq!(aligner.align(a, b).1.unwrap().to_string(), cigar);
+    }
+
+    // Either the insertion or the deletion can go first.
+    for (indel_tie_break, cigar) in [
+        (IndelTieBreak::Ins, "1D1=1I"),
+        (IndelTieBreak::Del, "1I1=1D"),
+    ] {
+        let aligner = AstarPa2 {
+            block: BlockParams {
+                indel_tie_break,
+                ..Default::default()
+            },
+            ..nw()
+        };
+        assert_eq!(aligner.align(b"AC", b"CA").1.unwrap().to_string(), cigar);
+    }
+}
+
+/// RNA `U` matches DNA `T`, in any case.
+#[test]
+fn rna() {
+    let (a, _) = pa_generate::uniform_fixed(1000, 0.);
+    let rna = a
+        .iter()
+        .map(|&c| match c {
+            b'T' => b'U',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect::<Vec<_>>();
+    let aligner = band_doubling(gcsh(10));
+    let (cost, cigar) = aligner.align(&a, &rna);
+    assert_eq!(cost, 0);
+    assert_eq!(cigar.unwrap().to_string(), format!("{}=", a.len()));
+}
+
+/// Starting at a lower bound skips iterations, and does not change the cost.
+#[test]
+fn initial_bound() {
+    let aligner = band_doubling(Domain::gap_gap());
+    let (a, b) = pa_generate::uniform_fixed(1000, 0.1);
+    let (cost, _, stats) = aligner
+        .cost_or_align(&a, &b, true, AlignOptions::default())
+        .unwrap();
+    assert!(stats.f_max_tries > 1);
+    for bound in [0, cost / 2, cost, 2 * cost] {
+        let (c, cigar) = aligner.align_with_lower_bound(&a, &b, bound);
+        assert_eq!(c, cost);
+        assert_eq!(cigar.unwrap().verify(&CostModel::unit(), &a, &b), cost);
+    }
+    let opts = AlignOptions {
+        initial_bound: Some(cost),
+        ..Default::default()
+    };
+    let (_, _, stats) = aligner.cost_or_align(&a, &b, true, opts).unwrap();
+    assert_eq!(stats.f_max_tries, 1);
+}
+
+/// The band never exceeds the full column, which the `Full` domain always uses.
+#[test]
+fn max_band_width() {
+    let (a, b) = pa_generate::uniform_fixed(2000, 0.05);
+    let (_, _, stats) = nw()
+        .cost_or_align(&a, &b, false, AlignOptions::default())
+        .unwrap();
+    assert_eq!(stats.max_band_width, b.len() as I + 1);
+
+    let aligner = band_doubling(gcsh(10));
+    let (_, _, stats) = aligner
+        .cost_or_align(&a, &b, false, AlignOptions::default())
+        .unwrap();
+    assert!(0 < stats.max_band_width && stats.max_band_width < b.len() as I / 2);
+}
+
+#[test]
+fn band_trace() {
+    let (a, b) = pa_generate::uniform_fixed(2000, 0.05);
+    let aligner = band_doubling(gcsh(10));
+    let (cost, _, trace) = aligner.align_with_band_trace(&a, &b);
+    assert_eq!(cost, aligner.align(&a, &b).0);
+
+    // Split the trace into tries, which each start at column 0.
+    let tries = trace.chunk_by(|_, &(i, ..)| i != 0).collect::<Vec<_>>();
+    assert!(!tries.is_empty());
+    for try_ in &tries {
+        assert_eq!(try_[0].0, 0);
+        for w in try_.windows(2) {
+            assert!(w[0].0 < w[1].0);
+        }
+        for &(_, j_range, fixed_j_range) in *try_ {
+            let fixed_j_range = fixed_j_range.unwrap();
+            if !fixed_j_range.is_empty() {
+                assert!(j_range.0 <= fixed_j_range.0 && fixed_j_range.1 <= j_range.1);
+            }
+        }
+    }
+    // The last try reaches the end.
+    assert_eq!(trace.last().unwrap().0, a.len() as I);
+
+    let (_, _, stats) = aligner
+        .cost_or_align(&a, &b, true, AlignOptions::default())
+        .unwrap();
+    assert!(stats.band_trace.is_empty());
+}
+
+#[test]
+fn streaming() {
+    for (n, e) in [(100, 0.1), (3000, 0.01), (5000, 0.2), (10000, 0.05)] {
+        for model in [
+            pa_generate::ErrorModel::Uniform,
+            pa_generate::ErrorModel::NoisyInsert,
+            pa_generate::ErrorModel::NoisyDelete,
+        ] {
+            let (a, b) = pa_generate::generate_model(n, e, model, n as u64);
+            let cost = StreamingAligner::default().cost(&a, &b);
+            assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+        }
+    }
+}
+
+#[test]
+fn streaming_mapped_file() {
+    let (a, b) = pa_generate::uniform_fixed(5000, 0.05);
+    let dir = std::env::temp_dir().join(format!("astarpa2-streaming-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (path_a, path_b) = (dir.join("a.seq"), dir.join("b.seq"));
+    std::fs::write(&path_a, [&a[..], b"\n"].concat()).unwrap();
+    std::fs::write(&path_b, &b).unwrap();
+    let map_a = map_sequence(&path_a).unwrap();
+    let map_b = map_sequence(&path_b).unwrap();
+    let cost = StreamingAligner::default().cost(map_a.trim_ascii_end(), &map_b);
+    assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+    drop((map_a, map_b));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,79 @@
+//! Cross-check the main configurations against `triple_accel` on many seeds.
+//!
+//! Unlike `tests`, which reuses the naive DP in places, this only relies on an
+//! external library, and all inputs are reproducible from the seed in the
+//! failure message. Run with `cargo test -p astarpa2 --features validate validate`.
+use super::*;
+use pa_heuristic::*;
+use pa_test::validate_aligner;
+use pa_vis::NoVis;
+
+const SEEDS: std::ops::Range<u64> = 0..200;
+
+fn base() -> AstarPa2<NoVis, NoCost> {
+    AstarPa2 {
+        doubling: DoublingType::band_doubling(),
+        domain: Domain::full(),
+        block_width: 64,
+        v: NoVis,
+        block: BlockParams::default(),
+        trace: true,
+        sparse_h: true,
+        prune: true,
+    }
+}
+
+#[test]
+fn gap_gap() {
+    validate_aligner(
+        AstarPa2 {
+            domain: Domain::gap_gap(),
+            ..base()
+        },
+        SEEDS,
+    );
+}
+
+#[test]
+fn dist_gap_incremental() {
+    validate_aligner(
+        AstarPa2 {
+            domain: Domain::dist_gap(),
+            block: BlockParams {
+                dt_trace: true,
+                incremental_doubling: true,
+                ..Default::default()
+            },
+            ..base()
+        },
+        SEEDS,
+    );
+}
+
+#[test]
+fn gcsh() {
+    validate_aligner(
+        AstarPa2 {
+            domain: Domain::Astar(GCSH::new(MatchConfig::exact(15), Pruning::start())),
+            block_width: 256,
+            ..base()
+        },
+        SEEDS,
+    );
+}
+
+#[test]
+fn gcsh_dt_trace() {
+    validate_aligner(
+        AstarPa2 {
+            domain: Domain::Astar(GCSH::new(MatchConfig::exact(15), Pruning::start())),
+            block_width: 256,
+            block: BlockParams {
+                dt_trace: true,
+                ..Default::default()
+            },
+            ..base()
+        },
+        SEEDS,
+    );
+}
@@ -0,0 +1,24 @@
+//! The core aligners must build and give correct distances without optional
+//! features, in particular without SDL for the visualizer.
+//!
+//! Run with `cargo test -p astarpa2 --no-default-features`.
+#![cfg(not(feature = "example"))]
+
+use pa_types::Cost;
+
+#[test]
+fn align_without_features() {
+    let (a, b) = pa_generate::uniform_fixed(2000, 0.1);
+    let d = triple_accel::levenshtein_exp(&a, &b) as Cost;
+
+    // Bitpacking only.
+    assert_eq!(pa_bitpacking::edit_distance(&a, &b), d);
+
+    // A*PA2 with and without a seed heuristic.
+    let (cost, cigar) = astarpa2::astarpa2_simple(&a, &b);
+    assert_eq!(cost, d);
+    assert_eq!(cigar.verify(&pa_types::CostModel::unit(), &a, &b), d);
+    let (cost, cigar) = astarpa2::astarpa2_full(&a, &b);
+    assert_eq!(cost, d);
+    assert_eq!(cigar.verify(&pa_types::CostModel::unit(), &a, &b), d);
+}
@@ -159,11 +159,52 @@ impl AffineCigar {
         });
     }
 
-    /// Reverse the cigar string.
+    /// Reverse the order of the elements, as needed after a traceback that
+    /// pushes elements from the end. Open and close markers are not swapped,
+    /// so these must be pushed in reverse as well. See `reverse_sequences`.
     pub fn reverse(&mut self) {
         self.ops.reverse()
     }
 
+    /// Turn this into an alignment of the reversed sequences.
+    ///
+    /// `a` and `b` keep their roles, so insertions stay insertions and
+    /// deletions stay deletions, as for `Cigar::reverse`. Unlike `reverse`,
+    /// the `AffineOpen` and `AffineClose` markers are swapped, so that each
+    /// affine gap is still opened before it is closed. Use `reverse_pos` to map
+    /// positions on the path.
+    ///
+    /// ```
+    /// # use pa_affine_types::*;
+    /// let cm = AffineCost::affine(1, 2, 1);
+    /// let mut cigar = AffineCigar::default();
+    /// for op in [
+    ///     AffineCigarOp::Match,
+    ///     AffineCigarOp::Match,
+    ///     AffineCigarOp::AffineOpen(1),
+    ///     AffineCigarOp::AffineDel(1),
+    ///     AffineCigarOp::AffineDel(1),
+    ///     AffineCigarOp::AffineClose(1),
+    ///     AffineCigarOp::Match,
+    ///     AffineCigarOp::Match,
+    /// ] {
+    ///     cigar.push_op(op);
+    /// }
+    /// assert_eq!(cigar.verify(&cm, b"AACCGT", b"AAGT"), 4);
+    /// cigar.reverse_sequences();
+    /// assert_eq!(cigar.verify(&cm, b"TGCCAA", b"TGAA"), 4);
+    /// ```
+    pub fn reverse_sequences(&mut self) {
+        self.ops.reverse();
+        for elem in &mut self.ops {
+            elem.op = match elem.op {
+                AffineCigarOp::AffineOpen(l) => AffineCigarOp::AffineClose(l),
+                AffineCigarOp::AffineClose(l) => AffineCigarOp::AffineOpen(l),
+                op => op,
+            };
+        }
+    }
+
     /// Append another cigar to this one.
     pub fn append(&mut self, other: &mut Self) {
         let Some(first) = other.ops.first_mut() else {return;};
@@ -260,6 +301,16 @@ impl AffineCigar {
         path
     }
 
+    /// See `pretty_alignment`.
+    pub fn pretty(&self, a: Seq, b: Seq, width: usize) -> String {
+        pretty_alignment(&self.to_base(), a, b, width)
+    }
+
+    /// Split the cost of this alignment by operation. See `CostBreakdown::new`.
+    pub fn cost_breakdown<const N: usize>(&self, cm: &AffineCost<N>) -> CostBreakdown {
+        CostBreakdown::new(&self.to_base(), cm)
+    }
+
     pub fn verify<const N: usize>(&self, cm: &AffineCost<N>, a: Seq, b: Seq) -> Cost {
         let mut pos = Pos(0, 0);
         let mut layer = None;
@@ -332,6 +383,257 @@ impl AffineCigar {
     }
 }
 
+/// The cost of an alignment, split by the type of operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CostBreakdown {
+    pub matches: I,
+    pub substitutions: I,
+    pub sub_cost: Cost,
+    /// The number of maximal runs of insertions or deletions.
+    pub gap_opens: I,
+    pub open_cost: Cost,
+    /// The total length of all gaps.
+    pub gap_extends: I,
+    pub extend_cost: Cost,
+}
+
+impl CostBreakdown {
+    /// Split the cost of `cigar` under `cm`.
+    ///
+    /// Each run of insertions or deletions is one gap, made in the cheapest
+    /// layer for its length: either the linear indel cost, which has no open
+    /// cost, or one of the affine layers, which adds its own open cost.
+    /// When `cm` has no substitution cost, each substitution is costed as one
+    /// insertion and one deletion.
+    ///
+    /// ```
+    /// # use pa_affine_types::*;
+    /// # use pa_types::*;
+    /// let mut cigar = Cigar::default();
+    /// for op in [CigarOp::Match, CigarOp::Sub, CigarOp::Ins, CigarOp::Ins, CigarOp::Match] {
+    ///     cigar.push(op);
+    /// }
+    /// let cm = AffineCost::affine(3, 5, 1);
+    /// let breakdown = CostBreakdown::new(&cigar, &cm);
+    /// assert_eq!((breakdown.matches, breakdown.substitutions, breakdown.gap_opens), (2, 1, 1));
+    /// assert_eq!((breakdown.sub_cost, breakdown.open_cost, breakdown.extend_cost), (3, 5, 2));
+    /// assert_eq!(breakdown.cost(), 10);
+    ///
+    /// // Short gaps use the linear cost, long gaps the affine layer.
+    /// let cm = AffineCost::linear_affine(1, 2, 4, 1);
+    /// let breakdown = CostBreakdown::new(&cigar, &cm);
+    /// assert_eq!((breakdown.open_cost, breakdown.extend_cost), (0, 4));
+    /// let mut long = Cigar::default();
+    /// for _ in 0..5 {
+    ///     long.push(CigarOp::Del);
+    /// }
+    /// let breakdown = CostBreakdown::new(&long, &cm);
+    /// assert_eq!((breakdown.open_cost, breakdown.extend_cost), (4, 5));
+    ///
+    /// // Without substitutions, a substitution is an insertion and a deletion.
+    /// let cm = AffineCost::new(None, Some(1), Some(2), []);
+    /// assert_eq!(CostBreakdown::new(&cigar, &cm).sub_cost, 3);
+    /// ```
+    pub fn new<const N: usize>(cigar: &Cigar, cm: &AffineCost<N>) -> Self {
+        let mut breakdown = Self::default();
+        let mut ops = cigar.ops.iter().peekable();
+        while let Some(el) = ops.next() {
+            let mut cnt = el.cnt;
+            // Merge adjacent elements of the same type into a single gap.
+            while let Some(next) = ops.next_if(|next| next.op == el.op) {
+                cnt += next.cnt;
+            }
+            let (linear, layer_type) = match el.op {
+                CigarOp::Match => {
+                    breakdown.matches += cnt;
+                    continue;
+                }
+                CigarOp::Sub => {
+                    let sub = cm.sub.unwrap_or_else(|| {
+                        cm.gap_cost(Pos(0, 0), Pos(0, 1)) + cm.gap_cost(Pos(0, 0), Pos(1, 0))
+                    });
+                    breakdown.substitutions += cnt;
+                    breakdown.sub_cost += sub * cnt as Cost;
+                    continue;
+                }
+                CigarOp::Ins => (cm.ins, AffineLayerType::InsertLayer),
+                CigarOp::Del => (cm.del, AffineLayerType::DeleteLayer),
+            };
+            // (open, extend) cost of the gap in each layer that can make it.
+            // On ties, the linear cost is preferred.
+            let (open, extend) = linear
+                .map(|c| (0, c * cnt as Cost))
+                .into_iter()
+                .chain(
+                    cm.affine
+                        .iter()
+                        .filter(|l| l.affine_type.base() == layer_type)
+                        .map(|l| (l.open, l.extend * cnt as Cost)),
+                )
+                .min_by_key(|&(open, extend)| open + extend)
+                .expect("The cost model does not allow this gap.");
+            breakdown.gap_opens += 1;
+            breakdown.gap_extends += cnt;
+            breakdown.open_cost += open;
+            breakdown.extend_cost += extend;
+        }
+        breakdown
+    }
+
+    /// The total cost.
+    pub fn cost(&self) -> Cost {
+        self.sub_cost + self.open_cost + self.extend_cost
+    }
+}
+
+/// Render the alignment of `a` and `b` given by `cigar` as three stacked lines:
+/// `a` with gaps, a ruler, and `b` with gaps. The ruler has `|` for matches,
+/// `X` for substitutions, and a space for gaps. Lines are wrapped at `width`
+/// columns, with an empty line between blocks.
+///
+/// ```
+/// # use pa_affine_types::*;
+/// # use pa_types::*;
+/// let mut cigar = Cigar::default();
+/// for op in "====I==X=".chars() {
+///     cigar.push(match op {
+///         '=' => CigarOp::Match,
+///         'X' => CigarOp::Sub,
+///         _ => CigarOp::Ins,
+///     });
+/// }
+/// assert_eq!(
+///     pretty_alignment(&cigar, b"ACGTACGT", b"ACGTAACCT", 80),
+///     "ACGT-ACGT\n|||| ||X|\nACGTAACCT\n"
+/// );
+/// assert_eq!(
+///     pretty_alignment(&cigar, b"ACGTACGT", b"ACGTAACCT", 4),
+///     "ACGT\n||||\nACGT\n\n-ACG\n ||X\nAACC\n\nT\n|\nT\n"
+/// );
+/// ```
+pub fn pretty_alignment(cigar: &Cigar, a: Seq, b: Seq, width: usize) -> String {
+    assert!(width > 0);
+    let mut rows = [vec![], vec![], vec![]];
+    let (mut i, mut j) = (0, 0);
+    for el in &cigar.ops {
+        for _ in 0..el.cnt {
+            let col = match el.op {
+                CigarOp::Match | CigarOp::Sub => {
+                    i += 1;
+                    j += 1;
+                    let ruler = if el.op == CigarOp::Match { b'|' } else { b'X' };
+                    [a[i - 1], ruler, b[j - 1]]
+                }
+                CigarOp::Ins => {
+                    j += 1;
+                    [b'-', b' ', b[j - 1]]
+                }
+                CigarOp::Del => {
+                    i += 1;
+                    [a[i - 1], b' ', b'-']
+                }
+            };
+            for (row, c) in rows.iter_mut().zip(col) {
+                row.push(c);
+            }
+        }
+    }
+    assert_eq!((i, j), (a.len(), b.len()), "Cigar does not cover a and b.");
+
+    let mut out = String::new();
+    for k in (0..rows[0].len()).step_by(width) {
+        if k > 0 {
+            out.push('\n');
+        }
+        for row in &rows {
+            let end = (k + width).min(row.len());
+            out += &String::from_utf8_lossy(&row[k..end]);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The position in the alignment of the reversed sequences that corresponds to
+/// `pos`, where `target` is `Pos(|a|, |b|)`. After `reverse_sequences`, the
+/// path of a cigar goes through `reverse_pos(pos, target)` for each `pos` on
+/// the original path.
+///
+/// ```
+/// # use pa_affine_types::*;
+/// # use pa_types::*;
+/// let target = Pos(6, 4);
+/// assert_eq!(reverse_pos(Pos(0, 0), target), target);
+/// assert_eq!(reverse_pos(Pos(2, 2), target), Pos(4, 2));
+/// assert_eq!(reverse_pos(reverse_pos(Pos(3, 1), target), target), Pos(3, 1));
+/// ```
+pub fn reverse_pos(Pos(i, j): Pos, target: Pos) -> Pos {
+    Pos(target.0 - i, target.1 - j)
+}
+
+/// Shift all indels in `cigar` as far left as possible, without changing the
+/// cost of the alignment. This is the normalization used for VCF.
+///
+/// A gap preceded by a match can move one position left when the character
+/// before the gap equals the last character in the gap. The match then moves
+/// to the end of the gap.
+///
+/// ```
+/// # use pa_affine_types::*;
+/// # use pa_types::*;
+/// let mut cigar = Cigar::default();
+/// for op in "====DD==".chars() {
+///     cigar.push(if op == '=' { CigarOp::Match } else { CigarOp::Del });
+/// }
+/// left_align(&mut cigar, b"ACACACGT", b"ACACGT");
+/// assert_eq!(cigar.to_string(), "2D6=");
+/// ```
+pub fn left_align(cigar: &mut Cigar, a: Seq, b: Seq) {
+    let mut ops = cigar
+        .ops
+        .iter()
+        .flat_map(|el| std::iter::repeat(el.op).take(el.cnt as usize))
+        .collect::<Vec<_>>();
+    // Shifting a gap can merge it with the gap before, which may then shift further.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let (mut i, mut j) = (0, 0);
+        let mut k = 0;
+        while k < ops.len() {
+            let op = ops[k];
+            if op == CigarOp::Match || op == CigarOp::Sub {
+                i += 1;
+                j += 1;
+                k += 1;
+                continue;
+            }
+            let len = ops[k..].iter().take_while(|&&o| o == op).count();
+            // The gap covers `seq[start..start + len]`.
+            let (seq, mut start) = if op == CigarOp::Del { (a, i) } else { (b, j) };
+            let mut s = k;
+            while s > 0 && ops[s - 1] == CigarOp::Match && seq[start - 1] == seq[start + len - 1] {
+                ops[s - 1..s + len].rotate_left(1);
+                s -= 1;
+                start -= 1;
+                changed = true;
+            }
+            if op == CigarOp::Del {
+                i += len;
+            } else {
+                j += len;
+            }
+            k += len;
+        }
+        assert_eq!((i, j), (a.len(), b.len()), "Cigar does not cover a and b.");
+    }
+    let mut aligned = Cigar::default();
+    for op in ops {
+        aligned.push(op);
+    }
+    *cigar = aligned;
+}
+
 impl<'a> IntoIterator for &'a AffineCigar {
     type Item = &'a AffineCigarElem;
 
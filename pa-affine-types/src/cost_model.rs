@@ -1,7 +1,13 @@
-//! This module contains the `AffineCost` and `LinearCost` cost models.
+//! This module contains the `AffineCost` and `LinearCost` cost models, the
+//! quality-aware `QualityCost`, and `MatchReward` scores.
+//!
+//! All aligners assume that matches cost 0 and all other edges have a strictly
+//! positive cost. A* in particular relies on this for the admissibility and
+//! consistency of its heuristics. Scores with a reward for matches must be
+//! converted using `MatchReward`.
 
 use pa_types::*;
-use std::cmp::{max, min};
+use std::cmp::{max, min, Ordering};
 
 /// An affine layer can either correspond to an insertion or deletion.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -43,6 +49,41 @@ pub struct AffineLayerCosts {
 
 /// A full cost model consists of linear substitution/insertion/delete costs,
 /// and zero or more (N) affine layers.
+///
+/// Besides computing the cost of individual edits, it provides bounds on gaps
+/// (`gap_cost`, `extend_cost`, `max_ins_for_cost`, `max_del_for_cost`, and the
+/// `min_*`/`max_*` fields) that can be used to restrict the computed domain.
+///
+/// # Example
+///
+/// A custom cost model where long deletions are cheap:
+/// ```
+/// use pa_affine_types::*;
+/// use pa_types::Pos;
+///
+/// // Substitutions cost 2 and single indels cost 3. Deletions can also use
+/// // an affine layer with open cost 4 and extend cost 1.
+/// let cm = AffineCost::new(
+///     Some(2),
+///     Some(3),
+///     Some(3),
+///     [AffineLayerCosts {
+///         affine_type: DeleteLayer,
+///         open: 4,
+///         extend: 1,
+///     }],
+/// );
+/// assert_eq!(cm.min_ins_extend, 3);
+/// assert_eq!(cm.min_del_extend, 1);
+///
+/// // With a budget of 10, at most 3 insertions or 6 deletions fit.
+/// assert_eq!(cm.max_ins_for_cost(10), 3);
+/// assert_eq!(cm.max_del_for_cost(10), 6);
+///
+/// // Deleting 5 characters costs 4 + 5*1, or 5*1 when the gap is already open.
+/// assert_eq!(cm.gap_cost(Pos(0, 0), Pos(5, 0)), 9);
+/// assert_eq!(cm.extend_cost(Pos(0, 0), Pos(5, 0)), 5);
+/// ```
 // The constructure is private to this module.
 #[non_exhaustive]
 #[derive(Clone, Debug, Copy, PartialEq)]
@@ -61,6 +102,10 @@ pub struct AffineCost<const N: usize> {
 
     /// Extra fields derived from the affine layers.
     /// We store them so we do not have to recompute them all the time.
+    ///
+    /// Each is the minimum or maximum of the open, extend, or open+extend
+    /// cost over all insertion or deletion layers, including the linear
+    /// `ins`/`del` cost as a layer with open cost 0.
     pub min_ins_open: Cost,
     pub max_ins_open: Cost,
     pub min_del_open: Cost,
@@ -227,6 +272,8 @@ impl AffineCost<4> {
 }
 
 impl<const N: usize> AffineCost<N> {
+    /// Panics unless all allowed costs are strictly positive.
+    /// Matches always cost 0.
     pub fn new(
         sub: Option<Cost>,
         ins: Option<Cost>,
@@ -308,6 +355,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// The cost of aligning `a` to `b`: 0 for a match, and `sub` otherwise.
     #[inline]
     pub fn sub_cost(&self, a: u8, b: u8) -> Option<Cost> {
         if a == b {
@@ -320,6 +368,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// `f(sub)`, or `default` when substitutions are not allowed.
     #[inline]
     pub fn sub_or<U, F>(&self, default: U, f: F) -> U
     where
@@ -328,6 +377,7 @@ impl<const N: usize> AffineCost<N> {
         self.sub.map_or(default, f)
     }
 
+    /// `f(sub_cost(a, b))`, or `default` when `a != b` and substitutions are not allowed.
     #[inline]
     pub fn sub_cost_or<U, F>(&self, a: u8, b: u8, default: U, f: F) -> U
     where
@@ -340,6 +390,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// `f(ins)`, or `default` when linear insertions are not allowed.
     #[inline]
     pub fn ins_or<U, F>(&self, default: U, f: F) -> U
     where
@@ -348,6 +399,7 @@ impl<const N: usize> AffineCost<N> {
         self.ins.map_or(default, f)
     }
 
+    /// `f(del)`, or `default` when linear deletions are not allowed.
     #[inline]
     pub fn del_or<U, F>(&self, default: U, f: F) -> U
     where
@@ -356,6 +408,7 @@ impl<const N: usize> AffineCost<N> {
         self.del.map_or(default, f)
     }
 
+    /// Calls `f(open, extend)` for each way to insert characters.
     /// NOTE: This also includes the linear insert cost.
     #[inline]
     pub fn for_ins(&self, mut f: impl FnMut(Cost, Cost)) {
@@ -369,6 +422,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// Calls `f(open, extend)` for each way to delete characters.
     /// NOTE: This also includes the linear delete cost.
     #[inline]
     pub fn for_del(&self, mut f: impl FnMut(Cost, Cost)) {
@@ -382,6 +436,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// The maximum number of characters that can be inserted at cost at most `s`.
     /// Returns 0 when insertions are not possible.
     #[inline]
     pub fn max_ins_for_cost(&self, s: Cost) -> I {
@@ -390,6 +445,7 @@ impl<const N: usize> AffineCost<N> {
         d
     }
 
+    /// The maximum number of characters that can be deleted at cost at most `s`.
     /// Returns 0 when deletions are not possible.
     #[inline]
     pub fn max_del_for_cost(&self, s: Cost) -> I {
@@ -441,6 +497,7 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// The cigar operation corresponding to the given affine layer.
     #[inline]
     pub fn to_cigar(&self, layer: usize) -> CigarOp {
         match self.affine[layer].affine_type {
@@ -449,6 +506,23 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// An upper bound on the cost of aligning `a` and `b`: the cost of
+    /// deleting all of `a` and inserting all of `b`.
+    /// Returns `Cost::MAX` when the required kind of indel is not allowed.
+    pub fn max_possible_cost(&self, a: Seq, b: Seq) -> Cost {
+        let can_ins = self.ins.is_some() || self.affine.iter().any(|l| l.affine_type.is_insert());
+        let can_del = self.del.is_some() || self.affine.iter().any(|l| l.affine_type.is_delete());
+        if (!a.is_empty() && !can_del) || (!b.is_empty() && !can_ins) {
+            return Cost::MAX;
+        }
+        let n = a.len() as I;
+        let m = b.len() as I;
+        self.gap_cost(Pos(0, 0), Pos(n, 0)) + self.gap_cost(Pos(0, 0), Pos(0, m))
+    }
+
+    /// A lower bound on the cost of going from `s` to `t`: the cheapest way
+    /// to open and extend a gap of the difference in diagonals.
+    /// Panics when the required kind of indel is not allowed.
     #[inline]
     pub fn gap_cost(&self, s: Pos, t: Pos) -> Cost {
         let delta = (t.0 - s.0) as isize - (t.1 - s.1) as isize;
@@ -486,6 +560,33 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 
+    /// The number of diagonals by which a path of cost at most `f_max` from
+    /// the start to `target` can leave the band between their diagonals.
+    /// This is negative when no such path exists.
+    ///
+    /// Leaving the band by `x` diagonals takes `x` insertions and `x` deletions
+    /// on top of the gap to `target`. This also opens a gap in the direction
+    /// the gap to `target` does not use, or in both directions when `target`
+    /// is on the main diagonal.
+    pub fn extra_diagonals_for_cost(&self, f_max: Cost, target: Pos) -> I {
+        // We subtract the cost needed to bridge the gap from the start to the end.
+        let s = f_max - self.gap_cost(Pos(0, 0), target);
+        // Each extra diagonal costs one insertion and one deletion.
+        let extend = self.min_ins_extend + self.min_del_extend;
+        if s < 0 {
+            return s / extend;
+        }
+        let open = match target.1.cmp(&target.0) {
+            // The target needs insertions, so leaving the band opens a deletion.
+            Ordering::Greater => self.min_del_open,
+            Ordering::Less => self.min_ins_open,
+            Ordering::Equal => self.min_ins_open.saturating_add(self.min_del_open),
+        };
+        max(s.saturating_sub(open), 0) / extend
+    }
+
+    /// As `gap_cost`, but without the open cost, i.e. when already in an
+    /// affine layer of the right type.
     #[inline]
     pub fn extend_cost(&self, s: Pos, t: Pos) -> Cost {
         let delta = (t.0 - s.0) as isize - (t.1 - s.1) as isize;
@@ -523,3 +624,112 @@ impl<const N: usize> AffineCost<N> {
         }
     }
 }
+
+/// Panics when a search for the cost gave no alignment at threshold `s >= s_max`.
+///
+/// `s_max` is an upper bound like `max_possible_cost`, i.e. the cost of an
+/// actual alignment, so any aligner must find one at that threshold. Failing
+/// to do so is a bug in `aligner`, and increasing `s` further would loop forever.
+#[track_caller]
+pub fn check_search_bound(aligner: &str, s: Cost, s_max: Cost) {
+    if s >= s_max {
+        panic!("No alignment found by {aligner} for s={s}, but the cost is at most s_max={s_max}.");
+    }
+}
+
+/// Linear costs where substitutions against low-quality bases of `b` are cheaper.
+///
+/// The substitution cost at a base of Phred quality `q` is `sub * q / max_quality`,
+/// rounded up, and the full `sub` for `q >= max_quality`. Use a `sub` larger
+/// than 1 to have some room for scaling.
+/// Mismatches cost at least 1, even at quality 0, so that they never cost the
+/// same as a match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityCost {
+    /// The costs at fully trusted bases.
+    pub cm: AffineCost<0>,
+    /// The Phred quality from which bases are fully trusted.
+    pub max_quality: u8,
+}
+
+impl QualityCost {
+    pub fn new(cm: AffineCost<0>, max_quality: u8) -> Self {
+        assert!(max_quality > 0);
+        Self { cm, max_quality }
+    }
+
+    /// The cost of aligning `a` to `b`, where `b` has Phred quality `q`.
+    #[inline]
+    pub fn sub_cost(&self, a: u8, b: u8, q: u8) -> Option<Cost> {
+        if a == b {
+            return Some(0);
+        }
+        let q = min(q, self.max_quality) as Cost;
+        let max_quality = self.max_quality as Cost;
+        self.cm
+            .sub
+            .map(|sub| max((sub * q).div_ceil(max_quality), 1))
+    }
+}
+
+/// Linear scores where matches are rewarded by `match_bonus`, and
+/// substitutions and indels are penalized by `sub` and `indel`.
+///
+/// For global alignment, maximizing this score is equivalent to minimizing the
+/// non-negative costs of `to_cost`: each match or substitution consumes two
+/// characters and each indel one, so the total reward `match_bonus * (|a|+|b|) / 2`
+/// can be distributed over the edits. The conversion only holds when both
+/// sequences are aligned completely, so rewards are supported by the global
+/// aligners (NW, DT, A*PA, A*PA2), but not by overlap alignment or other modes
+/// with free ends, which need plain non-negative costs.
+///
+/// ```
+/// use pa_affine_types::*;
+///
+/// // +1 per match, -1 per substitution, -2 per indel.
+/// let score = MatchReward::new(1, 1, 2);
+/// let cm = score.to_cost();
+/// assert_eq!((cm.sub, cm.ins, cm.del), (Some(4), Some(5), Some(5)));
+/// // Aligning ACGT to AGT with 3 matches and 1 deletion costs 5.
+/// assert_eq!(score.score(4, 3, 5), 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchReward {
+    pub match_bonus: Cost,
+    pub sub: Cost,
+    pub indel: Cost,
+}
+
+impl MatchReward {
+    /// Panics when a value is negative, or when an edit would have cost 0
+    /// after conversion, since that breaks A*.
+    pub fn new(match_bonus: Cost, sub: Cost, indel: Cost) -> Self {
+        assert!(
+            match_bonus >= 0 && sub >= 0 && indel >= 0,
+            "The match bonus and penalties must be non-negative."
+        );
+        assert!(
+            match_bonus + sub > 0 && match_bonus + 2 * indel > 0,
+            "Substitutions and indels must have a positive cost after conversion."
+        );
+        Self {
+            match_bonus,
+            sub,
+            indel,
+        }
+    }
+
+    /// The equivalent costs, scaled by 2 to keep them integer.
+    pub fn to_cost(&self) -> AffineCost<0> {
+        let indel = self.match_bonus + 2 * self.indel;
+        AffineCost::linear(2 * (self.match_bonus + self.sub), indel)
+    }
+
+    /// The score of a global alignment of sequences of lengths `len_a` and
+    /// `len_b`, from its cost under `to_cost`.
+    pub fn score(&self, len_a: usize, len_b: usize, cost: Cost) -> Cost {
+        let total = self.match_bonus * (len_a + len_b) as Cost - cost;
+        assert!(total % 2 == 0, "Cost {cost} is not a cost of `to_cost`.");
+        total / 2
+    }
+}
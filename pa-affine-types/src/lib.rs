@@ -1,4 +1,5 @@
 use pa_types::{Cost, Pos, Seq, I};
+use serde::{Deserialize, Serialize};
 
 pub mod cigar;
 pub mod cost_model;
@@ -27,6 +28,25 @@ impl State {
     }
 }
 
+/// Which of an insertion and a deletion traceback prefers when both are optimal.
+///
+/// Matches always come first, so the default order is M > I > D, and `Del`
+/// gives M > D > I. Where substitutions go relative to indels is up to the
+/// aligner, e.g. `TracebackPolicy` in A*PA2.
+///
+/// Swapping `a` and `b` turns insertions into deletions. So with symmetric
+/// costs, tracing `(b, a)` with the opposite tie-break gives the cigar of
+/// `(a, b)` with insertions and deletions swapped, while the same tie-break
+/// in general places the indels differently.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndelTieBreak {
+    /// Try insertions before deletions.
+    #[default]
+    Ins,
+    /// Try deletions before insertions.
+    Del,
+}
+
 /// Generic pairwise global alignment interface.
 pub trait AffineAligner: std::fmt::Debug {
     /// An alignment of sequences `a` and `b`.
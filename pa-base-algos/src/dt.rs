@@ -154,6 +154,7 @@ impl<const N: usize, V: VisualizerT, H: Heuristic> DiagonalTransition<N, V, H> {
             left_buf,
             top_buf,
             bot_buf,
+            retained: None,
         }
     }
 }
@@ -202,6 +203,15 @@ pub struct DTInstance<'a, const N: usize, V: VisualizerT, H: Heuristic> {
     /// FIXME: For affine GapClose costs, we add the max open cost to the substitution cost.
     top_buf: Fr,
     bot_buf: Fr,
+
+    /// The fronts kept by `cost_for_bounded_dist` for the next call with a
+    /// larger bound, together with the first layer that still has to be computed.
+    ///
+    /// Without a heuristic, `f_max` only shrinks the diagonal range by the
+    /// gap extension cost. The layers before the first layer that was
+    /// actually shrunk are thus exact for any bound, and are reused instead
+    /// of recomputed.
+    retained: Option<(Cost, Fronts<N>)>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -604,6 +614,15 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic> DTInstance<'a, N, V, H> {
                         );
                         let val = &mut fronts[g as Fr].layer_mut(layer)[d];
                         *val = max(*val, fr);
+                        if let Some(l) = layer
+                            && *val >= 0
+                        {
+                            self.v.borrow_mut().expand_affine(
+                                offset + fr_to_pos(d, *val),
+                                self.params.cm.affine[l].affine_type,
+                                g,
+                            );
+                        }
                     });
                 }
             }
@@ -636,6 +655,19 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic> DTInstance<'a, N, V, H> {
                         );
                         let val = &mut fronts[g as Fr].layer_mut(layer)[d];
                         *val = max(*val, fr);
+                        if let Some(l) = layer
+                            && *val >= 0
+                        {
+                            self.v.borrow_mut().expand_affine(
+                                offset
+                                    + fr_to_pos(
+                                        self.a.len() as Fr - self.b.len() as Fr - d,
+                                        max_fr - *val,
+                                    ),
+                                self.params.cm.affine[l].affine_type,
+                                g,
+                            );
+                        }
                     });
                 }
             }
@@ -1194,28 +1226,50 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic> DTInstance<'a, N, V, H> {
     /// The cost-only version uses linear memory.
     ///
     /// In particular, the number of fronts is max(sub, ins, del)+1.
+    ///
+    /// When called repeatedly with increasing `f_max`, as in an exponential
+    /// search, the computation resumes from the layers retained by the
+    /// previous call (see `retained`).
     fn cost_for_bounded_dist(&mut self, f_max: Option<Cost>) -> Option<Cost> {
-        self.v
-            .borrow_mut()
-            .expand::<NoCostI>(Pos(0, 0), 0, f_max.unwrap_or(0), None);
-        let mut fronts = match self.init_fronts(
-            f_max.unwrap_or(0),
-            Pos(0, 0),
-            None,
-            None,
-            Direction::Forward,
-        ) {
-            Ok(fronts) => fronts,
-            Err(r) => return Some(r.0),
+        let (start, mut fronts) = match self.retained.take() {
+            Some(retained) if H::IS_DEFAULT => retained,
+            _ => {
+                self.v
+                    .borrow_mut()
+                    .expand::<NoCostI>(Pos(0, 0), 0, f_max.unwrap_or(0), None);
+                match self.init_fronts(
+                    f_max.unwrap_or(0),
+                    Pos(0, 0),
+                    None,
+                    None,
+                    Direction::Forward,
+                ) {
+                    Ok(fronts) => (1, fronts),
+                    Err(r) => return Some(r.0),
+                }
+            }
         };
 
-        for s in 1.. {
+        // Whether a layer was shrunk by `f_max`. From then on, fronts can not be reused.
+        let mut shrunk = false;
+        for s in start.. {
             if let Some(f_max) = f_max
                 && s > f_max
             {
+                if H::IS_DEFAULT && !shrunk {
+                    self.retained = Some((s, fronts));
+                }
                 return None;
             }
             let range = self.d_range(s, f_max, &fronts);
+            if H::IS_DEFAULT
+                && f_max.is_some()
+                && !shrunk
+                && range != self.d_range(s, None, &fronts)
+            {
+                shrunk = true;
+                self.retained = Some((s, fronts.clone()));
+            }
             if range.is_empty() {
                 return None;
             }
@@ -1390,11 +1444,17 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic> DTInstance<'a, N, V, H> {
 }
 
 impl<const N: usize, V: VisualizerT, H: Heuristic> DiagonalTransition<N, V, H> {
+    /// The cost only, without traceback.
+    ///
+    /// Only the last `max(sub, ins, del)+1` fronts are kept, so this uses
+    /// `O(s)` memory instead of the `O(s^2)` needed by `align`.
     pub fn cost(&mut self, a: Seq, b: Seq) -> Cost {
         let v = &RefCell::new(self.v.build(a, b));
         let mut dt = self.build(a, b, v);
         let cost = if self.use_gap_cost_heuristic == GapCostHeuristic::Enable || !H::IS_DEFAULT {
-            exponential_search(0, self.cm.gap_cost(Pos(0, 0), Pos::target(a, b)), 2., |s| {
+            let s0 = self.cm.gap_cost(Pos(0, 0), Pos::target(a, b));
+            let s_max = self.cm.max_possible_cost(a, b);
+            exponential_search("DT", 0, s0, 2., s_max, |s| {
                 dt.cost_for_bounded_dist(Some(s)).map(|c| (c, c))
             })
             .1
@@ -1430,9 +1490,11 @@ impl<const N: usize, V: VisualizerT, H: Heuristic> DiagonalTransition<N, V, H> {
                 cc = dt.align_local_band_doubling();
             } else if self.use_gap_cost_heuristic == GapCostHeuristic::Enable || !H::IS_DEFAULT {
                 cc = exponential_search(
+                    "DT",
                     0,
                     self.cm.gap_cost(Pos(0, 0), Pos::target(a, b)),
                     2.,
+                    self.cm.max_possible_cost(a, b),
                     |s| dt.align_for_bounded_dist(Some(s)).map(|x @ (c, _)| (c, x)),
                 )
                 .1;
@@ -1444,6 +1506,8 @@ impl<const N: usize, V: VisualizerT, H: Heuristic> DiagonalTransition<N, V, H> {
         }
     }
 
+    /// As `cost`, but returns `None` when the cost is larger than `f_max`.
+    /// Uses `O(s)` memory.
     pub fn cost_for_bounded_dist(&mut self, a: Seq, b: Seq, f_max: Cost) -> Option<Cost> {
         self.build(a, b, &RefCell::new(self.v.build(a, b)))
             .cost_for_bounded_dist(Some(f_max))
@@ -1473,3 +1537,49 @@ impl<const N: usize, V: VisualizerT, H: Heuristic> Aligner for DiagonalTransitio
         (cost, Some(cigar.into()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::nw::NW;
+
+    /// The linear-memory cost-only path agrees with the traced alignment.
+    #[test]
+    fn cost_only() {
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+            let cm = AffineCost::affine(1, 2, 1);
+            let mut dt =
+                DiagonalTransition::new(cm, GapCostHeuristic::Disable, NoCost, false, NoVis);
+            let cost = dt.cost(&a, &b);
+            assert_eq!(cost, dt.align(&a, &b).0, "{params:?}");
+            assert_eq!(cost, NW::new(cm, false, false).cost(&a, &b), "{params:?}");
+            assert_eq!(dt.cost_for_bounded_dist(&a, &b, cost), Some(cost));
+            if cost > 0 {
+                assert_eq!(dt.cost_for_bounded_dist(&a, &b, cost - 1), None);
+            }
+        }
+    }
+
+    /// Increasing the bound on the same instance resumes from the retained fronts.
+    #[test]
+    fn reuse_fronts() {
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+            for gap_cost in [GapCostHeuristic::Disable, GapCostHeuristic::Enable] {
+                let cm = AffineCost::affine(1, 2, 1);
+                let params_dt = DiagonalTransition::new(cm, gap_cost, NoCost, false, NoVis);
+                let cost = NW::new(cm, false, false).cost(&a, &b);
+                let v = &RefCell::new(NoVis.build(&a, &b));
+                let mut dt = params_dt.build(&a, &b, v);
+                for f_max in 0.. {
+                    let c = dt.cost_for_bounded_dist(Some(f_max));
+                    if f_max < cost {
+                        assert_eq!(c, None, "{params:?}");
+                    } else {
+                        assert_eq!(c, Some(cost), "{params:?}");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -59,6 +59,7 @@ where
 
 /// `Fronts` is a vector of fronts, possibly with a buffer layer at the top.
 /// TODO: Add `fronts.rotate()` and `fronts.grow()` functions to add a new front.
+#[derive(Clone)]
 pub struct Fronts<const N: usize, T, I> {
     pub fronts: Vec<Front<N, T, I>>,
     /// The default value.
@@ -1,15 +1,22 @@
 #![feature(let_chains, step_trait, int_roundings, portable_simd)]
 
-use pa_types::Cost;
+use pa_affine_types::check_search_bound;
+use pa_types::{Cost, I};
 use serde::{Deserialize, Serialize};
 use std::cmp::{max, min};
 
 mod edit_graph;
 mod front;
+mod linear_dp;
 
 pub mod cli;
 pub mod dt;
+pub mod max_gap;
 pub mod nw;
+pub mod overlap;
+pub mod quality;
+pub mod second_best;
+pub mod semi_global;
 
 // #[cfg(test)]
 // mod tests;
@@ -51,17 +58,21 @@ const PRINT: bool = false;
 /// 2ns
 /// in case all errors are at the end and runtime is O(ng) per guess:
 /// 4.8 ns, only slightly worse than 4ns.
+///
+/// `s_max` is an upper bound on the cost, such as `AffineCost::max_possible_cost`.
+/// Panics when no alignment is found for `s_max`, instead of looping forever.
+/// `aligner` names the aligner in that message.
 fn exponential_search<T>(
+    aligner: &str,
     offset: Cost,
     s0: Cost,
     factor: f32,
+    s_max: Cost,
     mut f: impl FnMut(Cost) -> Option<(Cost, T)>,
 ) -> (Cost, T) {
     let mut last_s = -1;
-    let mut s = offset + s0;
+    let mut s = min(offset + s0, s_max);
     let mut maxs = Cost::MAX;
-    // TODO: Fix the potential infinite loop here.
-    //
     // Sanity checks:
     // - Once the answer is found, this should be larger than all previous thresholds.
     // - Once a value for maxs has been found, all subsequent larger values of s
@@ -85,22 +96,24 @@ fn exponential_search<T>(
                 "A solution {maxs} was found for a previous s<={last_s}, but not for current s={s}"
             );
         }
+        check_search_bound(aligner, s, s_max);
         last_s = s;
         s = max((factor * (s - offset) as f32).ceil() as Cost, 1) + offset;
-        s = min(s, maxs);
+        s = min(s, min(maxs, s_max));
     }
 }
 
+/// As `exponential_search`, but increases `s` by `delta` each iteration.
 fn linear_search<T>(
+    aligner: &str,
     s0: Cost,
     delta: Cost,
+    s_max: Cost,
     mut f: impl FnMut(Cost) -> Option<(Cost, T)>,
 ) -> (Cost, T) {
     let mut last_s = -1;
-    let mut s = s0;
+    let mut s = min(s0, s_max);
     let mut maxs = Cost::MAX;
-    // TODO: Fix the potential infinite loop here.
-    //
     // Sanity checks:
     // - Once the answer is found, this should be larger than all previous thresholds.
     // - Once a value for maxs has been found, all subsequent larger values of s
@@ -124,8 +137,9 @@ fn linear_search<T>(
                 "A solution {maxs} was found for a previous s<={last_s}, but not for current s={s}"
             );
         }
+        check_search_bound(aligner, s, s_max);
         last_s = s;
-        s = min(s + delta, maxs);
+        s = min(s + delta, min(maxs, s_max));
     }
 }
 
@@ -209,6 +223,37 @@ impl<H> Domain<H> {
     }
 }
 
+/// Which parts of the sequences must be aligned.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlignmentMode {
+    /// Align `a` and `b` end-to-end.
+    #[default]
+    Global,
+    /// Align a suffix of `a` to a prefix of `b`, or a suffix of `b` to a prefix of `a`.
+    /// The overlap contains at least `min_overlap` characters of both sequences.
+    /// See `overlap::OverlapAligner`.
+    Overlap { min_overlap: I },
+    /// Align all of `b` (the query) to a substring of `a` (the reference).
+    /// The ends of `b` may be clipped as allowed by `clip_start` and `clip_end`.
+    /// See `semi_global::SemiGlobalAligner`.
+    SemiGlobal {
+        clip_start: ClipPolicy,
+        clip_end: ClipPolicy,
+    },
+}
+
+/// Whether an end of the query may be left unaligned in semi-global alignment.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClipPolicy {
+    /// The query end must be aligned.
+    #[default]
+    Forbidden,
+    /// The query end may be clipped at no cost.
+    Free,
+    /// Clipping the query end costs a fixed penalty, independent of the clipped length.
+    Penalized(Cost),
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum DoublingStart {
     Zero,
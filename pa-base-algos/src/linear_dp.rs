@@ -0,0 +1,172 @@
+//! A plain quadratic DP over linear costs, shared by the aligners that `NW`
+//! does not support: `overlap`, `semi_global`, `second_best` and `quality`.
+//!
+//! Where paths may start is given by a `start` value per state, and the caller
+//! picks the end state from the filled matrix. Time and memory are `O(nm)`.
+//! States hold a `Value`: a plain `Cost`, or a cost with a tie-breaker.
+//!
+//! NOTE: This does not (yet) use bitpacking, band doubling or a heuristic.
+use pa_types::*;
+use std::cmp::min;
+
+/// The cost of unreachable states.
+/// All costs are clamped to `INF`, so that adding two of them never overflows.
+pub(crate) const INF: Cost = Cost::MAX / 2;
+
+/// `x + y`, clamped to `INF`.
+#[inline]
+pub(crate) fn add(x: Cost, y: Cost) -> Cost {
+    min(x.saturating_add(y), INF)
+}
+
+/// The value of a DP state. Smaller is better.
+pub(crate) trait Value: Copy + Ord + std::fmt::Debug {
+    /// The value of unreachable states.
+    const INF: Self;
+    fn cost(self) -> Cost;
+    /// Extend a path by an edge of cost `c`.
+    fn add(self, c: Cost) -> Self;
+}
+
+impl Value for Cost {
+    const INF: Self = INF;
+    fn cost(self) -> Cost {
+        self
+    }
+    fn add(self, c: Cost) -> Self {
+        add(self, c)
+    }
+}
+
+/// A cost with a tie-breaker that is kept along the path,
+/// such as the number of clipped characters at its start.
+impl Value for (Cost, I) {
+    const INF: Self = (INF, 0);
+    fn cost(self) -> Cost {
+        self.0
+    }
+    fn add(self, c: Cost) -> Self {
+        match add(self.0, c) {
+            INF => Self::INF,
+            cost => (cost, self.1),
+        }
+    }
+}
+
+/// Extend the `cigar` of `a[start.0..end.0]` and `b[start.1..end.1]` to all of
+/// `a` and `b`, with deletions for the skipped parts of `a` and insertions for
+/// the skipped parts of `b`.
+pub(crate) fn extend_cigar(cigar: &Cigar, start: Pos, end: Pos, a: Seq, b: Seq) -> Cigar {
+    let ops = [(CigarOp::Del, start.0), (CigarOp::Ins, start.1)]
+        .into_iter()
+        .chain(cigar.ops.iter().map(|e| (e.op, e.cnt)))
+        .chain([
+            (CigarOp::Ins, b.len() as I - end.1),
+            (CigarOp::Del, a.len() as I - end.0),
+        ]);
+    let mut full = Cigar { ops: vec![] };
+    for (op, cnt) in ops {
+        for _ in 0..cnt {
+            full.push(op);
+        }
+    }
+    full
+}
+
+pub(crate) struct LinearDp<'a, V, Sub, Start> {
+    a: Seq<'a>,
+    b: Seq<'a>,
+    ins: Cost,
+    del: Cost,
+    /// `sub(i, j)`: the cost of aligning `a[i-1]` to `b[j-1]`, or `INF`.
+    sub: Sub,
+    /// `start(i, j)`: the value of starting a path in `(i, j)`, or `V::INF`.
+    start: Start,
+    /// `g[i][j]`: the value of the best path from a start to `(i, j)`.
+    pub g: Vec<Vec<V>>,
+}
+
+impl<'a, V, Sub, Start> LinearDp<'a, V, Sub, Start>
+where
+    V: Value,
+    Sub: Fn(usize, usize) -> Cost,
+    Start: Fn(usize, usize) -> V,
+{
+    /// Fill the DP matrix. `ins` and `del` are `INF` when not allowed.
+    /// States `(i, j)` with `forbidden(i, j)` are never visited.
+    pub fn new(
+        a: Seq<'a>,
+        b: Seq<'a>,
+        ins: Cost,
+        del: Cost,
+        sub: Sub,
+        start: Start,
+        forbidden: impl Fn(usize, usize) -> bool,
+    ) -> Self {
+        let (n, m) = (a.len(), b.len());
+        let (ins, del) = (min(ins, INF), min(del, INF));
+        let mut g = vec![vec![V::INF; m + 1]; n + 1];
+        for i in 0..=n {
+            for j in 0..=m {
+                if forbidden(i, j) {
+                    continue;
+                }
+                let mut c = start(i, j).add(0);
+                if i > 0 && j > 0 {
+                    c = min(c, g[i - 1][j - 1].add(sub(i, j)));
+                }
+                if i > 0 {
+                    c = min(c, g[i - 1][j].add(del));
+                }
+                if j > 0 {
+                    c = min(c, g[i][j - 1].add(ins));
+                }
+                g[i][j] = c;
+            }
+        }
+        Self {
+            a,
+            b,
+            ins,
+            del,
+            sub,
+            start,
+            g,
+        }
+    }
+
+    /// Trace back from `end` to the start of its path.
+    ///
+    /// Prefers a diagonal step, then an insertion, then a deletion, and only
+    /// starts the path when it can not be extended.
+    /// Returns the start state and the cigar from there to `end`.
+    pub fn trace(&self, end: (usize, usize)) -> ((usize, usize), Cigar) {
+        let g = &self.g;
+        let mut cigar = Cigar { ops: vec![] };
+        let (mut i, mut j) = end;
+        assert!(g[i][j].cost() < INF, "No path ends in {end:?}.");
+        loop {
+            let cur = g[i][j];
+            if i > 0 && j > 0 && cur == g[i - 1][j - 1].add((self.sub)(i, j)) {
+                cigar.push(if self.a[i - 1] == self.b[j - 1] {
+                    CigarOp::Match
+                } else {
+                    CigarOp::Sub
+                });
+                i -= 1;
+                j -= 1;
+            } else if j > 0 && cur == g[i][j - 1].add(self.ins) {
+                cigar.push(CigarOp::Ins);
+                j -= 1;
+            } else if i > 0 && cur == g[i - 1][j].add(self.del) {
+                cigar.push(CigarOp::Del);
+                i -= 1;
+            } else {
+                assert_eq!(cur, (self.start)(i, j).add(0));
+                break;
+            }
+        }
+        cigar.reverse();
+        ((i, j), cigar)
+    }
+}
@@ -0,0 +1,210 @@
+//! Global alignment where runs of insertions or deletions are at most `max_gap` long.
+//!
+//! NOTE: This is a plain quadratic DP over linear costs. Unlike `LinearDp`,
+//! it keeps separate layers for paths ending in a match, an insertion, or a
+//! deletion, as in affine alignment, so that gap runs can not be concatenated.
+use crate::linear_dp::{add, INF};
+use pa_affine_types::AffineCost;
+use pa_types::*;
+use std::{cmp::min, collections::VecDeque};
+
+/// The layers: paths ending in a match or substitution, in an insertion, and in a deletion.
+const MAT: usize = 0;
+const INS: usize = 1;
+const DEL: usize = 2;
+
+/// Aligner that forbids runs of more than `max_gap` insertions or deletions.
+#[derive(Debug, Clone)]
+pub struct MaxGapAligner {
+    pub cm: AffineCost<0>,
+    /// The maximal length of a run of insertions or deletions. `None` for no limit.
+    pub max_gap: Option<I>,
+}
+
+/// Sliding window minimum of `g[k] + (idx - k) * cost` over the last `max_gap`
+/// indices `k`. Keeps indices with increasing `key = g[k] - k * cost`.
+#[derive(Default, Clone)]
+struct Window {
+    deque: VecDeque<(usize, Cost)>,
+}
+
+impl Window {
+    fn push(&mut self, k: usize, key: Cost) {
+        while self.deque.back().is_some_and(|&(_, back)| back >= key) {
+            self.deque.pop_back();
+        }
+        self.deque.push_back((k, key));
+    }
+
+    fn min(&mut self, idx: usize, max_gap: usize, cost: Cost) -> Cost {
+        while self.deque.front().is_some_and(|&(k, _)| idx - k > max_gap) {
+            self.deque.pop_front();
+        }
+        self.deque
+            .front()
+            .map_or(INF, |&(_, key)| add(key, idx as Cost * cost))
+    }
+}
+
+impl MaxGapAligner {
+    pub fn new(cm: AffineCost<0>, max_gap: Option<I>) -> Self {
+        Self { cm, max_gap }
+    }
+
+    /// Globally align `a` and `b`.
+    /// Returns `None` when every alignment contains a gap longer than `max_gap`.
+    pub fn align(&self, a: Seq, b: Seq) -> Option<(Cost, Cigar)> {
+        let n = a.len();
+        let m = b.len();
+        let max_gap = self.max_gap.map_or(usize::MAX, |g| g as usize);
+        let sub = |i: usize, j: usize| self.cm.sub_cost_or(a[i - 1], b[j - 1], INF, |c| c);
+
+        // g[layer][i][j]: cost of the best path from (0, 0) to (i, j) ending in `layer`.
+        let mut g = [(); 3].map(|_| vec![vec![INF; m + 1]; n + 1]);
+        // For each column, the window of deletions ending in it.
+        let mut del_windows = vec![Window::default(); m + 1];
+        for i in 0..=n {
+            let mut ins_window = Window::default();
+            for j in 0..=m {
+                if i == 0 && j == 0 {
+                    g[MAT][0][0] = 0;
+                } else if i > 0 && j > 0 {
+                    let prev = min(
+                        g[MAT][i - 1][j - 1],
+                        min(g[INS][i - 1][j - 1], g[DEL][i - 1][j - 1]),
+                    );
+                    g[MAT][i][j] = add(prev, sub(i, j));
+                }
+                if let Some(ins) = self.cm.ins {
+                    g[INS][i][j] = ins_window.min(j, max_gap, ins);
+                    let start = min(g[MAT][i][j], g[DEL][i][j]);
+                    if start < INF {
+                        ins_window.push(j, start - j as Cost * ins);
+                    }
+                }
+                if let Some(del) = self.cm.del {
+                    g[DEL][i][j] = del_windows[j].min(i, max_gap, del);
+                    let start = min(g[MAT][i][j], g[INS][i][j]);
+                    if start < INF {
+                        del_windows[j].push(i, start - i as Cost * del);
+                    }
+                }
+            }
+        }
+        let cost = (0..3).map(|l| g[l][n][m]).min().unwrap();
+        if cost >= INF {
+            return None;
+        }
+
+        // Trace back to the start.
+        let mut cigar = Cigar { ops: vec![] };
+        let (mut i, mut j) = (n, m);
+        let mut layer = (0..3).find(|&l| g[l][i][j] == cost).unwrap();
+        while i > 0 || j > 0 {
+            let cur = g[layer][i][j];
+            match layer {
+                MAT => {
+                    let c = sub(i, j);
+                    cigar.push(if a[i - 1] == b[j - 1] {
+                        CigarOp::Match
+                    } else {
+                        CigarOp::Sub
+                    });
+                    i -= 1;
+                    j -= 1;
+                    layer = (0..3).find(|&l| g[l][i][j] + c == cur).unwrap();
+                }
+                INS => {
+                    let ins = self.cm.ins.unwrap();
+                    let (len, from) = (1..=min(max_gap, j))
+                        .flat_map(|len| [(len, MAT), (len, DEL)])
+                        .find(|&(len, l)| g[l][i][j - len] + len as Cost * ins == cur)
+                        .unwrap();
+                    for _ in 0..len {
+                        cigar.push(CigarOp::Ins);
+                    }
+                    j -= len;
+                    layer = from;
+                }
+                _ => {
+                    let del = self.cm.del.unwrap();
+                    let (len, from) = (1..=min(max_gap, i))
+                        .flat_map(|len| [(len, MAT), (len, INS)])
+                        .find(|&(len, l)| g[l][i - len][j] + len as Cost * del == cur)
+                        .unwrap();
+                    for _ in 0..len {
+                        cigar.push(CigarOp::Del);
+                    }
+                    i -= len;
+                    layer = from;
+                }
+            }
+        }
+        cigar.reverse();
+        Some((cost, cigar))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn longest_gap(cigar: &Cigar) -> I {
+        cigar
+            .ops
+            .iter()
+            .filter(|e| matches!(e.op, CigarOp::Ins | CigarOp::Del))
+            .map(|e| e.cnt)
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn unbounded_is_edit_distance() {
+        let (a, b) = pa_generate::generate_model(200, 0.1, pa_generate::ErrorModel::Uniform, 31415);
+        let (cost, cigar) = MaxGapAligner::new(AffineCost::unit(), None)
+            .align(&a, &b)
+            .unwrap();
+        assert_eq!(cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+        assert_eq!(cigar.verify(&CostModel::unit(), &a, &b), cost);
+    }
+
+    #[test]
+    fn gaps_are_bounded() {
+        for max_gap in 1..5 {
+            let (a, b) = pa_generate::generate_model(
+                200,
+                0.1,
+                pa_generate::ErrorModel::NoisyInsert,
+                max_gap as u64,
+            );
+            let aligner = MaxGapAligner::new(AffineCost::unit(), Some(max_gap));
+            let (cost, cigar) = aligner.align(&a, &b).unwrap();
+            assert!(cost >= triple_accel::levenshtein_exp(&a, &b) as Cost);
+            assert_eq!(cigar.verify(&CostModel::unit(), &a, &b), cost);
+            assert!(longest_gap(&cigar) <= max_gap);
+        }
+    }
+
+    #[test]
+    fn split_gap() {
+        let a = b"AAAAAAAA";
+        let b = b"AAAA";
+        let unbounded = MaxGapAligner::new(AffineCost::unit(), None);
+        assert_eq!(unbounded.align(a, b).unwrap().0, 4);
+        // The deletion must be split into runs of 2, separated by matches.
+        let (cost, cigar) = MaxGapAligner::new(AffineCost::unit(), Some(2))
+            .align(a, b)
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert!(longest_gap(&cigar) <= 2);
+        // Gaps of length 1 can still be interleaved with the 4 matches.
+        assert!(MaxGapAligner::new(AffineCost::unit(), Some(1))
+            .align(a, b)
+            .is_some());
+        // Without gaps, sequences of different length can not be aligned.
+        assert!(MaxGapAligner::new(AffineCost::unit(), Some(0))
+            .align(a, b)
+            .is_none());
+    }
+}
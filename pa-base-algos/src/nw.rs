@@ -18,8 +18,10 @@ mod bitpacking;
 mod front;
 
 use crate::nw::front::{IRange, JRange, NwFront, NwFronts};
+use crate::overlap::OverlapAligner;
+use crate::semi_global::SemiGlobalAligner;
 use crate::{exponential_search, Strategy, PRINT};
-use crate::{linear_search, Domain};
+use crate::{linear_search, AlignmentMode, Domain};
 use pa_affine_types::*;
 use pa_heuristic::*;
 use pa_types::*;
@@ -45,6 +47,7 @@ impl Default for FrontType {
 // TODO: Fix these names to be the same.
 pub use affine::AffineNwFrontsTag as AffineFront;
 pub use bitpacking::BitFrontsTag as BitFront;
+pub use pa_affine_types::IndelTieBreak;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct AstarNwParams {
@@ -55,6 +58,11 @@ pub struct AstarNwParams {
     /// The domain to compute.
     pub domain: Domain<()>,
 
+    /// Global, overlap, or semi-global alignment.
+    /// Overlap and semi-global alignment ignore all other parameters.
+    #[serde(default)]
+    pub mode: AlignmentMode,
+
     /// Heuristic to use for A* domain.
     pub heuristic: HeuristicParams,
 
@@ -125,6 +133,12 @@ impl AstarNwParams {
         trace: bool,
         v: V,
     ) -> Box<dyn Aligner> {
+        if let AlignmentMode::Overlap { .. } = self.mode {
+            return Box::new(OverlapAligner::new(AffineCost::unit(), self.mode));
+        }
+        if let AlignmentMode::SemiGlobal { .. } = self.mode {
+            return Box::new(SemiGlobalAligner::new(AffineCost::unit(), self.mode));
+        }
         struct Mapper<V: VisualizerT, F: NwFrontsTag<0>> {
             params: AstarNwParams,
             trace: bool,
@@ -251,6 +265,7 @@ impl<const N: usize> NW<N, NoVis, NoCost, AffineNwFrontsTag<N>> {
 impl<const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>> NW<N, V, H, F> {
     pub fn build<'a>(&'a self, a: Seq<'a>, b: Seq<'a>) -> NWInstance<'a, N, V, H, F> {
         use Domain::*;
+        pa_heuristic::util::assert_input_len(a, b);
         NWInstance {
             a,
             b,
@@ -297,7 +312,31 @@ impl<const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>> NW<N, V, H
         (start_f, max(start_increment, F::BLOCKSIZE))
     }
 
+    /// True when the full matrix is computed for unit costs without visualization,
+    /// so that `pa_bitpacking::edit_distance_profile` gives the same result faster.
+    fn is_unit_cost_full(&self) -> bool {
+        N == 0
+            && self.cm.sub == Some(1)
+            && self.cm.ins == Some(1)
+            && self.cm.del == Some(1)
+            && matches!(self.domain, Domain::Full)
+            && V::IS_DEFAULT
+    }
+
     fn cost_or_align(&self, a: Seq, b: Seq, trace: bool) -> (Cost, Option<AffineCigar>) {
+        if !trace && self.is_unit_cost_full() {
+            use pa_bitpacking::{Alphabet, BitProfile, Profile, RankTransform};
+            pa_heuristic::util::assert_input_len(a, b);
+            // The default profile ignores case and maps `U` to `T`, while the DP
+            // below compares bytes. Only take the fast path when both agree.
+            let ranks = RankTransform::new(&Alphabet::new(b"ACGT"));
+            if let Ok((pa, pb)) = BitProfile::try_build_with(a, b, Some(&ranks)) {
+                return (
+                    pa_bitpacking::edit_distance_profile(&pa, &pb, b.len()),
+                    None,
+                );
+            }
+        }
         let mut nw = self.build(a, b);
         let h0 = nw.domain.h().map_or(0, |h| h.h(Pos(0, 0)));
         let (cost, cigar) = match self.strategy {
@@ -309,7 +348,8 @@ impl<const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>> NW<N, V, H
             Strategy::BandDoubling { start, factor } => {
                 let (start_f, start_increment) = self.band_doubling_params(start, a, b, &nw);
                 let mut fronts = self.front.new(trace, a, b, &self.cm);
-                exponential_search(start_f, start_increment, factor, |s| {
+                let s_max = self.cm.max_possible_cost(a, b);
+                exponential_search("NW", start_f, start_increment, factor, s_max, |s| {
                     nw.align_for_bounded_dist(Some(s), trace, Some(&mut fronts))
                         .map(|x @ (c, _)| (c, x))
                 })
@@ -323,7 +363,8 @@ impl<const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>> NW<N, V, H
             Strategy::LinearSearch { start, delta } => {
                 let start_f = self.band_doubling_params(start, a, b, &nw).0;
                 let mut fronts = self.front.new(trace, a, b, &self.cm);
-                linear_search(start_f, delta as Cost, |s| {
+                let s_max = self.cm.max_possible_cost(a, b);
+                linear_search("NW", start_f, delta as Cost, s_max, |s| {
                     nw.align_for_bounded_dist(Some(s), trace, Some(&mut fronts))
                         .map(|x @ (c, _)| (c, x))
                 })
@@ -468,16 +509,10 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>>
             }
             Domain::GapGap => {
                 let d = self.b.len() as I - self.a.len() as I;
-                // We subtract the cost needed to bridge the gap from the start to the end.
-                let s = f_max
-                    - self
-                        .params
-                        .cm
-                        .gap_cost(Pos(0, 0), Pos::target(&self.a, &self.b));
-                // Each extra diagonal costs one insertion and one deletion.
-                let extra_diagonals =
-                    s / (self.params.cm.min_ins_extend + self.params.cm.min_del_extend);
-                // NOTE: The range could be reduced slightly further by considering gap open costs.
+                let extra_diagonals = self
+                    .params
+                    .cm
+                    .extra_diagonals_for_cost(f_max, Pos::target(&self.a, &self.b));
                 let range = JRange(
                     min(d, 0) - extra_diagonals as I,
                     max(d, 0) + extra_diagonals as I,
@@ -1065,15 +1100,68 @@ impl<'a, const N: usize, V: VisualizerT, H: Heuristic, F: NwFrontsTag<N>>
     }
 }
 
+/// Trace back a path through a precomputed DP matrix, independent of the
+/// aligner that computed it. `matrix[i][j]` is the cost of aligning `a[..i]`
+/// to `b[..j]` under the linear cost model `cm`.
+/// Substitutions are tried before indels.
+///
+/// Panics when the matrix is not consistent with `cm`.
+pub fn traceback(
+    matrix: &[Vec<Cost>],
+    a: Seq,
+    b: Seq,
+    cm: &AffineCost<0>,
+    tie_break: IndelTieBreak,
+) -> Cigar {
+    assert_eq!(matrix.len(), a.len() + 1);
+    let mut cigar = Cigar::default();
+    let (mut i, mut j) = (a.len(), b.len());
+    assert_eq!(matrix[i].len(), b.len() + 1);
+    while i > 0 || j > 0 {
+        let cur = matrix[i][j];
+        if i > 0
+            && j > 0
+            && let Some(sub) = cm.sub_cost(a[i - 1], b[j - 1])
+            && matrix[i - 1][j - 1] + sub == cur
+        {
+            cigar.push(if a[i - 1] == b[j - 1] {
+                CigarOp::Match
+            } else {
+                CigarOp::Sub
+            });
+            i -= 1;
+            j -= 1;
+            continue;
+        }
+        let ins = j > 0 && cm.ins.is_some_and(|ins| matrix[i][j - 1] + ins == cur);
+        let del = i > 0 && cm.del.is_some_and(|del| matrix[i - 1][j] + del == cur);
+        match (ins, del, tie_break) {
+            (true, false, _) | (true, true, IndelTieBreak::Ins) => {
+                cigar.push(CigarOp::Ins);
+                j -= 1;
+            }
+            (_, true, _) => {
+                cigar.push(CigarOp::Del);
+                i -= 1;
+            }
+            _ => panic!("No parent of ({i}, {j}) with cost {cur} in the matrix."),
+        }
+    }
+    cigar.reverse();
+    cigar
+}
+
 #[cfg(test)]
 mod test {
     use pa_affine_types::AffineCost;
-    use pa_heuristic::{MatchConfig, Pruning, GCSH};
-    use pa_vis::NoVis;
+    use pa_heuristic::{HeuristicInstance, MatchConfig, NoCost, Pruning, GCSH};
+    use pa_vis::{CanvasFactory, NoVis, VisualizerInstance, VisualizerT};
+    use std::{cell::Cell, rc::Rc};
 
     use crate::{Domain, Strategy};
+    use pa_types::{Cost, CostModel, Pos, Seq, I};
 
-    use super::{BitFront, NW};
+    use super::{traceback, AffineNwFrontsTag, BitFront, IndelTieBreak, NW};
 
     #[test]
     fn nw() {
@@ -1096,6 +1184,66 @@ mod test {
         assert_eq!(d, d2);
     }
 
+    /// The traceback through a naive DP matrix is an optimal alignment.
+    #[test]
+    fn traceback_matrix() {
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+            let matrix = pa_test::dp_matrix(&a, &b, &CostModel::unit());
+            let cigar = traceback(&matrix, &a, &b, &AffineCost::unit(), IndelTieBreak::Del);
+            assert_eq!(
+                cigar.verify(&CostModel::unit(), &a, &b),
+                matrix[a.len()][b.len()],
+                "{params:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn traceback_tie_break() {
+        // Substitutions cost more than an insertion and a deletion, which can go in either order.
+        let cm = AffineCost::linear(3, 1);
+        let a = b"ACGT";
+        let b = b"AGGT";
+        let linear = CostModel {
+            sub: 3,
+            open: 0,
+            extend: 1,
+        };
+        let matrix = |a: &[u8], b: &[u8]| pa_test::dp_matrix(a, b, &linear);
+        let ins = traceback(&matrix(a, b), a, b, &cm, IndelTieBreak::Ins);
+        let del = traceback(&matrix(a, b), a, b, &cm, IndelTieBreak::Del);
+        assert_eq!(ins.to_string(), "1=1D1I2=");
+        assert_eq!(del.to_string(), "1=1I1D2=");
+
+        // Transposing the input and the tie-break swaps insertions and deletions.
+        let transposed = traceback(&matrix(b, a), b, a, &cm, IndelTieBreak::Del);
+        assert_eq!(transposed.to_string(), "1=1I1D2=");
+    }
+
+    #[test]
+    fn bit_front_tie_break() {
+        for (indel_tie_break, cigar) in [
+            (IndelTieBreak::Ins, "1D1=1I"),
+            (IndelTieBreak::Del, "1I1=1D"),
+        ] {
+            let nw = NW {
+                cm: AffineCost::unit(),
+                strategy: Strategy::None,
+                domain: Domain::<NoCost>::Full,
+                block_width: 32,
+                v: NoVis,
+                front: BitFront {
+                    indel_tie_break,
+                    ..BitFront::default()
+                },
+                trace: true,
+                sparse_h: true,
+                prune: false,
+            };
+            assert_eq!(nw.align(b"AC", b"CA").1.unwrap().to_string(), cigar);
+        }
+    }
+
     #[test]
     fn nw_prune() {
         let (a, b) =
@@ -1162,4 +1310,120 @@ mod test {
         let d2 = triple_accel::levenshtein_exp(&a, &b) as _;
         assert_eq!(d, d2);
     }
+
+    /// The bitpacked cost-only fast path must match the general DP.
+    #[test]
+    fn unit_cost_fast_path() {
+        let nw = NW::new(AffineCost::unit(), false, false);
+        assert!(nw.is_unit_cost_full());
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 300) {
+            let d = nw.cost(&a, &b);
+            assert_eq!(d, nw.align(&a, &b).0, "{params:?}");
+            assert_eq!(
+                d,
+                triple_accel::levenshtein_exp(&a, &b) as Cost,
+                "{params:?}"
+            );
+        }
+        // Inputs the profile does not support, or compares differently, fall back to the DP.
+        for (a, b) in [
+            (&b"ACNGT"[..], &b"ACGGT"[..]),
+            (&b"acgt"[..], &b"ACGT"[..]),
+            (&b"ACGU"[..], &b"ACGT"[..]),
+        ] {
+            assert_eq!(nw.cost(a, b), nw.align(a, b).0);
+        }
+    }
+
+    /// Gap open costs shrink the `GapGap` band but leave unit costs unchanged.
+    #[test]
+    fn gap_gap_open_cost() {
+        let unit = AffineCost::unit();
+        let affine = AffineCost::affine(1, 5, 1);
+        for target in [Pos(100, 100), Pos(100, 150), Pos(150, 100)] {
+            for f_max in [0, 10, 60, 100] {
+                let s = f_max - unit.gap_cost(Pos(0, 0), target);
+                let old = s / (unit.min_ins_extend + unit.min_del_extend);
+                assert_eq!(unit.extra_diagonals_for_cost(f_max, target), old);
+
+                let s = f_max - affine.gap_cost(Pos(0, 0), target);
+                let old = s / (affine.min_ins_extend + affine.min_del_extend);
+                let new = affine.extra_diagonals_for_cost(f_max, target);
+                assert!(new <= old);
+                if s >= 10 {
+                    assert!(new < old);
+                }
+                // A negative budget still gives an empty band.
+                assert_eq!(new < 0, s < 0);
+            }
+        }
+
+        // The narrower band must not change the cost on inputs with a large gap.
+        let (a, b) =
+            pa_generate::generate_model(500, 0.05, pa_generate::ErrorModel::NoisyInsert, 31415);
+        let full = NW::new(affine.clone(), false, false).cost(&a, &b);
+        assert_eq!(NW::new(affine.clone(), true, true).cost(&a, &b), full);
+        assert_eq!(NW::new(affine, true, true).cost(&b, &a), full);
+    }
+
+    /// Counts the states in all computed blocks.
+    #[derive(Clone, Default, Debug, PartialEq)]
+    struct StateCounter(Rc<Cell<I>>);
+
+    impl VisualizerT for StateCounter {
+        type Instance = Self;
+        fn build(&self, _a: Seq, _b: Seq) -> Self {
+            self.clone()
+        }
+        fn build_from_factory<CF: CanvasFactory>(&self, a: Seq, b: Seq) -> Self {
+            self.build(a, b)
+        }
+    }
+
+    impl VisualizerInstance for StateCounter {
+        fn expand_block<'a, HI: HeuristicInstance<'a>>(
+            &mut self,
+            _pos: Pos,
+            size: Pos,
+            _g: Cost,
+            _f: Cost,
+            _h: Option<&HI>,
+        ) {
+            self.0.set(self.0.get() + size.0 * size.1);
+        }
+    }
+
+    /// On an input with a large gap, gap open costs shrink the `GapGap` band
+    /// and thereby the number of computed states, without changing the cost.
+    #[test]
+    fn gap_gap_open_cost_states() {
+        use pa_test::generate::{mutate, random_sequence};
+        use rand::{rngs::StdRng, SeedableRng};
+        let rng = &mut StdRng::seed_from_u64(31415);
+        let a = random_sequence(1000, rng);
+        let b = mutate(&a, 0.02, rng);
+        let b = [&b[..500], &random_sequence(200, rng), &b[500..]].concat();
+
+        let cm = AffineCost::affine(1, 10, 1);
+        let cost = NW::new(cm.clone(), false, false).cost(&a, &b);
+        let states = |f_max| {
+            let counter = StateCounter::default();
+            let nw = NW {
+                cm: cm.clone(),
+                strategy: Strategy::None,
+                domain: Domain::<NoCost>::GapGap,
+                block_width: 32,
+                v: counter.clone(),
+                front: AffineNwFrontsTag::<2>,
+                trace: false,
+                sparse_h: true,
+                prune: false,
+            };
+            assert_eq!(nw.cost_for_bounded_dist(&a, &b, f_max), Some(cost));
+            counter.0.get()
+        };
+        // Without the gap open cost, the band for `cost` was the band that is
+        // now used for `cost` plus the deletion open cost.
+        assert!(states(cost) < states(cost + cm.min_del_open));
+    }
 }
@@ -34,6 +34,9 @@ pub struct BitFrontsTag {
     pub max_g: Cost,
     #[serde(default)]
     pub drop: I,
+    /// Whether traceback prefers an insertion or a deletion when both are optimal.
+    #[serde(default)]
+    pub indel_tie_break: IndelTieBreak,
 }
 
 impl Default for BitFrontsTag {
@@ -45,6 +48,7 @@ impl Default for BitFrontsTag {
             dt_trace: false,
             max_g: 40,
             drop: 20,
+            indel_tie_break: IndelTieBreak::Ins,
         }
     }
 }
@@ -270,7 +274,7 @@ impl BitFront {
 
 impl NwFrontsTag<0usize> for BitFrontsTag {
     type Fronts<'a> = BitFronts;
-    const BLOCKSIZE: I = 64;
+    const BLOCKSIZE: I = WI;
     fn new<'a>(
         &self,
         trace: bool,
@@ -722,7 +726,9 @@ impl NwFronts<0usize> for BitFronts {
             }
 
             // Try a Diagonal Transition based traceback first which should be faster for small distances.
-            if self.params.dt_trace && to.i > 0 {
+            // It does not follow the indel tie-break, so is only used for the default one.
+            if self.params.dt_trace && self.params.indel_tie_break == IndelTieBreak::Ins && to.i > 0
+            {
                 let prev_front = &self.fronts[self.last_front_idx - 1];
                 if prev_front.i < to.i - 1 {
                     dt_trace_tries += 1;
@@ -939,42 +945,32 @@ impl BitFronts {
         }
 
         // Vertical delta (insert).
-        // (This is first since it only needs a single delta bit, instead of an index() call.)
-        let vd = front.get_diff(st.j - 1);
-        if vd == Some(1) {
+        // (By default this is first since it only needs a single delta bit, instead of an index() call.)
+        let ins = || front.get_diff(st.j - 1) == Some(1);
+        // Horizontal delta (delete).
+        let hd = || {
+            let prev_front = &self.fronts[self.last_front_idx - 1];
+            assert!(prev_front.i == st.i - 1);
+            *g - prev_front.index(st.j)
+        };
+        let op = match self.params.indel_tie_break {
+            IndelTieBreak::Ins if ins() => Some(AffineCigarOp::Ins),
+            IndelTieBreak::Ins if hd() == 1 => Some(AffineCigarOp::Del),
+            IndelTieBreak::Del if st.i > 0 && hd() == 1 => Some(AffineCigarOp::Del),
+            IndelTieBreak::Del if ins() => Some(AffineCigarOp::Ins),
+            _ => None,
+        };
+        if let Some(op) = op {
             *g -= 1;
-            return (
-                State {
-                    i: st.i,
-                    j: st.j - 1,
-                    layer: None,
-                },
-                AffineCigarElem {
-                    op: AffineCigarOp::Ins,
-                    cnt: 1,
-                },
-            );
+            let (i, j) = match op {
+                AffineCigarOp::Ins => (st.i, st.j - 1),
+                _ => (st.i - 1, st.j),
+            };
+            return (State { i, j, layer: None }, AffineCigarElem { op, cnt: 1 });
         }
 
         let prev_front = &self.fronts[self.last_front_idx - 1];
-        assert!(prev_front.i == st.i - 1);
-
-        // Horizontal delta (delete).
-        let hd = *g - prev_front.index(st.j);
-        if hd == 1 {
-            *g -= 1;
-            return (
-                State {
-                    i: st.i - 1,
-                    j: st.j,
-                    layer: None,
-                },
-                AffineCigarElem {
-                    op: AffineCigarOp::Del,
-                    cnt: 1,
-                },
-            );
-        }
+        let hd = hd();
 
         // Diagonal delta (substitution).
         // This edge case happens when entering the previous front exactly in
@@ -1236,7 +1232,7 @@ impl BitFronts {
             Pos(i_range.len(), j_range_rounded.exclusive_len()),
         );
         if self.params.simd {
-            pa_bitpacking::simd::fill::<2, H, 4>(
+            pa_bitpacking::fill::<H>(
                 &self.a[i_range.0 as usize..i_range.1 as usize],
                 &self.b[v_range],
                 h,
@@ -1310,7 +1306,7 @@ fn compute_columns(
     let run = |h, exact_end| {
         if params.simd {
             // FIXME: Choose the optimal scalar function to use here.
-            pa_bitpacking::simd::compute::<2, H, 4>(
+            pa_bitpacking::compute::<H>(
                 &a[i_range.0 as usize..i_range.1 as usize],
                 &b[v_range],
                 h,
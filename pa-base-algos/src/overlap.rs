@@ -0,0 +1,207 @@
+//! Overlap (dovetail) alignment, as used in overlap-layout-consensus assembly.
+//!
+//! A suffix of one sequence is aligned to a prefix of the other. The skipped
+//! prefix and suffix on either side are free, but only on the ends that do not
+//! take part in the overlap. This is different from semi-global alignment,
+//! where one sequence must be fully contained in the other.
+//!
+//! Uses the quadratic `LinearDp`.
+use crate::linear_dp::{extend_cigar, LinearDp, INF};
+use crate::AlignmentMode;
+use pa_affine_types::AffineCost;
+use pa_types::*;
+
+/// The result of an overlap alignment.
+///
+/// The overlap covers `a[start.0..end.0]` and `b[start.1..end.1]`.
+/// Either `start.0 == 0` or `start.1 == 0`, and either `end.0 == a.len()` or `end.1 == b.len()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapAlignment {
+    pub cost: Cost,
+    /// The alignment of the overlapping part only.
+    pub cigar: Cigar,
+    pub start: Pos,
+    pub end: Pos,
+}
+
+/// Aligner for `AlignmentMode::Overlap`.
+#[derive(Debug, Clone)]
+pub struct OverlapAligner {
+    /// The cost model to use. Only linear costs are supported.
+    pub cm: AffineCost<0>,
+    /// The minimal number of characters of each sequence in the overlap.
+    pub min_overlap: I,
+}
+
+impl OverlapAligner {
+    pub fn new(cm: AffineCost<0>, mode: AlignmentMode) -> Self {
+        let AlignmentMode::Overlap { min_overlap } = mode else {
+            panic!("OverlapAligner requires AlignmentMode::Overlap.");
+        };
+        Self { cm, min_overlap }
+    }
+
+    /// Find the best overlap in either direction:
+    /// a suffix of `a` against a prefix of `b`, or a suffix of `b` against a prefix of `a`.
+    ///
+    /// On ties, the longer overlap is preferred.
+    /// Returns `None` when no overlap of length at least `min_overlap` exists.
+    pub fn align_overlap(&self, a: Seq, b: Seq) -> Option<OverlapAlignment> {
+        let fwd = self.align_suffix_prefix(a, b);
+        let rev = self.align_suffix_prefix(b, a).map(|o| OverlapAlignment {
+            cost: o.cost,
+            cigar: transpose(&o.cigar),
+            start: Pos(o.start.1, o.start.0),
+            end: Pos(o.end.1, o.end.0),
+        });
+        let len = |o: &OverlapAlignment| (o.end.0 - o.start.0) + (o.end.1 - o.start.1);
+        match (fwd, rev) {
+            (Some(f), Some(r)) => {
+                if (r.cost, -len(&r)) < (f.cost, -len(&f)) {
+                    Some(r)
+                } else {
+                    Some(f)
+                }
+            }
+            (f, r) => f.or(r),
+        }
+    }
+
+    /// Align a suffix of `a` to a prefix of `b`.
+    ///
+    /// The top row (`j=0`) is initialized to 0, and the answer is the minimum
+    /// over the last column (`i=a.len()`).
+    /// Only the first `a.len() - min_overlap` cells of the top row are free,
+    /// so that the overlap always contains at least `min_overlap` characters of `a`.
+    /// When `min_overlap > a.len()`, no cell is free and `None` is returned.
+    fn align_suffix_prefix(&self, a: Seq, b: Seq) -> Option<OverlapAlignment> {
+        let n = a.len();
+        let m = b.len();
+        let min_overlap = self.min_overlap.max(0) as usize;
+        let dp = LinearDp::new(
+            a,
+            b,
+            self.cm.ins_or(INF, |c| c),
+            self.cm.del_or(INF, |c| c),
+            |i, j| self.cm.sub_cost_or(a[i - 1], b[j - 1], INF, |c| c),
+            |i, j| {
+                if j == 0 && i + min_overlap <= n {
+                    0
+                } else {
+                    INF
+                }
+            },
+            |_, _| false,
+        );
+
+        // Take the best end point in the last column, preferring longer overlaps.
+        let (cost, end_j) = (min_overlap..=m)
+            .map(|j| (dp.g[n][j], j))
+            .min_by_key(|&(c, j)| (c, std::cmp::Reverse(j)))?;
+        if cost >= INF {
+            return None;
+        }
+
+        // Trace back to the top row.
+        let ((i, j), cigar) = dp.trace((n, end_j));
+        assert_eq!(j, 0);
+
+        Some(OverlapAlignment {
+            cost,
+            cigar,
+            start: Pos(i as I, 0),
+            end: Pos(n as I, end_j as I),
+        })
+    }
+}
+
+/// Swap the roles of `a` and `b` in a cigar.
+fn transpose(cigar: &Cigar) -> Cigar {
+    Cigar {
+        ops: cigar
+            .ops
+            .iter()
+            .map(|&CigarElem { op, cnt }| CigarElem {
+                op: match op {
+                    CigarOp::Ins => CigarOp::Del,
+                    CigarOp::Del => CigarOp::Ins,
+                    op => op,
+                },
+                cnt,
+            })
+            .collect(),
+    }
+}
+
+impl Aligner for OverlapAligner {
+    /// Returns the cost of the overlap, and its cigar extended to all of `a`
+    /// and `b` with deletions and insertions for the parts outside the overlap.
+    /// Use `align_overlap` to get the overlap coordinates directly.
+    ///
+    /// When there is no overlap of at least `min_overlap` characters, returns
+    /// `Cost::MAX` and no cigar.
+    fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+        match self.align_overlap(a, b) {
+            Some(o) => (o.cost, Some(extend_cigar(&o.cigar, o.start, o.end, a, b))),
+            None => (Cost::MAX, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn aligner(min_overlap: I) -> OverlapAligner {
+        OverlapAligner::new(AffineCost::unit(), AlignmentMode::Overlap { min_overlap })
+    }
+
+    #[test]
+    fn exact_suffix_prefix() {
+        let a = b"GGGGACGTACGT";
+        let b = b"ACGTACGTTTTT";
+        let o = aligner(4).align_overlap(a, b).unwrap();
+        assert_eq!(o.cost, 0);
+        assert_eq!(o.start, Pos(4, 0));
+        assert_eq!(o.end, Pos(12, 8));
+        o.cigar.verify(&CostModel::unit(), &a[4..], &b[..8]);
+        // The Aligner cigar also contains the 4 + 4 characters outside the overlap.
+        let (cost, cigar) = aligner(4).align(a, b);
+        assert_eq!(cost, 0);
+        assert_eq!(cigar.unwrap().verify(&CostModel::unit(), a, b), 8);
+    }
+
+    #[test]
+    fn reverse_direction() {
+        let a = b"ACGTACGTTTTT";
+        let b = b"GGGGACGTACGT";
+        let o = aligner(4).align_overlap(a, b).unwrap();
+        assert_eq!(o.cost, 0);
+        assert_eq!(o.start, Pos(0, 4));
+        assert_eq!(o.end, Pos(8, 12));
+        o.cigar.verify(&CostModel::unit(), &a[..8], &b[4..]);
+    }
+
+    #[test]
+    fn overlap_with_errors() {
+        let a = b"TTTTTTACGTAGGTACGT";
+        let b = b"ACGTACGTACGTCCCCCC";
+        let o = aligner(8).align_overlap(a, b).unwrap();
+        assert_eq!(o.cost, 1);
+        assert_eq!(o.end.0, a.len() as I);
+        assert_eq!(o.start.1, 0);
+        let (s, e) = (o.start, o.end);
+        o.cigar.verify(
+            &CostModel::unit(),
+            &a[s.0 as usize..e.0 as usize],
+            &b[s.1 as usize..e.1 as usize],
+        );
+    }
+
+    #[test]
+    fn min_overlap_too_large() {
+        assert!(aligner(5).align_overlap(b"ACGT", b"ACGTACGT").is_none());
+        assert!(aligner(5).align_overlap(b"ACGTACGT", b"ACGT").is_none());
+        assert_eq!(aligner(5).align(b"ACGT", b"ACGTACGT"), (Cost::MAX, None));
+    }
+}
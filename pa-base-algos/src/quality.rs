@@ -0,0 +1,100 @@
+//! Global alignment where mismatches against low-quality bases of `b` are cheaper.
+//!
+//! NOTE: This uses the quadratic `LinearDp`, since the per-position
+//! substitution costs of `QualityCost` are not supported by the bitpacked
+//! fronts of `NW` or by the heuristics.
+use crate::linear_dp::{LinearDp, INF};
+use pa_affine_types::QualityCost;
+use pa_types::*;
+
+/// Aligner using a `QualityCost`.
+#[derive(Debug, Clone)]
+pub struct QualityAligner {
+    pub cm: QualityCost,
+}
+
+impl QualityAligner {
+    pub fn new(cm: QualityCost) -> Self {
+        Self { cm }
+    }
+
+    /// Globally align `a` and `b`, where `quality` is the FASTQ (Phred+33)
+    /// quality string of `b`.
+    pub fn align(&self, a: Seq, b: Seq, quality: &[u8]) -> (Cost, Cigar) {
+        assert_eq!(
+            b.len(),
+            quality.len(),
+            "b and its quality differ in length."
+        );
+        let n = a.len();
+        let m = b.len();
+        let dp = LinearDp::new(
+            a,
+            b,
+            self.cm.cm.ins_or(INF, |c| c),
+            self.cm.cm.del_or(INF, |c| c),
+            |i, j| {
+                let q = quality[j - 1].saturating_sub(33);
+                self.cm.sub_cost(a[i - 1], b[j - 1], q).unwrap_or(INF)
+            },
+            |i, j| if (i, j) == (0, 0) { 0 } else { INF },
+            |_, _| false,
+        );
+        let cost = dp.g[n][m];
+        assert!(cost < INF, "No alignment exists with the given costs.");
+
+        // Trace back to the start.
+        let (_, cigar) = dp.trace((n, m));
+        (cost, cigar)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pa_affine_types::AffineCost;
+
+    fn aligner() -> QualityAligner {
+        QualityAligner::new(QualityCost::new(AffineCost::linear(4, 4), 40))
+    }
+
+    #[test]
+    fn full_quality_is_linear() {
+        let (a, b) = pa_generate::generate_model(200, 0.1, pa_generate::ErrorModel::Uniform, 31415);
+        let quality = vec![b'I'; b.len()];
+        let (cost, cigar) = aligner().align(&a, &b, &quality);
+        assert_eq!(
+            cost,
+            4 * triple_accel::levenshtein_exp(&a, &b) as Cost,
+            "Unit cost scaled by 4"
+        );
+        assert_eq!(cigar.verify(&CostModel::unit(), &a, &b) * 4, cost);
+    }
+
+    #[test]
+    fn low_quality_mismatch_is_cheaper() {
+        let a = b"ACGTACGT";
+        let b = b"ACGAACGT";
+        let aligner = aligner();
+        // Phred 40 everywhere.
+        let high = vec![b'I'; b.len()];
+        assert_eq!(aligner.align(a, b, &high).0, 4);
+        // Phred 10 at the mismatch: ceil(4 * 10 / 40) = 1.
+        let mut low = high.clone();
+        low[3] = b'+';
+        let (cost, cigar) = aligner.align(a, b, &low);
+        assert_eq!(cost, 1);
+        assert_eq!(cigar.verify(&CostModel::unit(), a, b), 1);
+    }
+
+    #[test]
+    fn zero_quality_mismatch_is_not_free() {
+        let cm = aligner().cm;
+        assert_eq!(cm.sub_cost(b'A', b'A', 0), Some(0));
+        assert_eq!(cm.sub_cost(b'A', b'C', 0), Some(1));
+        // Phred 0 is `!`.
+        let (cost, cigar) = aligner().align(b"ACGT", b"ACCT", b"II!I");
+        assert_eq!(cost, 1);
+        assert_eq!(cigar.to_string(), "2=1X1=");
+    }
+}
@@ -0,0 +1,132 @@
+//! The cost of the second-best alignment, as a confidence signal for mapping.
+//!
+//! After the optimal path is found, the alignment is repeated with all interior
+//! states of the optimal path forbidden. The difference between the two costs
+//! is small when the optimal alignment is ambiguous.
+//!
+//! Uses the quadratic `LinearDp`, with the optimal path masked out.
+use crate::linear_dp::{LinearDp, INF};
+use pa_affine_types::AffineCost;
+use pa_types::*;
+
+/// The optimal alignment, and the cost of the best alignment that avoids it.
+#[derive(Debug)]
+pub struct SecondBest {
+    pub cost: Cost,
+    pub cigar: Cigar,
+    /// The cost of the best alignment that does not visit any state of the
+    /// optimal path other than the start and end.
+    /// `None` when there is no such alignment, and when the optimal path is a
+    /// single edge without interior states.
+    pub second_cost: Option<Cost>,
+}
+
+/// Aligner that also returns the cost of the second-best alignment.
+#[derive(Debug, Clone)]
+pub struct SecondBestAligner {
+    pub cm: AffineCost<0>,
+}
+
+impl SecondBestAligner {
+    pub fn new(cm: AffineCost<0>) -> Self {
+        Self { cm }
+    }
+
+    pub fn align(&self, a: Seq, b: Seq) -> SecondBest {
+        let (cost, cigar) = self.align_masked(a, b, None).unwrap();
+
+        // Forbid the interior states of the optimal path.
+        let mut mask = vec![vec![false; b.len() + 1]; a.len() + 1];
+        let mut pos = Pos(0, 0);
+        for el in &cigar.ops {
+            for _ in 0..el.cnt {
+                mask[pos.0 as usize][pos.1 as usize] = true;
+                match el.op {
+                    CigarOp::Match | CigarOp::Sub => pos = Pos(pos.0 + 1, pos.1 + 1),
+                    CigarOp::Del => pos.0 += 1,
+                    CigarOp::Ins => pos.1 += 1,
+                }
+            }
+        }
+        mask[0][0] = false;
+
+        // When the optimal path has no interior states, the same path would be found again.
+        let has_interior = mask.iter().flatten().any(|&m| m);
+        let second_cost = has_interior
+            .then(|| self.align_masked(a, b, Some(&mask)))
+            .flatten()
+            .map(|(c, _)| c);
+        SecondBest {
+            cost,
+            cigar,
+            second_cost,
+        }
+    }
+
+    /// Globally align `a` and `b` without visiting states `(i, j)` with `mask[i][j]`.
+    /// Returns `None` when no such alignment exists.
+    fn align_masked(&self, a: Seq, b: Seq, mask: Option<&[Vec<bool>]>) -> Option<(Cost, Cigar)> {
+        let n = a.len();
+        let m = b.len();
+        let dp = LinearDp::new(
+            a,
+            b,
+            self.cm.ins_or(INF, |c| c),
+            self.cm.del_or(INF, |c| c),
+            |i, j| self.cm.sub_cost_or(a[i - 1], b[j - 1], INF, |c| c),
+            |i, j| if (i, j) == (0, 0) { 0 } else { INF },
+            |i, j| mask.is_some_and(|mask| mask[i][j]),
+        );
+        let cost = dp.g[n][m];
+        if cost >= INF {
+            return None;
+        }
+
+        // Trace back to the start.
+        let (_, cigar) = dp.trace((n, m));
+        Some((cost, cigar))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ambiguous_deletion() {
+        // Either `A` can be deleted, on disjoint paths.
+        let r = SecondBestAligner::new(AffineCost::unit()).align(b"AA", b"A");
+        assert_eq!(r.cost, 1);
+        assert_eq!(r.second_cost, Some(1));
+    }
+
+    #[test]
+    fn unique_alignment() {
+        let a = b"AAAAAAAA";
+        let r = SecondBestAligner::new(AffineCost::unit()).align(a, a);
+        assert_eq!(r.cost, 0);
+        // The alternative is shifted by one diagonal, with an insertion and a deletion.
+        assert_eq!(r.second_cost, Some(2));
+        // A single match has no interior states to avoid.
+        let r = SecondBestAligner::new(AffineCost::unit()).align(b"A", b"A");
+        assert_eq!(r.second_cost, None);
+        // Avoiding `(1, 0)`, `A` is substituted instead of deleted.
+        let r = SecondBestAligner::new(AffineCost::unit()).align(b"AC", b"C");
+        assert_eq!(r.cost, 1);
+        assert_eq!(r.second_cost, Some(2));
+        let r = SecondBestAligner::new(AffineCost::unit()).align(b"A", b"");
+        assert_eq!(r.second_cost, None);
+    }
+
+    #[test]
+    fn random() {
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 100) {
+            let r = SecondBestAligner::new(AffineCost::unit()).align(&a, &b);
+            assert_eq!(r.cost, triple_accel::levenshtein_exp(&a, &b) as Cost);
+            assert_eq!(r.cigar.verify(&CostModel::unit(), &a, &b), r.cost);
+            if let Some(second) = r.second_cost {
+                assert!(second >= r.cost, "{params:?}");
+            }
+        }
+    }
+}
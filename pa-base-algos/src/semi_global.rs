@@ -0,0 +1,239 @@
+//! Semi-global alignment of a query `b` into a reference `a`, as used in read mapping.
+//!
+//! The prefix and suffix of the reference that are not aligned are free.
+//! Whether the ends of the query may be left unaligned (soft-clipped), and at
+//! what cost, is given by a `ClipPolicy` for each end.
+//!
+//! Uses the quadratic `LinearDp`.
+use crate::linear_dp::{add, extend_cigar, LinearDp, INF};
+use crate::{AlignmentMode, ClipPolicy};
+use pa_affine_types::AffineCost;
+use pa_types::*;
+
+/// The result of a semi-global alignment.
+///
+/// The alignment covers `a[start.0..end.0]` and `b[start.1..end.1]`.
+/// `b[..start.1]` and `b[end.1..]` are clipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemiGlobalAlignment {
+    /// The cost of the alignment, including the clipping penalties.
+    pub cost: Cost,
+    /// The alignment of the aligned part only.
+    pub cigar: Cigar,
+    pub start: Pos,
+    pub end: Pos,
+}
+
+impl SemiGlobalAlignment {
+    /// The number of clipped characters at the start of the query.
+    pub fn clipped_start(&self) -> I {
+        self.start.1
+    }
+
+    /// The number of clipped characters at the end of the query `b`.
+    pub fn clipped_end(&self, b: Seq) -> I {
+        b.len() as I - self.end.1
+    }
+
+    /// The cigar extended to all of `a` and `b`: the unaligned prefix and
+    /// suffix of `a` are deletions, and the clipped ends of `b` are insertions.
+    pub fn full_cigar(&self, a: Seq, b: Seq) -> Cigar {
+        extend_cigar(&self.cigar, self.start, self.end, a, b)
+    }
+}
+
+impl ClipPolicy {
+    /// The cost of clipping a non-empty end, at most `INF`.
+    fn cost(&self) -> Cost {
+        match *self {
+            ClipPolicy::Forbidden => INF,
+            ClipPolicy::Free => 0,
+            ClipPolicy::Penalized(c) => c.clamp(0, INF),
+        }
+    }
+}
+
+/// Aligner for `AlignmentMode::SemiGlobal`.
+#[derive(Debug, Clone)]
+pub struct SemiGlobalAligner {
+    /// The cost model to use. Only linear costs are supported.
+    pub cm: AffineCost<0>,
+    pub clip_start: ClipPolicy,
+    pub clip_end: ClipPolicy,
+}
+
+impl SemiGlobalAligner {
+    pub fn new(cm: AffineCost<0>, mode: AlignmentMode) -> Self {
+        let AlignmentMode::SemiGlobal {
+            clip_start,
+            clip_end,
+        } = mode
+        else {
+            panic!("SemiGlobalAligner requires AlignmentMode::SemiGlobal.");
+        };
+        Self {
+            cm,
+            clip_start,
+            clip_end,
+        }
+    }
+
+    /// Align the query `b` into the reference `a`.
+    ///
+    /// The path may start in any cell of the top row (`j=0`), and, when
+    /// clipping the start of the query is allowed, in any other cell at the
+    /// cost of clipping. Similarly, it ends in the bottom row (`j=b.len()`),
+    /// or anywhere else at the cost of clipping the end.
+    ///
+    /// Note that clipping the entire query is a valid alignment, so the cost is
+    /// at most the clipping penalty. With `ClipPolicy::Free` on both ends, the
+    /// cost is always 0.
+    ///
+    /// On ties, fewer clipped characters are preferred.
+    pub fn align_semi_global(&self, a: Seq, b: Seq) -> SemiGlobalAlignment {
+        let n = a.len();
+        let m = b.len();
+        let clip_start = self.clip_start.cost();
+        let clip_end = self.clip_end.cost();
+
+        // g[i][j]: (cost, clipped start) of the best path from a start to (i, j).
+        // Paths with equal cost are ordered by the number of clipped characters.
+        let dp = LinearDp::new(
+            a,
+            b,
+            self.cm.ins_or(INF, |c| c),
+            self.cm.del_or(INF, |c| c),
+            |i, j| self.cm.sub_cost_or(a[i - 1], b[j - 1], INF, |c| c),
+            |_, j| if j == 0 { (0, 0) } else { (clip_start, j as I) },
+            |_, _| false,
+        );
+
+        // Take the best end point, preferring fewer clipped characters.
+        let (cost, _, end) = (0..=n)
+            .flat_map(|i| (0..=m).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let (cost, clipped) = dp.g[i][j];
+                let clip_end = if j == m { 0 } else { clip_end };
+                (add(cost, clip_end), clipped + (m - j) as I, (i, j))
+            })
+            .min()
+            .unwrap();
+
+        // Trace back to a start. Extending the alignment is preferred over clipping.
+        let (start, cigar) = dp.trace(end);
+
+        SemiGlobalAlignment {
+            cost,
+            cigar,
+            start: Pos(start.0 as I, start.1 as I),
+            end: Pos(end.0 as I, end.1 as I),
+        }
+    }
+}
+
+impl Aligner for SemiGlobalAligner {
+    /// Returns the semi-global cost, including clipping penalties, and the
+    /// `full_cigar` spanning all of `a` and `b`, so that the clipped lengths can
+    /// be recovered from its first and last operations.
+    ///
+    /// NOTE: Verifying this cigar gives its cost as a global alignment, which is
+    /// larger than the returned cost when anything is skipped or clipped.
+    fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+        let r = self.align_semi_global(a, b);
+        (r.cost, Some(r.full_cigar(a, b)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn aligner(clip_start: ClipPolicy, clip_end: ClipPolicy) -> SemiGlobalAligner {
+        SemiGlobalAligner::new(
+            AffineCost::unit(),
+            AlignmentMode::SemiGlobal {
+                clip_start,
+                clip_end,
+            },
+        )
+    }
+
+    fn verify(r: &SemiGlobalAlignment, a: Seq, b: Seq) -> Cost {
+        let (s, e) = (r.start, r.end);
+        r.cigar.verify(
+            &CostModel::unit(),
+            &a[s.0 as usize..e.0 as usize],
+            &b[s.1 as usize..e.1 as usize],
+        )
+    }
+
+    #[test]
+    fn contained_query() {
+        let a = b"GGGGGACGTACGTGGGGG";
+        let b = b"ACGTTACGT";
+        let r = aligner(ClipPolicy::Forbidden, ClipPolicy::Forbidden).align_semi_global(a, b);
+        assert_eq!(r.cost, 1);
+        assert_eq!((r.clipped_start(), r.clipped_end(b)), (0, 0));
+        assert_eq!(verify(&r, a, b), 1);
+    }
+
+    #[test]
+    fn clip_policies() {
+        // The query ends in 4 characters that are not in the reference.
+        let a = b"GGGGACGTACGTACGT";
+        let b = b"ACGTACGTACGTTTTT";
+        let forbidden =
+            aligner(ClipPolicy::Forbidden, ClipPolicy::Forbidden).align_semi_global(a, b);
+        assert_eq!(forbidden.cost, 4);
+        assert_eq!(forbidden.clipped_end(b), 0);
+        assert_eq!(verify(&forbidden, a, b), 4);
+
+        let free = aligner(ClipPolicy::Forbidden, ClipPolicy::Free).align_semi_global(a, b);
+        assert_eq!(free.cost, 0);
+        assert_eq!(free.end, Pos(16, 12));
+        assert_eq!(free.clipped_end(b), 4);
+        assert_eq!(verify(&free, a, b), 0);
+
+        // Clipping is only used when it is cheaper than aligning the end.
+        let penalized = aligner(ClipPolicy::Forbidden, ClipPolicy::Penalized(3));
+        let r = penalized.align_semi_global(a, b);
+        assert_eq!(r.cost, 3);
+        assert_eq!(r.clipped_end(b), 4);
+        assert_eq!(verify(&r, a, b), 0);
+        let r = penalized.align_semi_global(a, &b[..14]);
+        assert_eq!(r.cost, 2);
+        assert_eq!(r.clipped_end(&b[..14]), 0);
+    }
+
+    #[test]
+    fn clip_start() {
+        let a = b"ACGTACGTGGGG";
+        let b = b"TTTTACGTACGT";
+        let r = aligner(ClipPolicy::Penalized(1), ClipPolicy::Forbidden).align_semi_global(a, b);
+        assert_eq!(r.cost, 1);
+        assert_eq!(r.start, Pos(0, 4));
+        assert_eq!(r.clipped_start(), 4);
+        assert_eq!(verify(&r, a, b), 0);
+    }
+
+    #[test]
+    fn aligner_cigar_spans_input() {
+        let a = b"ACGTACGTGGGG";
+        let b = b"TTTTACGTACGT";
+        let mut aligner = aligner(ClipPolicy::Penalized(1), ClipPolicy::Forbidden);
+        let (cost, cigar) = aligner.align(a, b);
+        assert_eq!(cost, 1);
+        // 4 clipped insertions, 8 matches, and 4 skipped deletions.
+        assert_eq!(cigar.unwrap().verify(&CostModel::unit(), a, b), 8);
+    }
+
+    #[test]
+    fn large_penalty_does_not_overflow() {
+        let a = b"GGGGACGTACGT";
+        let b = b"ACGTACGTTTTT";
+        let r = aligner(ClipPolicy::Forbidden, ClipPolicy::Penalized(Cost::MAX))
+            .align_semi_global(a, b);
+        assert_eq!(r.cost, 4);
+        assert_eq!(r.clipped_end(b), 0);
+    }
+}
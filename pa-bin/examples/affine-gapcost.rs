@@ -62,6 +62,9 @@ fn main() {
                     length: pa_heuristic::LengthConfig::Fixed(k),
                     r: 1,
                     local_pruning: 7,
+                    offset: 0,
+                    mask_low_complexity: None,
+                    respect_softmask: false,
                 },
                 distance_function: dist,
                 pruning: Pruning::both(),
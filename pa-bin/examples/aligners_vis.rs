@@ -62,6 +62,9 @@ fn main() {
             length: pa_heuristic::LengthConfig::Fixed(4),
             r: 1,
             local_pruning: 1,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: false,
         },
         Pruning::start(),
     );
@@ -50,6 +50,9 @@ fn main() {
         length: pa_heuristic::LengthConfig::Fixed(k),
         r: 1,
         local_pruning: 0,
+        offset: 0,
+        mask_low_complexity: None,
+        respect_softmask: false,
     };
     let pruning = Prune::None;
     for p in [0, 5] {
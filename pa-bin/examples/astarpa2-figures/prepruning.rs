@@ -51,6 +51,9 @@ fn main() {
         length: pa_heuristic::LengthConfig::Fixed(k),
         r: 1,
         local_pruning: 3,
+        offset: 0,
+        mask_low_complexity: None,
+        respect_softmask: false,
     };
 
     let prepruned_states = |transform| {
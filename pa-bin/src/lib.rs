@@ -1,17 +1,20 @@
 #![feature(trait_upcasting)]
 
+pub mod prefilter;
+
 use astarpa::{make_aligner, HeuristicParams};
-use astarpa2::AstarPa2Params;
+use astarpa2::{AstarPa2Params, AstarPa2StatsAligner};
 use bio::io::fasta;
-use clap::{value_parser, Parser};
+use clap::{error::ErrorKind, value_parser, CommandFactory, Parser};
 use itertools::Itertools;
-use pa_types::{Aligner, Seq};
+use pa_affine_types::{AffineCost, CostBreakdown};
+use pa_types::{Aligner, Cigar, CigarOp, Cost, Seq, I};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, BufWriter, Write},
     ops::ControlFlow,
     path::PathBuf,
 };
@@ -32,6 +35,324 @@ impl AlignerType {
             AlignerType::Astarpa2Full => AstarPa2Params::full().make_aligner(true),
         }
     }
+
+    /// The A*PA2 aligner, or `None` for A*PA.
+    pub fn build_astarpa2(&self) -> Option<Box<dyn AstarPa2StatsAligner>> {
+        match self {
+            AlignerType::Astarpa => None,
+            AlignerType::Astarpa2Simple => Some(AstarPa2Params::simple().make_aligner(true)),
+            AlignerType::Astarpa2Full => Some(AstarPa2Params::full().make_aligner(true)),
+        }
+    }
+}
+
+/// How matches and substitutions are written in output cigars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CigarStyle {
+    /// Write matches and substitutions as `=` and `X`.
+    /// Otherwise, both are merged into `M` for tools that do not support `=`/`X`.
+    pub use_eqx: bool,
+}
+
+impl Default for CigarStyle {
+    fn default() -> Self {
+        Self { use_eqx: true }
+    }
+}
+
+impl CigarStyle {
+    pub fn format(&self, cigar: &Cigar) -> String {
+        if self.use_eqx {
+            return cigar.to_string();
+        }
+        let mut s = String::new();
+        let mut matches = 0;
+        for el in &cigar.ops {
+            let c = match el.op {
+                CigarOp::Match | CigarOp::Sub => {
+                    matches += el.cnt;
+                    continue;
+                }
+                CigarOp::Ins => 'I',
+                CigarOp::Del => 'D',
+            };
+            if matches > 0 {
+                s += &format!("{matches}M");
+                matches = 0;
+            }
+            s += &format!("{}{c}", el.cnt);
+        }
+        if matches > 0 {
+            s += &format!("{matches}M");
+        }
+        s
+    }
+}
+
+/// Counts of the operations in a cigar, and the identities derived from them.
+///
+/// Tools define percent identity differently, so both common definitions are
+/// provided and named explicitly in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CigarStats {
+    pub matches: I,
+    pub mismatches: I,
+    pub insertions: I,
+    pub deletions: I,
+    /// The number of runs of insertions or deletions.
+    pub gap_opens: I,
+}
+
+impl CigarStats {
+    pub fn new(cigar: &Cigar) -> Self {
+        let mut stats = Self::default();
+        let mut prev = None;
+        for el in &cigar.ops {
+            match el.op {
+                CigarOp::Match => stats.matches += el.cnt,
+                CigarOp::Sub => stats.mismatches += el.cnt,
+                CigarOp::Ins => stats.insertions += el.cnt,
+                CigarOp::Del => stats.deletions += el.cnt,
+            }
+            if matches!(el.op, CigarOp::Ins | CigarOp::Del) && prev != Some(el.op) {
+                stats.gap_opens += 1;
+            }
+            prev = Some(el.op);
+        }
+        stats
+    }
+
+    /// The number of columns of the alignment.
+    pub fn columns(&self) -> I {
+        self.matches + self.mismatches + self.insertions + self.deletions
+    }
+
+    /// The fraction of columns that are matches, as reported by BLAST.
+    /// Each gap character counts as a column.
+    pub fn blast_identity(&self) -> f64 {
+        self.matches as f64 / self.columns().max(1) as f64
+    }
+
+    /// `matches / (matches + mismatches + gap_opens)`, where each gap counts
+    /// once regardless of its length. Minimap2 reports `1 -` this as `de:f`.
+    pub fn gap_compressed_identity(&self) -> f64 {
+        let events = self.matches + self.mismatches + self.gap_opens;
+        self.matches as f64 / events.max(1) as f64
+    }
+}
+
+/// The origin of coordinates in the output.
+///
+/// Internally, positions are 0-based and ranges are half-open, as in PAF and BED.
+/// SAM and GFF instead use 1-based positions and inclusive ranges.
+/// Pair indices are not coordinates, and always start at 0.
+/// PAF is always 0-based, so only `Zero` is supported for it.
+#[derive(clap::ValueEnum, Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CoordBase {
+    /// 0-based positions and half-open ranges `[start, end)`.
+    #[default]
+    Zero,
+    /// 1-based positions and inclusive ranges `[start, end]`.
+    One,
+}
+
+impl CoordBase {
+    /// The 0-based position `pos` in this base.
+    pub fn pos(&self, pos: usize) -> usize {
+        match self {
+            CoordBase::Zero => pos,
+            CoordBase::One => pos + 1,
+        }
+    }
+
+    /// The 0-based half-open range `start..end` in this base.
+    /// The end is the same in both, since only the start shifts.
+    pub fn range(&self, start: usize, end: usize) -> (usize, usize) {
+        (self.pos(start), end)
+    }
+}
+
+/// The format of the `--output` file.
+#[derive(clap::ValueEnum, Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// `{cost},{cigar}` lines.
+    #[default]
+    Csv,
+    /// Tab-separated `pair`, `len_a`, `len_b`, `cost`, `blast_identity`,
+    /// `gap_compressed_identity`, `cigar` columns, with a header.
+    Tsv,
+    /// One JSON object per line, with the same fields as `Tsv`, the aligned ranges
+    /// `a_start`, `a_end`, `b_start`, `b_end` in `--coord-base`, and a
+    /// `cost_breakdown` of the (unit) cost into substitutions, gap opens and gap extensions.
+    Jsonl,
+    /// PAF, with `b` as the query and `a` as the target.
+    /// Column 10 over column 11 is the BLAST identity, and the `de:f` tag is
+    /// the gap-compressed divergence, as in minimap2.
+    /// Coordinates are always 0-based.
+    Paf,
+}
+
+/// Writes the result of each aligned pair.
+pub trait ResultWriter {
+    fn write(&mut self, a: Seq, b: Seq, cost: Cost, cigar: &Cigar) -> io::Result<()>;
+    /// Called once after the last pair.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Discards all results.
+pub struct NullWriter;
+
+impl ResultWriter for NullWriter {
+    fn write(&mut self, _a: Seq, _b: Seq, _cost: Cost, _cigar: &Cigar) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct CsvWriter<W: Write> {
+    pub w: W,
+    pub cigar_style: CigarStyle,
+}
+
+impl<W: Write> ResultWriter for CsvWriter<W> {
+    fn write(&mut self, _a: Seq, _b: Seq, cost: Cost, cigar: &Cigar) -> io::Result<()> {
+        writeln!(self.w, "{cost},{}", self.cigar_style.format(cigar))
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+pub struct TsvWriter<W: Write> {
+    pub w: W,
+    pub cigar_style: CigarStyle,
+    pair: usize,
+}
+
+impl<W: Write> ResultWriter for TsvWriter<W> {
+    fn write(&mut self, a: Seq, b: Seq, cost: Cost, cigar: &Cigar) -> io::Result<()> {
+        if self.pair == 0 {
+            writeln!(
+                self.w,
+                "pair\tlen_a\tlen_b\tcost\tblast_identity\tgap_compressed_identity\tcigar"
+            )?;
+        }
+        let stats = CigarStats::new(cigar);
+        writeln!(
+            self.w,
+            "{}\t{}\t{}\t{cost}\t{:.4}\t{:.4}\t{}",
+            self.pair,
+            a.len(),
+            b.len(),
+            stats.blast_identity(),
+            stats.gap_compressed_identity(),
+            self.cigar_style.format(cigar)
+        )?;
+        self.pair += 1;
+        Ok(())
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+pub struct JsonlWriter<W: Write> {
+    pub w: W,
+    pub cigar_style: CigarStyle,
+    pub coord_base: CoordBase,
+    pair: usize,
+}
+
+impl<W: Write> ResultWriter for JsonlWriter<W> {
+    fn write(&mut self, a: Seq, b: Seq, cost: Cost, cigar: &Cigar) -> io::Result<()> {
+        // Cigar strings only contain digits and letters, so no escaping is needed.
+        let stats = CigarStats::new(cigar);
+        // All aligners use unit costs.
+        let bd = CostBreakdown::new(cigar, &AffineCost::unit());
+        let (a_start, a_end) = self.coord_base.range(0, a.len());
+        let (b_start, b_end) = self.coord_base.range(0, b.len());
+        writeln!(
+            self.w,
+            r#"{{"pair":{},"len_a":{},"len_b":{},"a_start":{a_start},"a_end":{a_end},"b_start":{b_start},"b_end":{b_end},"cost":{cost},"blast_identity":{:.4},"gap_compressed_identity":{:.4},"cigar":"{}","cost_breakdown":{{"substitutions":{},"gap_opens":{},"gap_extends":{},"sub_cost":{},"open_cost":{},"extend_cost":{}}}}}"#,
+            self.pair,
+            a.len(),
+            b.len(),
+            stats.blast_identity(),
+            stats.gap_compressed_identity(),
+            self.cigar_style.format(cigar),
+            bd.substitutions,
+            bd.gap_opens,
+            bd.gap_extends,
+            bd.sub_cost,
+            bd.open_cost,
+            bd.extend_cost
+        )?;
+        self.pair += 1;
+        Ok(())
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+/// Pairs are named `a{i}` and `b{i}`, since input names are not kept.
+pub struct PafWriter<W: Write> {
+    pub w: W,
+    pub cigar_style: CigarStyle,
+    pair: usize,
+}
+
+impl<W: Write> ResultWriter for PafWriter<W> {
+    fn write(&mut self, a: Seq, b: Seq, _cost: Cost, cigar: &Cigar) -> io::Result<()> {
+        let stats = CigarStats::new(cigar);
+        let matches = stats.matches;
+        let block_len = stats.columns();
+        let edits = block_len - matches;
+        let divergence = 1. - stats.gap_compressed_identity();
+        let (i, n, m) = (self.pair, a.len(), b.len());
+        writeln!(
+            self.w,
+            "b{i}\t{m}\t0\t{m}\t+\ta{i}\t{n}\t0\t{n}\t{matches}\t{block_len}\t255\tNM:i:{edits}\tde:f:{divergence:.4}\tcg:Z:{}",
+            self.cigar_style.format(cigar)
+        )?;
+        self.pair += 1;
+        Ok(())
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+impl OutputFormat {
+    /// A writer for this format. Coordinates are written in `coord_base`,
+    /// except for `Paf`, which is always 0-based.
+    pub fn writer<'w, W: Write + 'w>(
+        &self,
+        w: W,
+        cigar_style: CigarStyle,
+        coord_base: CoordBase,
+    ) -> Box<dyn ResultWriter + 'w> {
+        match self {
+            OutputFormat::Csv => Box::new(CsvWriter { w, cigar_style }),
+            OutputFormat::Tsv => Box::new(TsvWriter {
+                w,
+                cigar_style,
+                pair: 0,
+            }),
+            OutputFormat::Jsonl => Box::new(JsonlWriter {
+                w,
+                cigar_style,
+                coord_base,
+                pair: 0,
+            }),
+            OutputFormat::Paf => Box::new(PafWriter {
+                w,
+                cigar_style,
+                pair: 0,
+            }),
+        }
+    }
 }
 
 /// Globally align pairs of sequences using A*PA.
@@ -53,35 +374,119 @@ pub struct Cli {
     #[clap(short, long, value_parser = value_parser!(PathBuf), display_order = 1)]
     pub input: Option<PathBuf>,
 
-    /// Write a .csv of `{cost},{cigar}` lines
+    /// A Fasta file with a single reference sequence. Each sequence in the
+    /// Fasta `--input` is then aligned to it, instead of aligning consecutive pairs.
+    /// The profile and seeds of the reference are built once. Requires an A*PA2 aligner.
+    #[clap(long, value_parser = value_parser!(PathBuf), requires = "input", display_order = 1)]
+    pub reference: Option<PathBuf>,
+
+    /// Write a .csv of `{cost},{cigar}` lines, or another `--format`.
     #[clap(short, long, value_parser = value_parser!(PathBuf), display_order = 1)]
     pub output: Option<PathBuf>,
 
+    /// The format of the output file.
+    #[clap(long, default_value_t, value_enum, display_order = 1)]
+    pub format: OutputFormat,
+
+    /// Write `M` instead of `=` and `X` in output cigars.
+    #[clap(long)]
+    pub no_eqx: bool,
+
+    /// Write 0-based half-open or 1-based inclusive coordinates.
+    /// Only `zero` is supported for `--format paf`.
+    #[clap(long, default_value_t, value_enum)]
+    pub coord_base: CoordBase,
+
     /// The aligner to use.
     #[clap(long, default_value = "astarpa2-full")]
     pub aligner: AlignerType,
 
+    /// Checkpoint band doubling progress to this file, to resume long alignments after a crash.
+    /// Only supported for A*PA2.
+    #[clap(long, value_parser = value_parser!(PathBuf))]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Resume from the existing checkpoint instead of starting over.
+    #[clap(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// Print statistics of each pair to stdout: the number of tries, the maximal
+    /// band width, and the time spent building the heuristic, searching, and tracing back.
+    /// Only supported for A*PA2.
+    #[clap(long, conflicts_with = "checkpoint")]
+    pub stats: bool,
+
+    /// Skip pairs containing bytes that A*PA2 does not support instead of panicking:
+    /// anything but ACGT (case-insensitive, with U as T). A*PA supports all bytes.
+    #[clap(long)]
+    pub skip_invalid: bool,
+
     /// Options to generate an input pair.
     #[clap(flatten, next_help_heading = "Generated input")]
     pub generate: pa_generate::DatasetGenerator,
 }
 
 impl Cli {
+    pub fn cigar_style(&self) -> CigarStyle {
+        CigarStyle {
+            use_eqx: !self.no_eqx,
+        }
+    }
+
+    /// Check combinations of arguments that clap cannot express.
+    ///
+    /// Call this right after parsing; `Error::exit` prints the usage like any other parse error.
+    pub fn validate(&self) -> Result<(), clap::Error> {
+        if self.format == OutputFormat::Paf && self.coord_base == CoordBase::One {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "PAF coordinates are always 0-based; --coord-base one is not supported for --format paf.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// A writer for `--output` in the chosen `--format`, or a `NullWriter`.
+    pub fn result_writer(&self) -> io::Result<Box<dyn ResultWriter>> {
+        Ok(match &self.output {
+            Some(path) => {
+                let f = BufWriter::new(File::create(path)?);
+                self.format.writer(f, self.cigar_style(), self.coord_base)
+            }
+            None => Box::new(NullWriter),
+        })
+    }
+
     /// Call the given function for each pair in the input.
     pub fn process_input_pairs(&self, mut run_pair: impl FnMut(Seq, Seq) -> ControlFlow<()>) {
-        if let Some(input) = &self.input {
-            // Parse file
-            let files = if input.is_file() {
-                vec![input.clone()]
-            } else {
-                input
-                    .read_dir()
-                    .expect(&format!("{} is not a file or directory", input.display()))
-                    .map(|x| x.unwrap().path())
-                    .collect_vec()
-            };
-
-            'outer: for f in files {
+        let mut pair_idx = 0;
+        let mut run_pair = |a: Seq, b: Seq| {
+            pair_idx += 1;
+            if self.skip_invalid && self.aligner != AlignerType::Astarpa {
+                if let Err(e) = astarpa2::check_input(a, b) {
+                    eprintln!("Skipping pair {pair_idx}: {e}");
+                    return ControlFlow::Continue(());
+                }
+            }
+            run_pair(a, b)
+        };
+        if let Some(reference) = &self.reference {
+            // Align each query to the reference, which is read only once.
+            let reference = read_reference(reference);
+            'queries: for f in input_files(self.input.as_ref().unwrap()) {
+                let ext = f.extension().expect("Unknown file extension");
+                assert!(
+                    ext == "fna" || ext == "fa" || ext == "fasta",
+                    "With --reference, queries must be in {{fna,fa,fasta}} files, not {ext:?}."
+                );
+                for query in fasta::Reader::new(BufReader::new(File::open(&f).unwrap())).records() {
+                    if let ControlFlow::Break(()) = run_pair(&reference, query.unwrap().seq()) {
+                        break 'queries;
+                    }
+                }
+            }
+        } else if let Some(input) = &self.input {
+            'outer: for f in input_files(input) {
                 match f.extension().expect("Unknown file extension") {
                     ext if ext == "seq" || ext == "txt" => {
                         let f = std::fs::File::open(&f).unwrap();
@@ -132,3 +537,32 @@ impl Cli {
         }
     }
 }
+
+/// `input` itself if it is a file, and otherwise the files in the directory `input`.
+fn input_files(input: &PathBuf) -> Vec<PathBuf> {
+    if input.is_file() {
+        vec![input.clone()]
+    } else {
+        input
+            .read_dir()
+            .expect(&format!("{} is not a file or directory", input.display()))
+            .map(|x| x.unwrap().path())
+            .collect_vec()
+    }
+}
+
+/// The single sequence in the Fasta file at `path`.
+fn read_reference(path: &PathBuf) -> Vec<u8> {
+    let mut records = fasta::Reader::new(BufReader::new(File::open(path).unwrap())).records();
+    let reference = records
+        .next()
+        .expect("The reference file is empty.")
+        .unwrap()
+        .seq()
+        .to_vec();
+    assert!(
+        records.next().is_none(),
+        "The reference file must contain a single sequence."
+    );
+    reference
+}
@@ -3,39 +3,94 @@
 use clap::Parser;
 use pa_bin::Cli;
 use pa_types::*;
-use std::{
-    io::{BufWriter, Write},
-    ops::ControlFlow,
-};
+use std::ops::ControlFlow;
 
 fn main() {
     let args = Cli::parse();
+    if let Err(e) = args.validate() {
+        e.exit();
+    }
 
     let mut aligner = args.aligner.build();
 
-    let mut out_file = args
-        .output
-        .as_ref()
-        .map(|o| BufWriter::new(std::fs::File::create(o).unwrap()));
+    // A*PA2 aligner used for checkpointing.
+    let mut checkpoint_aligner = args.checkpoint.as_ref().map(|path| {
+        if !args.resume {
+            std::fs::remove_file(path).ok();
+        }
+        args.aligner
+            .build_astarpa2()
+            .expect("--checkpoint is only supported for A*PA2 aligners.")
+    });
+
+    // A*PA2 aligner used for printing statistics.
+    let mut stats_aligner = args.stats.then(|| {
+        args.aligner
+            .build_astarpa2()
+            .expect("--stats is only supported for A*PA2 aligners.")
+    });
+    let mut printed_header = false;
+
+    // A*PA2 aligner used with --reference, and the prepared reference.
+    let mut reference_aligner = args.reference.is_some().then(|| {
+        args.aligner
+            .build_astarpa2()
+            .expect("--reference is only supported for A*PA2 aligners.")
+    });
+    let mut reference = None;
+
+    let mut writer = args.result_writer().unwrap_or_else(|e| {
+        let path = args.output.as_ref().unwrap();
+        eprintln!("Cannot create {}: {e}", path.display());
+        std::process::exit(1);
+    });
 
     let mut done = 0;
+    let mut checkpoint_error = None;
 
     eprint!("Done: {done:>3}\r");
 
     // Process the input.
     args.process_input_pairs(|a: Seq, b: Seq| {
         // Run the pair.
-        let (cost, cigar) = aligner.align(a, b);
+        let (cost, cigar) = if let Some(aligner) = &mut checkpoint_aligner {
+            match aligner.align_with_checkpoint(a, b, args.checkpoint.as_ref().unwrap()) {
+                Ok(r) => r,
+                Err(e) => {
+                    checkpoint_error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+        } else if let Some(aligner) = &mut stats_aligner {
+            let (cost, cigar, stats) = aligner.align_with_stats(a, b);
+            let (header, values) = stats.values();
+            if !printed_header {
+                println!("{}", header.join(" "));
+                printed_header = true;
+            }
+            println!("{}", values.join(" "));
+            (cost, cigar)
+        } else if let Some(aligner) = &mut reference_aligner {
+            // `a` is the reference for all pairs, so it is prepared only once.
+            let reference = reference.get_or_insert_with(|| aligner.reference(a));
+            aligner.align_to_reference(reference, b)
+        } else {
+            aligner.align(a, b)
+        };
 
         done += 1;
         eprint!("Done: {done:>3}\r");
 
-        if let Some(f) = &mut out_file {
-            writeln!(f, "{cost},{}", cigar.unwrap().to_string()).unwrap();
-        }
+        writer.write(a, b, cost, &cigar.unwrap()).unwrap();
         ControlFlow::Continue(())
     });
+    writer.finish().unwrap();
     eprintln!();
+    if let Some(e) = checkpoint_error {
+        let path = args.checkpoint.as_ref().unwrap();
+        eprintln!("Checkpoint {} failed: {e}", path.display());
+        std::process::exit(1);
+    }
 }
 
 #[cfg(test)]
@@ -44,4 +99,195 @@ mod test {
     fn cli_test() {
         <super::Cli as clap::CommandFactory>::command().debug_assert();
     }
+
+    #[test]
+    fn reference() {
+        use clap::Parser;
+        use std::ffi::OsStr;
+        let dir = std::env::temp_dir().join(format!("pa-bin-reference-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (reference, queries) = (dir.join("reference.fa"), dir.join("queries.fa"));
+        std::fs::write(&reference, ">ref\nACGTACGT\n").unwrap();
+        std::fs::write(&queries, ">q0\nACGT\n>q1\nACCTACGT\n>q2\nTT\n").unwrap();
+        let args = super::Cli::parse_from([
+            OsStr::new("pa-bin"),
+            OsStr::new("--input"),
+            queries.as_os_str(),
+            OsStr::new("--reference"),
+            reference.as_os_str(),
+        ]);
+        let mut pairs = vec![];
+        args.process_input_pairs(|a, b| {
+            pairs.push((a.to_vec(), b.to_vec()));
+            std::ops::ControlFlow::Continue(())
+        });
+        let expected =
+            [&b"ACGT"[..], b"ACCTACGT", b"TT"].map(|q| (b"ACGTACGT".to_vec(), q.to_vec()));
+        assert_eq!(pairs, expected);
+
+        let mut aligner = args.aligner.build_astarpa2().unwrap();
+        let prepared = aligner.reference(b"ACGTACGT");
+        for (a, b) in &pairs {
+            let cost = aligner.align_with_stats(a, b).0;
+            assert_eq!(aligner.align_to_reference(&prepared, b).0, cost);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn skip_invalid() {
+        use clap::Parser;
+        use std::ffi::OsStr;
+        let dir = std::env::temp_dir().join(format!("pa-bin-skip-invalid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.seq");
+        std::fs::write(
+            &input,
+            ">ACGT\n<acgu\n>ACNT\n<ACGT\n>AC-T\n<ACGT\n>ACGT\n<ACGA\n",
+        )
+        .unwrap();
+        let pairs = |aligner| {
+            let args = super::Cli::parse_from([
+                OsStr::new("pa-bin"),
+                OsStr::new("--input"),
+                input.as_os_str(),
+                OsStr::new("--skip-invalid"),
+                OsStr::new("--aligner"),
+                OsStr::new(aligner),
+            ]);
+            let mut pairs = vec![];
+            args.process_input_pairs(|a, b| {
+                pairs.push((a.to_vec(), b.to_vec()));
+                std::ops::ControlFlow::Continue(())
+            });
+            pairs
+        };
+        // RNA and lowercase input is supported by A*PA2, but `N` and `-` are not.
+        assert_eq!(
+            pairs("astarpa2-full"),
+            [
+                (b"ACGT".to_vec(), b"acgu".to_vec()),
+                (b"ACGT".to_vec(), b"ACGA".to_vec())
+            ]
+        );
+        // A*PA compares bytes, so nothing is skipped.
+        assert_eq!(pairs("astarpa").len(), 4);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stats_values() {
+        let mut aligner = pa_bin::AlignerType::Astarpa2Full.build_astarpa2().unwrap();
+        let (cost, _, stats) = aligner.align_with_stats(b"ACGTACGT", b"ACCTACGT");
+        assert_eq!(cost, 1);
+        let (header, values) = stats.values();
+        assert_eq!(header.len(), values.len());
+        for column in ["tries", "band", "pre", "search", "trace"] {
+            assert!(header.iter().any(|h| h.trim() == column));
+        }
+    }
+
+    #[test]
+    fn cigar_style() {
+        use pa_bin::CigarStyle;
+        use pa_types::{Cigar, CigarOp::*};
+        let mut cigar = Cigar::default();
+        for op in [Match, Match, Sub, Match, Ins, Del, Del, Sub] {
+            cigar.push(op);
+        }
+        assert_eq!(CigarStyle { use_eqx: true }.format(&cigar), cigar.to_string());
+        assert_eq!(CigarStyle { use_eqx: false }.format(&cigar), "4M1I2D1M");
+    }
+
+    #[test]
+    fn cigar_stats() {
+        use pa_bin::CigarStats;
+        use pa_types::{Cigar, CigarOp::*};
+        let mut cigar = Cigar::default();
+        for op in [Match, Match, Ins, Ins, Ins, Match, Sub, Del, Match] {
+            cigar.push(op);
+        }
+        let stats = CigarStats::new(&cigar);
+        assert_eq!(stats.gap_opens, 2);
+        assert_eq!(stats.columns(), 9);
+        // 4 matches in 9 columns.
+        assert_eq!(stats.blast_identity(), 4. / 9.);
+        // Each gap counts once: 4 matches, 1 mismatch, and 2 gaps.
+        assert_eq!(stats.gap_compressed_identity(), 4. / 7.);
+    }
+
+    #[test]
+    fn result_writers() {
+        use pa_bin::{CigarStyle, CoordBase, OutputFormat};
+        use pa_types::{Cigar, CigarOp::*};
+        let mut cigar = Cigar::default();
+        for op in [Match, Sub, Match, Ins] {
+            cigar.push(op);
+        }
+        let output_in = |format: OutputFormat, coord_base| {
+            let mut out = vec![];
+            let mut writer = format.writer(&mut out, CigarStyle::default(), coord_base);
+            writer.write(b"ACG", b"AGGT", 2, &cigar).unwrap();
+            writer.finish().unwrap();
+            drop(writer);
+            String::from_utf8(out).unwrap()
+        };
+        let output = |format| output_in(format, CoordBase::Zero);
+        assert_eq!(output(OutputFormat::Csv), "2,1=1X1=1I\n");
+        assert_eq!(
+            output(OutputFormat::Tsv),
+            "pair\tlen_a\tlen_b\tcost\tblast_identity\tgap_compressed_identity\tcigar\n0\t3\t4\t2\t0.5000\t0.5000\t1=1X1=1I\n"
+        );
+        assert_eq!(
+            output(OutputFormat::Jsonl),
+            r#"{"pair":0,"len_a":3,"len_b":4,"a_start":0,"a_end":3,"b_start":0,"b_end":4,"cost":2,"blast_identity":0.5000,"gap_compressed_identity":0.5000,"cigar":"1=1X1=1I","cost_breakdown":{"substitutions":1,"gap_opens":1,"gap_extends":1,"sub_cost":1,"open_cost":0,"extend_cost":1}}"#.to_string() + "\n"
+        );
+        assert_eq!(
+            output(OutputFormat::Paf),
+            "b0\t4\t0\t4\t+\ta0\t3\t0\t3\t2\t4\t255\tNM:i:2\tde:f:0.5000\tcg:Z:1=1X1=1I\n"
+        );
+        assert!(output_in(OutputFormat::Jsonl, CoordBase::One)
+            .contains(r#""a_start":1,"a_end":3,"b_start":1,"b_end":4,"#));
+        // Only coordinates change.
+        assert_eq!(
+            output_in(OutputFormat::Tsv, CoordBase::One),
+            output(OutputFormat::Tsv)
+        );
+        // PAF is always 0-based.
+        assert_eq!(
+            output_in(OutputFormat::Paf, CoordBase::One),
+            output(OutputFormat::Paf)
+        );
+    }
+
+    #[test]
+    fn paf_coord_base() {
+        use clap::{error::ErrorKind, Parser};
+        let args = |format| {
+            super::Cli::parse_from([
+                "pa-bin",
+                "--input",
+                "input.seq",
+                "--format",
+                format,
+                "--coord-base",
+                "one",
+            ])
+        };
+        assert_eq!(
+            args("paf").validate().unwrap_err().kind(),
+            ErrorKind::ArgumentConflict
+        );
+        assert!(args("jsonl").validate().is_ok());
+
+        // The output file is created when making the writer, and errors are returned.
+        let args = super::Cli::parse_from([
+            "pa-bin",
+            "--input",
+            "input.seq",
+            "--output",
+            "/nonexistent/output.csv",
+        ]);
+        assert!(args.result_writer().is_err());
+    }
 }
@@ -0,0 +1,103 @@
+//! MinHash prefiltering, to skip obviously dissimilar pairs in an all-vs-all.
+//!
+//! Each sequence is sketched by the minimal hash of its k-mers under
+//! `SKETCH_SIZE` hash functions. The fraction of equal minima estimates the
+//! Jaccard similarity of the k-mer sets.
+use bio::alphabets::{Alphabet, RankTransform};
+use pa_types::Seq;
+
+/// The k-mer length. K-mers are encoded in 2 bits per base.
+pub const K: u32 = 16;
+/// The number of hash functions, i.e. the length of each sketch.
+pub const SKETCH_SIZE: usize = 128;
+
+/// SplitMix64 finalizer, mixing all input bits into the output.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// The MinHash sketch of the k-mers of `seq` over `ACGT` (in any case).
+/// K-mers containing other characters, such as `N`, are skipped.
+/// Sequences without any k-mer, e.g. because they are shorter than `K`, have an empty sketch.
+pub fn sketch(ranks: &RankTransform, seq: Seq) -> Vec<u64> {
+    let mut sketch = vec![u64::MAX; SKETCH_SIZE];
+    let mut empty = true;
+    for run in seq.split(|c| !b"ACGT".contains(&c.to_ascii_uppercase())) {
+        if run.len() < K as usize {
+            continue;
+        }
+        for kmer in ranks.qgrams(K, run.iter().map(|c| c.to_ascii_uppercase())) {
+            empty = false;
+            for (i, min) in sketch.iter_mut().enumerate() {
+                *min = (*min).min(mix(kmer as u64 ^ mix(i as u64)));
+            }
+        }
+    }
+    if empty {
+        sketch.clear();
+    }
+    sketch
+}
+
+/// The estimated Jaccard similarity of the k-mer sets of two sketches.
+/// Empty sketches are not similar to anything.
+pub fn jaccard(a: &[u64], b: &[u64]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f32 / SKETCH_SIZE as f32
+}
+
+/// All pairs `(i, j)` with `i < j` whose estimated Jaccard similarity is at least `threshold`.
+/// Only these pairs need to be aligned in an all-vs-all.
+pub fn minhash_prefilter(seqs: &[impl AsRef<[u8]>], threshold: f32) -> Vec<(usize, usize)> {
+    let ranks = RankTransform::new(&Alphabet::new(b"ACGT"));
+    let sketches = seqs
+        .iter()
+        .map(|seq| sketch(&ranks, seq.as_ref()))
+        .collect::<Vec<_>>();
+    let mut pairs = vec![];
+    for i in 0..sketches.len() {
+        for j in i + 1..sketches.len() {
+            if jaccard(&sketches[i], &sketches[j]) >= threshold {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn similar_pairs() {
+        use pa_generate::{generate_model, ErrorModel::Uniform};
+        let (a, b) = generate_model(10000, 0.01, Uniform, 1);
+        let (c, _) = generate_model(10000, 0.01, Uniform, 2);
+        let lower = a.to_ascii_lowercase();
+        // The case of the input does not matter.
+        assert_eq!(
+            minhash_prefilter(&[&a, &b, &c, &lower], 0.5),
+            [(0, 1), (0, 3), (1, 3)]
+        );
+    }
+
+    #[test]
+    fn short_and_non_acgt() {
+        let short = b"ACGTACGT";
+        let n = b"NNNNNNNNNNNNNNNNNNNNNNNN";
+        // Sequences without k-mers are not similar to each other, or to themselves.
+        assert!(minhash_prefilter(&[short, short], 0.5).is_empty());
+        assert!(minhash_prefilter(&[n, n], 0.5).is_empty());
+        // Runs of `ACGT` between `N`s are still sketched.
+        let (a, _) = pa_generate::generate_model(1000, 0.0, pa_generate::ErrorModel::Uniform, 3);
+        let mut b = a.clone();
+        b[500] = b'N';
+        assert_eq!(minhash_prefilter(&[a, b], 0.5), [(0, 1)]);
+    }
+}
@@ -159,9 +159,42 @@ fn bench(c: &mut Criterion) {
     }
 }
 
+/// Wide inputs, where `a` and `h` do not fit in cache.
+/// Compare `cargo bench --bench nw -- wide` with and without `--features prefetch`.
+fn bench_wide(c: &mut Criterion) {
+    let c = &mut c.benchmark_group("wide");
+    let rng = &mut get_rng(Some(31415));
+    let a = &random_sequence(1 << 20, rng);
+    let b = &random_sequence(512, rng);
+    let (ref pa, ref pb) = BitProfile::build(a, b);
+    c.bench_function("simd4rowx/2", |bb| {
+        bb.iter(|| {
+            let h = &mut vec![<(u64, u64)>::one(); pa.len()];
+            let v = &mut vec![V::one(); pb.len()];
+            simd::compute::<2, (u64, u64), 4>(pa, pb, h, v, true)
+        })
+    });
+}
+
+/// Full-column vs banded computation on a similar pair, where a narrow band
+/// around the diagonal suffices.
+fn bench_banded(c: &mut Criterion) {
+    let c = &mut c.benchmark_group("banded");
+    let (ref a, ref b) = pa_generate::uniform_fixed(4096, 0.01);
+    // 2 words above and below the diagonal.
+    let bands = (0..a.len())
+        .map(|i| ((i / 64).saturating_sub(2), i / 64 + 3))
+        .collect::<Vec<_>>();
+    if TEST {
+        assert_eq!(edit_distance(a, b), banded_distance(a, b, &bands).unwrap());
+    }
+    c.bench_function("full", |bb| bb.iter(|| edit_distance(a, b)));
+    c.bench_function("banded", |bb| bb.iter(|| banded_distance(a, b, &bands)));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().measurement_time(Duration::from_millis(500)).warm_up_time(Duration::from_millis(100));
-    targets = bench
+    targets = bench, bench_banded, bench_wide
 );
 criterion_main!(benches);
@@ -0,0 +1,79 @@
+//! Unit-cost alignment with traceback, for any `Profile`.
+use crate::*;
+use itertools::izip;
+use pa_types::{Cigar, CigarOp, Cost, I};
+
+/// The unit-cost edit distance and an optimal alignment of sequences that were
+/// already packed using any `Profile`, so that e.g. a reference can be packed
+/// only once.
+///
+/// `b_len` is the length of `b`, since the last word of `pb` may be padded.
+/// This stores the vertical deltas of all columns, using `O(nm/W)` memory.
+/// Ties are broken towards matches, then insertions, deletions, and substitutions.
+///
+/// ```
+/// # use pa_bitpacking::*;
+/// let (a, b) = (b"ACGTTGCA", b"ACNTGGCA");
+/// let (pa, pb) = ScatterProfile::build(a, b);
+/// let (cost, cigar) = align_profile::<ScatterProfile>(&pa, &pb, b.len());
+/// assert_eq!((cost, cigar.to_string().as_str()), (1, "4=1X3="));
+///
+/// let (pa, pb) = BitProfile::build(a, b"ACTTGGCA");
+/// assert_eq!(
+///     align_profile::<BitProfile>(&pa, &pb, 8).0,
+///     edit_distance_profile(&pa, &pb, 8)
+/// );
+/// ```
+pub fn align_profile<P: Profile>(pa: &[P::A], pb: &[P::B], b_len: usize) -> (Cost, Cigar) {
+    assert_eq!(pb.len(), b_len.div_ceil(W), "b_len does not match pb");
+
+    // `v[i]`: the vertical deltas of column `i`.
+    let mut v = vec![vec![V::one(); pb.len()]];
+    for ca in pa {
+        let mut col = v.last().unwrap().clone();
+        let mut h = H::one();
+        for (cb, v) in izip!(pb, &mut col) {
+            myers::compute_block::<P, H>(&mut h, v, ca, cb);
+        }
+        v.push(col);
+    }
+    // `tops[i][k]`: the value at the top of word `k` of column `i`.
+    let tops = v
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            let mut tops = vec![i as Cost];
+            for v in col {
+                tops.push(tops.last().unwrap() + v.value());
+            }
+            tops
+        })
+        .collect::<Vec<_>>();
+    let g = |i: usize, j: usize| {
+        let (k, r) = (j / W, j % W);
+        tops[i][k] + v[i].get(k).map_or(0, |v| v.value_of_prefix(r as I))
+    };
+
+    let (mut i, mut j) = (pa.len(), b_len);
+    let cost = g(i, j);
+    let mut cigar = Cigar::default();
+    while i > 0 || j > 0 {
+        let cur = g(i, j);
+        // Greedy matching is always optimal for unit costs.
+        let (op, di, dj) = if i > 0 && j > 0 && P::is_match(pa, pb, i as I - 1, j as I - 1) {
+            (CigarOp::Match, 1, 1)
+        } else if j > 0 && g(i, j - 1) + 1 == cur {
+            (CigarOp::Ins, 0, 1)
+        } else if i > 0 && g(i - 1, j) + 1 == cur {
+            (CigarOp::Del, 1, 0)
+        } else {
+            debug_assert_eq!(g(i - 1, j - 1) + 1, cur);
+            (CigarOp::Sub, 1, 1)
+        };
+        cigar.push(op);
+        i -= di;
+        j -= dj;
+    }
+    cigar.reverse();
+    (cost, cigar)
+}
@@ -0,0 +1,124 @@
+//! Bitpacked computation restricted to a band of words per column.
+use crate::*;
+use itertools::izip;
+use pa_types::{Cost, Seq, I};
+use std::cmp::min;
+
+/// The unit-cost distance between `a` and `b`, computing only words
+/// `bands[i].0 .. bands[i].1` of `b` in column `i+1`.
+///
+/// Bands must be monotone: both their start and end may not decrease.
+/// Outside the band, all deltas are assumed to be `+1`: the horizontal delta at
+/// the top of the band, and the vertical deltas of words that newly enter the
+/// band from below. The result is an upper bound on the distance, and exact when
+/// an optimal path stays inside the band.
+///
+/// Returns `None` when the band of the last column does not contain the last word.
+pub fn banded_distance(a: Seq, b: Seq, bands: &[(usize, usize)]) -> Option<Cost> {
+    let (pa, pb) = BitProfile::build(a, b);
+    banded_distance_profile::<BitProfile>(&pa, &pb, b.len(), bands)
+}
+
+/// As `banded_distance`, but for sequences that were already packed using
+/// any `Profile`. `b_len` is the length of `b`.
+pub fn banded_distance_profile<P: Profile>(
+    pa: &[P::A],
+    pb: &[P::B],
+    b_len: usize,
+    bands: &[(usize, usize)],
+) -> Option<Cost> {
+    assert_eq!(pa.len(), bands.len());
+    assert_eq!(pb.len(), b_len.div_ceil(W), "b_len does not match pb");
+    let words = pb.len();
+    let mut v = vec![V::one(); words];
+
+    // The vertical delta of word `k` in the previous column, given its band.
+    let word_value = |v: &[V], k: usize, (_, prev_end): (usize, usize)| {
+        if k < prev_end {
+            v[k].value()
+        } else {
+            W as Cost
+        }
+    };
+
+    // The value at the top of the band of the previous column.
+    let mut top: Cost = 0;
+    let mut prev = (0, 0);
+    for (ca, &(start, end)) in izip!(pa, bands) {
+        let end = min(end, words);
+        assert!(start <= end, "Empty band {start}..{end}");
+        assert!(
+            start >= prev.0 && end >= prev.1,
+            "Bands must be monotone: {start}..{end} after {}..{}",
+            prev.0,
+            prev.1
+        );
+
+        // Move the top of the band down to `start`, and step right with delta +1.
+        top += (prev.0..start)
+            .map(|k| word_value(&v, k, prev))
+            .sum::<Cost>()
+            + 1;
+
+        // Words entering the band from below start with vertical deltas +1.
+        for v in &mut v[prev.1.max(start)..end] {
+            *v = V::one();
+        }
+
+        let mut h = H::one();
+        for (cb, v) in izip!(&pb[start..end], &mut v[start..end]) {
+            myers::compute_block::<P, H>(&mut h, v, ca, cb);
+        }
+        prev = (start, end);
+    }
+
+    if prev.1 < words {
+        return None;
+    }
+    let mut dist = top;
+    for k in prev.0..words {
+        let rows = b_len - k * W;
+        dist += if rows >= W {
+            v[k].value()
+        } else {
+            v[k].value_of_prefix(rows as I)
+        };
+    }
+    Some(dist)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A full band gives the exact distance, and a narrower one an upper bound.
+    #[test]
+    fn banded_distance() {
+        // Without columns, there is no band that contains the last word.
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| !a.is_empty()) {
+            let d = edit_distance(&a, &b);
+            let words = b.len().div_ceil(W);
+            let full = vec![(0, words); a.len()];
+            assert_eq!(super::banded_distance(&a, &b, &full), Some(d), "{params:?}");
+
+            // A band of 2 words around the main diagonal, growing to the end.
+            let mut band = (0..a.len())
+                .map(|i| {
+                    let k = i / W;
+                    let end = min(k + 1, words);
+                    (min(k.saturating_sub(1), end), end)
+                })
+                .collect::<Vec<_>>();
+            band.last_mut().unwrap().1 = words;
+            if let Some(banded) = super::banded_distance(&a, &b, &band) {
+                assert!(banded >= d, "{params:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn banded_distance_missing_last_word() {
+        let (a, b) = (vec![b'A'; 10], vec![b'A'; 2 * W]);
+        assert_eq!(super::banded_distance(&a, &b, &vec![(0, 1); a.len()]), None);
+    }
+}
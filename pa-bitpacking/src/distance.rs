@@ -0,0 +1,72 @@
+//! Cost-only unit-cost edit distance between two full sequences.
+use crate::{bit_profile::Bits, *};
+use pa_types::{Cost, Seq};
+use std::cmp::min;
+
+/// The unit-cost edit distance between `a` and `b`, without traceback.
+///
+/// Computes the full DP matrix using (SIMD) bitpacking, so this is the fastest
+/// option when the full matrix is needed anyway.
+pub fn edit_distance(a: Seq, b: Seq) -> Cost {
+    if a.is_empty() || b.is_empty() {
+        return (a.len() + b.len()) as Cost;
+    }
+    let (pa, pb) = BitProfile::build(a, b);
+    edit_distance_profile(&pa, &pb, b.len())
+}
+
+/// As `edit_distance`, but for sequences that were already packed using
+/// `BitProfile::build`, so that e.g. a reference can be packed only once.
+///
+/// `b_len` is the length of `b`, since the last word of `pb` may be padded.
+/// For other profiles, use `align_profile`.
+///
+/// ```
+/// # use pa_bitpacking::*;
+/// let (pa, pb) = BitProfile::build(b"ACGTTGCA", b"ACTTGGCA");
+/// assert_eq!(edit_distance_profile(&pa, &pb, 8), edit_distance(b"ACGTTGCA", b"ACTTGGCA"));
+/// ```
+pub fn edit_distance_profile(pa: &[Bits], pb: &[Bits], b_len: usize) -> Cost {
+    assert_eq!(pb.len(), b_len.div_ceil(W), "b_len does not match pb");
+    if pa.is_empty() || pb.is_empty() {
+        return (pa.len() + b_len) as Cost;
+    }
+    let mut h = vec![H::one(); pa.len()];
+    let mut v = vec![V::one(); pb.len()];
+    compute(pa, pb, &mut h, &mut v, true);
+
+    // The top of the last column has value `|a|`. Add the vertical deltas of
+    // all rows of `b`, ignoring the padding at the end of the last word.
+    let mut dist = pa.len() as Cost;
+    for (k, v) in v.iter().enumerate() {
+        let rows = min(W, b_len - k * W);
+        let mask = if rows == W { B::MAX } else { (1 << rows) - 1 };
+        dist += (v.p() & mask).count_ones() as Cost - (v.m() & mask).count_ones() as Cost;
+    }
+    dist
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Lengths of `b` that are not a multiple of the word size are padded internally.
+    #[test]
+    fn bitpacking_partial_words() {
+        for len in [W - 1, W, W + 1, 3 * W + 5] {
+            for seed in 0..5 {
+                let (a, b) =
+                    pa_generate::generate_model(len, 0.1, pa_generate::ErrorModel::Uniform, seed);
+                let d = triple_accel::levenshtein_exp(&a, &b) as Cost;
+                assert_eq!(edit_distance(&a, &b), d);
+                let (pa, pb) = BitProfile::build(&a, &b);
+                assert_eq!(edit_distance_profile(&pa, &pb, b.len()), d);
+                let bands = vec![(0, pb.len()); a.len()];
+                assert_eq!(
+                    banded_distance_profile::<BitProfile>(&pa, &pb, b.len(), &bands),
+                    Some(d)
+                );
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{B, W};
 use pa_types::{Cost, I};
 
+const WI: I = W as I;
+
 #[derive(Clone, Default, Copy, PartialEq, Eq, Debug)]
 pub struct V(B, B);
 impl V {
@@ -48,25 +50,25 @@ impl V {
     pub fn m(&self) -> B {
         self.1
     }
-    pub(crate) fn one_mut(&mut self) -> &mut u64 {
+    pub(crate) fn one_mut(&mut self) -> &mut B {
         &mut self.0
     }
     pub fn value_to(v: &Vec<V>, j: I) -> Cost {
         let mut s = 0;
-        for vj in &v[0..j as usize / 64] {
+        for vj in &v[0..j as usize / W] {
             s += vj.value();
         }
-        if j % 64 != 0 {
-            s += v[j as usize / 64].value_of_prefix(j % 64);
+        if j % WI != 0 {
+            s += v[j as usize / W].value_of_prefix(j % WI);
         }
         s
     }
     pub fn value_from(v: &Vec<V>, j: I) -> Cost {
         let mut s = 0;
-        if j % 64 != 0 {
-            s += v[j as usize / 64].value_of_suffix(64 - j % 64);
+        if j % WI != 0 {
+            s += v[j as usize / W].value_of_suffix(WI - j % WI);
         }
-        for vj in &v[j.div_ceil(64) as usize..] {
+        for vj in &v[j.div_ceil(WI) as usize..] {
             s += vj.value();
         }
         s
@@ -0,0 +1,102 @@
+//! Furthest reaching points per diagonal, for interop with diagonal transition.
+use crate::*;
+use pa_types::{Cost, Seq, I};
+use std::cmp::min;
+
+/// For each diagonal `d = i - j` with `|d| <= s`, the furthest reaching point
+/// with cost at most `s`, as a pair `(fr, cost)` with `fr = i + j`.
+///
+/// This uses the same `(d, fr)` coordinates as diagonal transition.
+/// Element `k` of the result corresponds to diagonal `k - min(s, |b|)`.
+/// Since the first cell of each of these diagonals has cost `|d| <= s`, every
+/// diagonal has a furthest reaching point.
+///
+/// The full matrix is computed using bitpacking. Only the cells within `s` of
+/// the main diagonal are read back, at a cost of `O(s)` per column.
+pub fn furthest_reaching(a: Seq, b: Seq, s: Cost) -> Vec<(I, Cost)> {
+    assert!(s >= 0);
+    let n = a.len() as I;
+    let m = b.len() as I;
+    let d_min = -min(s, m);
+    let d_max = min(s, n);
+    let mut fr = vec![(I::MIN, Cost::MAX); (d_max - d_min + 1) as usize];
+
+    // The first column: D(0, j) = j.
+    for j in 0..=-d_min {
+        fr[(-j - d_min) as usize] = (j, j);
+    }
+    if a.is_empty() || b.is_empty() {
+        // The first row: D(i, 0) = i.
+        for i in 0..=d_max {
+            fr[(i - d_min) as usize] = (i, i);
+        }
+        return fr;
+    }
+
+    let (pa, pb) = BitProfile::build(a, b);
+    let mut v = vec![V::one(); pb.len()];
+    // The vertical delta into row `j > 0`.
+    let delta = |v: &[V], j: I| {
+        let (k, r) = ((j - 1) as usize / W, (j - 1) as usize % W);
+        ((v[k].p() >> r) & 1) as Cost - ((v[k].m() >> r) & 1) as Cost
+    };
+
+    for (i, ca) in (1..=n).zip(&pa) {
+        let mut h = H::one();
+        for (v, cb) in v.iter_mut().zip(&pb) {
+            myers::compute_block::<BitProfile, H>(&mut h, v, ca, cb);
+        }
+
+        // The value at the first row on a diagonal within range.
+        let lo = (i - s).max(0);
+        if lo > m {
+            continue;
+        }
+        let (k, r) = (lo as usize / W, lo as usize % W);
+        let mut cost = i + v[..k].iter().map(|v| v.value()).sum::<Cost>();
+        if r > 0 {
+            cost += v[k].value_of_prefix(r as I);
+        }
+
+        // Columns are processed left to right, so later points on a diagonal
+        // are always further.
+        for j in lo..=min(m, i + s) {
+            if j > lo {
+                cost += delta(&v, j);
+            }
+            if cost <= s {
+                fr[(i - j - d_min) as usize] = (i + j, cost);
+            }
+        }
+    }
+    fr
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Bitpacked furthest reaching points must match a plain DP.
+    #[test]
+    fn furthest_reaching() {
+        for ((a, b), params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 100) {
+            let d = pa_test::dp_matrix(&a, &b, &pa_types::CostModel::unit());
+            for s in [0, 1, 5, 20] {
+                let fr = super::furthest_reaching(&a, &b, s);
+                let d_min = -s.min(b.len() as Cost);
+                for (k, &(fr, cost)) in fr.iter().enumerate() {
+                    let diag = k as I + d_min;
+                    let (i, j) = ((fr + diag) / 2, (fr - diag) / 2);
+                    assert_eq!(d[i as usize][j as usize], cost, "{params:?}");
+                    assert!(cost <= s, "{params:?}");
+                    // No further point on this diagonal has cost at most `s`.
+                    let further = (1..)
+                        .map(|x| (i + x, j + x))
+                        .take_while(|&(i, j)| i as usize <= a.len() && j as usize <= b.len())
+                        .any(|(i, j)| d[i as usize][j as usize] <= s);
+                    assert!(!further, "{params:?}");
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,16 @@
 //! Bitpacking methods.
 //!
+//! Public building blocks:
+//! - `BitProfile`, `ScatterProfile` and `FreeNProfile`: profiles of the input sequences.
+//! - `myers::compute_block` and `myers::compute_block_simd`: the Myers'99 kernels for a single word or `L` words.
+//! - `simd::compute` and `simd::fill`: SIMD computation of a rectangle, using `N` vectors of `L` lanes.
+//!   With the `no_simd` feature, these and all other uses of `std::simd` are compiled out.
+//!   `compute` and `fill` dispatch to the SIMD or scalar versions accordingly.
+//! - `edit_distance` and `banded_distance`: complete unit-cost distance computations.
+//!   The `_profile` variants take sequences that were already packed.
+//! - `align_profile`: unit-cost alignment with traceback, for sequences packed by any `Profile`.
+//! - `furthest_reaching`: furthest reaching points per diagonal, as used by diagonal transition.
+//!
 //! Given a width of 256, the fastest methods per height are:
 //!  64: bit/u8/row (2.4), bit/u64/row (2.6)
 //! 128: bit/u64/simd2row/1 (3.50)
@@ -19,23 +30,33 @@
     array_windows,
     bigint_helper_methods,
     concat_idents,
-    generic_const_exprs,
     int_roundings,
     iter_array_chunks,
     let_chains,
-    portable_simd,
     test
 )]
+#![cfg_attr(not(feature = "no_simd"), feature(generic_const_exprs, portable_simd))]
 
+mod align;
+mod banded;
+mod distance;
 mod encoding;
+mod furthest;
 pub mod myers;
 pub mod profile;
 pub mod scalar;
+#[cfg(not(feature = "no_simd"))]
 pub mod search;
+#[cfg(not(feature = "no_simd"))]
 pub mod simd;
 
+pub use align::align_profile;
+pub use banded::{banded_distance, banded_distance_profile};
+pub use distance::{edit_distance, edit_distance_profile};
 pub use encoding::*;
+pub use furthest::furthest_reaching;
 pub use profile::*;
+#[cfg(not(feature = "no_simd"))]
 pub use search::search;
 
 /// The type used for all bitvectors.
@@ -44,7 +65,12 @@ pub use search::search;
 pub type B = u8;
 
 /// The type used for all bitvectors.
-#[cfg(not(feature = "small_blocks"))]
+/// 32-bit words are faster on 32-bit targets such as wasm32.
+#[cfg(all(feature = "word32", not(feature = "small_blocks")))]
+pub type B = u32;
+
+/// The type used for all bitvectors.
+#[cfg(not(any(feature = "small_blocks", feature = "word32")))]
 pub type B = u64;
 
 /// The length of each bitvector.
@@ -54,7 +80,44 @@ pub const W: usize = B::BITS as usize;
 pub type H = (u8, u8);
 
 /// The number of lanes in a Simd vector.
+#[cfg(not(feature = "no_simd"))]
 pub const L: usize = 4;
 
 /// The type for a Simd vector of `L` lanes of `B`.
+#[cfg(not(feature = "no_simd"))]
 pub type S<const L: usize> = std::simd::Simd<B, L>;
+
+/// Compute a rectangle of the DP matrix using the fastest available kernel:
+/// `simd::compute`, or `scalar::compute` with the `no_simd` feature.
+///
+/// See `simd::compute` for the meaning of the arguments.
+#[inline]
+pub fn compute<H: HEncoding>(
+    a: &[bit_profile::Bits],
+    b: &[bit_profile::Bits],
+    h: &mut [H],
+    v: &mut [V],
+    exact_end: bool,
+) -> pa_types::Cost {
+    #[cfg(not(feature = "no_simd"))]
+    return simd::compute::<2, H, L>(a, b, h, v, exact_end);
+    #[cfg(feature = "no_simd")]
+    return scalar::compute(a, b, h, v, exact_end);
+}
+
+/// Same as `compute`, but returns all computed values:
+/// `simd::fill`, or `scalar::fill` with the `no_simd` feature.
+#[inline]
+pub fn fill<H: HEncoding>(
+    a: &[bit_profile::Bits],
+    b: &[bit_profile::Bits],
+    h: &mut [H],
+    v: &mut [V],
+    exact_end: bool,
+    values: &mut [Vec<V>],
+) -> pa_types::Cost {
+    #[cfg(not(feature = "no_simd"))]
+    return simd::fill::<2, H, L>(a, b, h, v, exact_end, values);
+    #[cfg(feature = "no_simd")]
+    return scalar::fill::<BitProfile, H>(a, b, h, v, values);
+}
@@ -1,6 +1,10 @@
 //! The basic bitpacked algorithm from Myers'99.
-use crate::{HEncoding, Profile, B, S, V, W};
-use std::simd::{LaneCount, SupportedLaneCount};
+use crate::{HEncoding, Profile, B, V, W};
+#[cfg(not(feature = "no_simd"))]
+use {
+    crate::S,
+    std::simd::{LaneCount, SupportedLaneCount},
+};
 
 /// Implements Myers '99 bitpacking based algorithm. Terminology is as in the
 /// paper. The code is a translation from the implementation in Edlib.
@@ -54,9 +58,15 @@ pub fn compute_block<P: Profile, H: HEncoding>(h0: &mut H, v: &mut V, ca: &P::A,
     *v = V::from(hm | !(vx | hp), hp & vx);
 }
 
-/// Simd version of `compute_block`.
+/// Simd version of `compute_block`, computing `L` independent words at once.
 ///
-/// This assumes HEncoding of `(u64,u64)`.
+/// - `hp0`, `hm0`: the positive and negative horizontal deltas at the top of each word (0 or 1).
+/// - `vp`, `vm`: the positive and negative vertical delta bits of each word.
+/// - `eq`: the match bits, e.g. from `BitProfile::eq_simd`.
+///
+/// This is the kernel of `simd::compute`, and can be used to build custom aligners.
+/// This assumes HEncoding of `(B, B)`.
+#[cfg(not(feature = "no_simd"))]
 #[inline(always)]
 pub fn compute_block_simd<const L: usize>(
     hp0: &mut S<L>,
@@ -1,16 +1,77 @@
-use bio::alphabets::{Alphabet, RankTransform};
-use itertools::Itertools;
 use pa_types::{Seq, I};
 
 use crate::{B, W};
 
+pub use bio::alphabets::{Alphabet, RankTransform};
+
+/// A byte in the input that is not supported by the profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProfileError {
+    /// The offending byte.
+    pub byte: u8,
+    /// The position of the byte in its sequence.
+    pub pos: usize,
+    /// `'a'` or `'b'`, the sequence containing the byte.
+    pub seq: char,
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unsupported byte {:?} (0x{:02x}) at position {} of sequence {}.",
+            self.byte as char, self.byte, self.pos, self.seq
+        )
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// The rank of `c` in `ranks`, when it is one of the first 4 ranks.
+fn rank_in(ranks: &RankTransform, c: u8, pos: usize, seq: char) -> Result<u8, ProfileError> {
+    ranks
+        .ranks
+        .get(c as usize)
+        .copied()
+        .filter(|&r| r < 4)
+        .ok_or(ProfileError { byte: c, pos, seq })
+}
+
 /// Builds a 'profile' of `b` in `64`-bit blocks, and compressed `a` into a `[0,1,2,3]` alphabet.
 ///
 /// Returns a bitpacked `B` indicating which chars of `b` equal a given char of `a`.
 pub trait Profile: Clone + Copy + std::fmt::Debug {
     type A;
     type B;
-    fn build(a: Seq, b: Seq) -> (Vec<Self::A>, Vec<Self::B>);
+    /// Build the profile, or return the first byte that is not supported.
+    ///
+    /// When `ranks` is given, characters are mapped by it instead of the
+    /// built-in case-insensitive `ACGT` encoding, in which RNA `U` equals `T`.
+    /// Only the first 4 ranks are supported. The same `RankTransform` can be
+    /// reused between calls.
+    ///
+    /// ```
+    /// # use pa_bitpacking::*;
+    /// let (pa, pb) = BitProfile::try_build(b"ACGU", b"acgt").unwrap();
+    /// assert!((0..4).all(|i| BitProfile::is_match(&pa, &pb, i, i)));
+    /// ```
+    fn try_build_with(
+        a: Seq,
+        b: Seq,
+        ranks: Option<&RankTransform>,
+    ) -> Result<(Vec<Self::A>, Vec<Self::B>), ProfileError>;
+    /// Build the profile for `ACGT` input, or return the first byte that is not supported.
+    fn try_build(a: Seq, b: Seq) -> Result<(Vec<Self::A>, Vec<Self::B>), ProfileError> {
+        Self::try_build_with(a, b, None)
+    }
+    /// As `try_build_with`, but panicking with a `ProfileError` message on unsupported bytes.
+    fn build_with(a: Seq, b: Seq, ranks: Option<&RankTransform>) -> (Vec<Self::A>, Vec<Self::B>) {
+        Self::try_build_with(a, b, ranks).unwrap_or_else(|e| panic!("{e}"))
+    }
+    /// Build the profile, panicking with a `ProfileError` message on unsupported bytes.
+    fn build(a: Seq, b: Seq) -> (Vec<Self::A>, Vec<Self::B>) {
+        Self::build_with(a, b, None)
+    }
     fn eq(ca: &Self::A, cb: &Self::B) -> B;
     fn is_match(a: &[Self::A], b: &[Self::B], i: I, j: I) -> bool;
 }
@@ -18,7 +79,42 @@ pub trait Profile: Clone + Copy + std::fmt::Debug {
 #[derive(Clone, Copy, Debug)]
 pub struct ScatterProfile;
 
-/// Compressed Character in [0,1,2,3] alphabet.
+/// How `ScatterProfile` matches the wildcards `N` and `*` in `b`.
+///
+/// With `MatchAny`, long runs of `N` give free paths through the alignment,
+/// which may be undesirable. To also support `N` in `a`, use `FreeNProfile`.
+///
+/// ```
+/// # use pa_bitpacking::*;
+/// let is_match = |a: &[u8], b: &[u8], policy| {
+///     let (pa, pb) = ScatterProfile::try_build_with_n_policy(a, b, None, policy).unwrap();
+///     ScatterProfile::is_match(&pa, &pb, 0, 0)
+/// };
+/// assert!(is_match(b"A", b"N", NPolicy::MatchAny));
+/// assert!(!is_match(b"A", b"N", NPolicy::AlwaysMismatch));
+/// // Only `FreeNProfile` supports `N` in `a`.
+/// assert!(ScatterProfile::try_build(b"N", b"C").is_err());
+/// let (pa, pb) = FreeNProfile::build(b"N", b"C");
+/// assert!(FreeNProfile::is_match(&pa, &pb, 0, 0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NPolicy {
+    /// `N` and `*` in `b` match all of `ACGT`.
+    #[default]
+    MatchAny,
+    /// `N` and `*` in `b` match nothing, so each costs a substitution.
+    AlwaysMismatch,
+}
+
+/// Like `ScatterProfile`, but `N` and `*` in either `a` or `b` match
+/// everything, including each other.
+///
+/// This needs a 5th row per block of `b`, and is not supported by the SIMD
+/// kernels in `simd::scatter_profile`.
+#[derive(Clone, Copy, Debug)]
+pub struct FreeNProfile;
+
+/// Compressed Character in [0,1,2,3] alphabet, or 4 for a wildcard in `a` of a `FreeNProfile`.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct CC(u8);
 
@@ -26,42 +122,51 @@ impl Profile for ScatterProfile {
     type A = CC;
     type B = [B; 4];
 
-    fn build(a: Seq, b: Seq) -> (Vec<CC>, Vec<Self::B>) {
-        fn get_char(c: u8) -> u8 {
-            match c {
-                b'a' | b'A' => 0,
-                b'c' | b'C' => 1,
-                b't' | b'T' => 2,
-                b'g' | b'G' => 3,
-                _ => panic!(),
-            }
-        }
-        fn get_mask(c: u8) -> [u64; 4] {
-            match c {
-                b'a' | b'A' => [1, 0, 0, 0],
-                b'c' | b'C' => [0, 1, 0, 0],
-                b't' | b'T' => [0, 0, 1, 0],
-                b'g' | b'G' => [0, 0, 0, 1],
-                b'n' | b'N' | b'*' => [1, 1, 1, 1],
-                b'y' | b'Y' => [0, 1, 1, 0], // C or T
-                b'r' | b'R' => [1, 0, 0, 1], // A or G
-                x => panic!("Unknown base {}", x as char),
-            }
-        }
-        let pa = a.iter().map(|ca| CC(get_char(*ca))).collect_vec();
-        let mut pb = vec![[0; 4]; b.len().div_ceil(W)];
-        for (j, cb) in b.iter().enumerate() {
-            let mask = get_mask(*cb);
-            for i in 0..4 {
-                pb[j / W][i] |= mask[i] << (j % W);
-            }
-        }
-        for j in b.len()..b.len().next_multiple_of(W) {
-            for x in &mut pb[j / W] {
-                *x |= 1 << (j % W);
-            }
-        }
-        (pa, pb)
+    /// With custom `ranks`, the wildcards `N`, `*`, `Y` and `R` in `b` are not supported.
+    /// Wildcards are handled using `NPolicy::MatchAny`.
+    fn try_build_with(
+        a: Seq,
+        b: Seq,
+        ranks: Option<&RankTransform>,
+    ) -> Result<(Vec<CC>, Vec<Self::B>), ProfileError> {
+        Self::try_build_with_n_policy(a, b, ranks, NPolicy::default())
+    }
+
+    #[inline(always)]
+    fn eq(ca: &Self::A, cb: &Self::B) -> B {
+        cb[ca.0 as usize]
+    }
+
+    fn is_match(a: &[Self::A], b: &[Self::B], i: I, j: I) -> bool {
+        (Self::eq(&a[i as usize], &b[j as usize / W]) & (1 << (j as usize % W))) != 0
+    }
+}
+
+impl ScatterProfile {
+    /// As `try_build_with`, but matching `N` and `*` according to `n_policy`.
+    /// With custom `ranks`, there are no wildcards and `n_policy` is ignored.
+    pub fn try_build_with_n_policy(
+        a: Seq,
+        b: Seq,
+        ranks: Option<&RankTransform>,
+        n_policy: NPolicy,
+    ) -> Result<(Vec<CC>, Vec<[B; 4]>), ProfileError> {
+        build_scatter(a, b, ranks, Some(n_policy))
+    }
+}
+
+impl Profile for FreeNProfile {
+    type A = CC;
+    /// The last row indicates which chars of `b` match a wildcard in `a`.
+    type B = [B; 5];
+
+    /// With custom `ranks`, there are no wildcards.
+    fn try_build_with(
+        a: Seq,
+        b: Seq,
+        ranks: Option<&RankTransform>,
+    ) -> Result<(Vec<CC>, Vec<Self::B>), ProfileError> {
+        build_scatter(a, b, ranks, None)
     }
 
     #[inline(always)]
@@ -74,12 +179,88 @@ impl Profile for ScatterProfile {
     }
 }
 
+/// Build a scatter profile with `K` rows per block of `b`.
+/// `n_policy` is `None` for a `FreeNProfile`, where `K = 5` and wildcards match everything.
+fn build_scatter<const K: usize>(
+    a: Seq,
+    b: Seq,
+    ranks: Option<&RankTransform>,
+    n_policy: Option<NPolicy>,
+) -> Result<(Vec<CC>, Vec<[B; K]>), ProfileError> {
+    let free = n_policy.is_none();
+    let get_char = |c: u8| -> Option<u8> {
+        Some(match c {
+            b'a' | b'A' => 0,
+            b'c' | b'C' => 1,
+            b't' | b'T' | b'u' | b'U' => 2,
+            b'g' | b'G' => 3,
+            b'n' | b'N' | b'*' if free => 4,
+            _ => return None,
+        })
+    };
+    let get_mask = |c: u8| -> Option<[B; 5]> {
+        let f = free as B;
+        Some(match c {
+            b'a' | b'A' => [1, 0, 0, 0, f],
+            b'c' | b'C' => [0, 1, 0, 0, f],
+            b't' | b'T' | b'u' | b'U' => [0, 0, 1, 0, f],
+            b'g' | b'G' => [0, 0, 0, 1, f],
+            b'n' | b'N' | b'*' => match n_policy {
+                Some(NPolicy::MatchAny) => [1, 1, 1, 1, 0],
+                Some(NPolicy::AlwaysMismatch) => [0; 5],
+                None => [1; 5],
+            },
+            b'y' | b'Y' => [0, 1, 1, 0, f], // C or T
+            b'r' | b'R' => [1, 0, 0, 1, f], // A or G
+            _ => return None,
+        })
+    };
+    let pa = a
+        .iter()
+        .enumerate()
+        .map(|(i, &ca)| match ranks {
+            Some(ranks) => rank_in(ranks, ca, i, 'a').map(CC),
+            None => get_char(ca).map(CC).ok_or(ProfileError {
+                byte: ca,
+                pos: i,
+                seq: 'a',
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut pb = vec![[0; K]; b.len().div_ceil(W)];
+    for (j, &cb) in b.iter().enumerate() {
+        let mask = match ranks {
+            Some(ranks) => {
+                let mut mask = [0; 5];
+                mask[rank_in(ranks, cb, j, 'b')? as usize] = 1;
+                mask
+            }
+            None => get_mask(cb).ok_or(ProfileError {
+                byte: cb,
+                pos: j,
+                seq: 'b',
+            })?,
+        };
+        for i in 0..K {
+            pb[j / W][i] |= mask[i] << (j % W);
+        }
+    }
+    for j in b.len()..b.len().next_multiple_of(W) {
+        for x in &mut pb[j / W] {
+            *x |= 1 << (j % W);
+        }
+    }
+    Ok((pa, pb))
+}
+
 pub use bit_profile::BitProfile;
 
 // Many public types with private members here, to keep things clean.
 pub mod bit_profile {
+    #[cfg(not(feature = "no_simd"))]
     use std::simd::{LaneCount, SupportedLaneCount};
 
+    #[cfg(not(feature = "no_simd"))]
     use crate::S;
 
     use super::*;
@@ -109,27 +290,45 @@ pub mod bit_profile {
         /// See `eq` for details.
         type B = Bits;
 
-        fn build(a: Seq, b: Seq) -> (Vec<Self::A>, Vec<Self::B>) {
-            let r = RankTransform::new(&Alphabet::new(b"ACGT"));
+        fn try_build_with(
+            a: Seq,
+            b: Seq,
+            ranks: Option<&RankTransform>,
+        ) -> Result<(Vec<Self::A>, Vec<Self::B>), ProfileError> {
+            // Same ranks as `RankTransform::new(&Alphabet::new(b"ACGT"))`, but case-insensitive,
+            // with `U` as `T`, and without panicking on other bytes.
+            let rank = |c: u8, pos: usize, seq: char| -> Result<u8, ProfileError> {
+                if let Some(ranks) = ranks {
+                    return rank_in(ranks, c, pos, seq);
+                }
+                match c {
+                    b'a' | b'A' => Ok(0),
+                    b'c' | b'C' => Ok(1),
+                    b'g' | b'G' => Ok(2),
+                    b't' | b'T' | b'u' | b'U' => Ok(3),
+                    _ => Err(ProfileError { byte: c, pos, seq }),
+                }
+            };
             let pa = a
                 .iter()
-                .map(|ca| {
-                    let a = CC(r.get(*ca));
-                    Bits(
+                .enumerate()
+                .map(|(i, &ca)| {
+                    let a = CC(rank(ca, i, 'a')?);
+                    Ok(Bits(
                         (0 as B).wrapping_sub(a.0 as B & 1),
                         (0 as B).wrapping_sub((a.0 as B >> 1) & 1),
-                    )
+                    ))
                 })
-                .collect_vec();
+                .collect::<Result<Vec<_>, _>>()?;
             let mut pb = vec![Bits(0, 0); b.len().div_ceil(W)];
             for (j, &cb) in b.iter().enumerate() {
-                let cb = r.get(cb);
+                let cb = rank(cb, j, 'b')?;
                 // !cb[0]
                 pb[j / W].0 |= ((cb as B & 1) ^ 1) << (j % W);
                 // !cb[1]
                 pb[j / W].1 |= (((cb as B >> 1) & 1) ^ 1) << (j % W);
             }
-            (pa, pb)
+            Ok((pa, pb))
         }
 
         /// `a` is equals to `b` if both bits are the same, so
@@ -146,6 +345,7 @@ pub mod bit_profile {
             (Self::eq(&a[i as usize], &b[j as usize / W]) & (1 << (j as usize % W))) != 0
         }
     }
+    #[cfg(not(feature = "no_simd"))]
     impl BitProfile {
         #[inline(always)]
         pub fn eq_simd<const L: usize>(ca: (&S<L>, &S<L>), cb: (&S<L>, &S<L>)) -> S<L>
@@ -156,3 +356,32 @@ pub mod bit_profile {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Profiles built from a shared `RankTransform` support other alphabets.
+    #[test]
+    fn profile_rank_transform() {
+        let ranks = RankTransform::new(&Alphabet::new(b"WXYZ"));
+        let to_wxyz = |s: &[u8]| -> Vec<u8> {
+            s.iter()
+                .map(|&c| b"WXYZ"[b"ACGT".iter().position(|&x| x == c).unwrap()])
+                .collect()
+        };
+        for ((a, b), _params) in pa_test::gen_seqs().filter(|((a, _), _)| a.len() <= 100) {
+            let (a, b) = (to_wxyz(&a), to_wxyz(&b));
+            let (pa, pb) = BitProfile::build_with(&a, &b, Some(&ranks));
+            let (sa, sb) = ScatterProfile::build_with(&a, &b, Some(&ranks));
+            for i in 0..a.len() {
+                for j in 0..b.len() {
+                    let eq = a[i] == b[j];
+                    assert_eq!(BitProfile::is_match(&pa, &pb, i as I, j as I), eq);
+                    assert_eq!(ScatterProfile::is_match(&sa, &sb, i as I, j as I), eq);
+                }
+            }
+        }
+        assert!(BitProfile::try_build_with(b"ACGT", b"", Some(&ranks)).is_err());
+    }
+}
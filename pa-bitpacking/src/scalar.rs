@@ -3,7 +3,7 @@ use std::cmp::min;
 use itertools::izip;
 use pa_types::Cost;
 
-use crate::{myers, profile::Profile, HEncoding, V};
+use crate::{bit_profile::Bits, myers, profile::Profile, BitProfile, HEncoding, V};
 
 /// Compute a rectangle column by column.
 pub fn col<P: Profile, H: HEncoding>(a: &[P::A], b: &[P::B], h: &mut [H], v: &mut [V]) -> Cost {
@@ -403,6 +403,19 @@ pub fn rows_ld<const N: usize, P: Profile, H: HEncoding>(
     h.iter().map(|h| h.value()).sum::<Cost>()
 }
 
+/// Scalar version of `simd::compute`, with the same signature and result.
+///
+/// `exact_end` is ignored, since no padding rows are ever added.
+pub fn compute<H: HEncoding>(
+    a: &[Bits],
+    b: &[Bits],
+    h: &mut [H],
+    v: &mut [V],
+    _exact_end: bool,
+) -> Cost {
+    row::<BitProfile, H>(a, b, h, v)
+}
+
 /// Same as `compute`, but returns all computed value.
 pub fn fill<P: Profile, H: HEncoding>(
     a: &[P::A],
@@ -51,7 +51,7 @@ pub fn search<'s>(pattern: &'s [u8], text: &'s [u8], unmatched_cost: f32) -> Sea
     let (t, p) = P::build(text, pattern);
     h = vec![<(u8, u8)>::zero(); t.len()];
     let mut v0 = vec![V::zero(); p.len()];
-    let padding = pattern.len().next_multiple_of(64) - pattern.len();
+    let padding = pattern.len().next_multiple_of(W) - pattern.len();
 
     assert!(unmatched_cost >= 0.0 && unmatched_cost <= 1.0);
     if unmatched_cost > 0.0 {
@@ -60,7 +60,7 @@ pub fn search<'s>(pattern: &'s [u8], text: &'s [u8], unmatched_cost: f32) -> Sea
             if idx >= pattern.len() {
                 break;
             }
-            *v0[idx / 64].one_mut() |= 1 << (idx % 64);
+            *v0[idx / W].one_mut() |= 1 << (idx % W);
         }
     }
     v = v0.clone();
@@ -82,9 +82,9 @@ pub fn search<'s>(pattern: &'s [u8], text: &'s [u8], unmatched_cost: f32) -> Sea
         }
     }
 
-    // Fix since we round up to multiple of 64 chars.
+    // Fix since we round up to multiple of W chars.
     for (v, vu) in std::iter::zip(v, &v0).rev() {
-        for j in 1..=64 {
+        for j in 1..=W {
             let delta = v.value_of_suffix(j as _);
             let unmatched = vu.value_of_suffix(j as _);
             let val = b - delta + unmatched;
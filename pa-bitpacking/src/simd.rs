@@ -64,6 +64,22 @@ where
     }
 }
 
+/// How many columns ahead to prefetch `a` and `h` with the `prefetch` feature.
+const PREFETCH_DIST: usize = 64;
+
+/// Hint the CPU to load `slice[idx]` into cache. No-op without the `prefetch` feature.
+#[inline(always)]
+fn prefetch<T>(slice: &[T], idx: usize) {
+    #[cfg(all(feature = "prefetch", target_arch = "x86_64"))]
+    if idx < slice.len() {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        // SAFE: `idx` is in bounds, and prefetching never faults anyway.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(slice.as_ptr().add(idx) as *const i8) };
+    }
+    #[cfg(not(all(feature = "prefetch", target_arch = "x86_64")))]
+    let _ = (slice, idx);
+}
+
 #[inline(always)]
 fn rotate_left<const N: usize, const L: usize>(ph_simd: &mut [S<L>; N], mut carry: B) -> B
 where
@@ -78,9 +94,19 @@ where
     carry
 }
 
-// If `exact_end` is false, padding rows may be added at the end to speed things
-// up. This means `h` will have a meaningless value at the end that does not
-// correspond to the bottom row of the input range.
+/// Compute a rectangle of the DP matrix using SIMD, `L*N` rows of words at a time.
+///
+/// - `a` and `b`: the `BitProfile` of the two sequences, one entry per column of `a` and per word of `b`.
+/// - `h`: the horizontal differences along the top, replaced by those along the bottom.
+/// - `v`: the vertical differences along the left, replaced by those along the right.
+/// - `L`: the number of `B` lanes per SIMD vector; `N`: the number of SIMD vectors processed in parallel.
+///   `N=2, L=4` is fastest for large inputs.
+///
+/// Returns the sum of the horizontal differences along the bottom.
+///
+/// If `exact_end` is false, padding rows may be added at the end to speed things
+/// up. This means `h` will have a meaningless value at the end that does not
+/// correspond to the bottom row of the input range.
 pub fn compute<const N: usize, H: HEncoding, const L: usize>(
     a: &[Bits],
     b: &[Bits],
@@ -254,6 +280,10 @@ fn compute_block_of_rows<const N: usize, H: HEncoding, const L: usize>(
     )
     .enumerate()
     {
+        prefetch(ap0, i + L * N + PREFETCH_DIST);
+        prefetch(ap1, i + L * N + PREFETCH_DIST);
+        prefetch(h, i + L * N + PREFETCH_DIST);
+
         // Read the unaligned lanes of a.
         let a0 = slice_to_simd(a0);
         let a1 = slice_to_simd(a1);
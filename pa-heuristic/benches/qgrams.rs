@@ -11,9 +11,11 @@ fn bench(c: &mut Criterion) {
         let mut test = |name, f: &dyn Fn() -> usize| {
             c.bench_function(&format!("{name}"), |bb| bb.iter(|| f()));
         };
-        test("a_qgrams", &|| qgrams.a_qgrams(10).map(|(_i, q)| q).sum());
+        test("a_qgrams", &|| {
+            qgrams.a_qgrams(10, 0).map(|(_i, q)| q).sum()
+        });
         test("a_qgrams_rev", &|| {
-            qgrams.a_qgrams_rev(10).map(|(_i, q)| q).sum()
+            qgrams.a_qgrams_rev(10, 0).map(|(_i, q)| q).sum()
         });
         test("b_qgrams", &|| qgrams.b_qgrams(10).map(|(_i, q)| q).sum());
         test("b_qgrams_rev", &|| {
@@ -95,6 +95,11 @@ pub struct HeuristicParams {
     #[clap(long, hide_short_help = true)]
     #[serde(default)]
     pub skip_prune: Option<usize>,
+
+    /// Do not place seeds over lowercase (soft-masked) characters.
+    #[clap(long, hide_short_help = true)]
+    #[serde(default)]
+    pub respect_softmask: bool,
 }
 
 impl Default for HeuristicParams {
@@ -109,6 +114,7 @@ impl Default for HeuristicParams {
             kmax: None,
             max_matches: None,
             skip_prune: None,
+            respect_softmask: false,
         }
     }
 }
@@ -177,6 +183,9 @@ impl HeuristicParams {
             },
             r: self.r,
             local_pruning: self.p,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: self.respect_softmask,
         };
         let pruning = Pruning {
             enabled: self.prune,
@@ -8,7 +8,7 @@ pub use hint_contours::*;
 
 use std::fmt::{Debug, Display};
 
-use crate::{prelude::*, seeds::MatchCost};
+use crate::{prelude::*, seeds::MatchCost, ContourStats};
 
 pub type Layer = u32;
 
@@ -105,6 +105,18 @@ pub trait Contours: Default + Debug {
         unimplemented!();
     }
 
+    /// Whether `push_arrows` is supported.
+    const CAN_PUSH_ARROWS: bool = false;
+
+    /// Add arrows that all start before the arrows already in the contours,
+    /// continuing the construction done by `new`.
+    /// NOTE: Arrows must be reverse sorted by start.
+    /// NOTE: Hints returned before this call are invalidated.
+    /// Returns false, without changing the contours, when this is not supported.
+    fn push_arrows(&mut self, _arrows: impl IntoIterator<Item = Arrow>) -> bool {
+        false
+    }
+
     /// The value of the contour this point is on.
     /// Hint is guaranteed to be for the current position.
     fn score(&self, q: Pos) -> Cost;
@@ -149,4 +161,9 @@ pub trait Contours: Default + Debug {
 
     /// Returns some statistics.
     fn print_stats(&mut self) {}
+
+    /// The number and sizes of the layers.
+    fn contour_stats(&self) -> ContourStats {
+        Default::default()
+    }
 }
@@ -85,15 +85,22 @@ pub struct BruteForceContours {
 }
 
 impl Contours for BruteForceContours {
+    const CAN_PUSH_ARROWS: bool = true;
+
     fn new(arrows: impl IntoIterator<Item = Arrow>, _max_len: I) -> Self {
         let mut this = BruteForceContours {
             valued_arrows: Vec::default(),
         };
+        this.push_arrows(arrows);
+        this
+    }
+
+    fn push_arrows(&mut self, arrows: impl IntoIterator<Item = Arrow>) -> bool {
         for arrow in arrows {
-            let val = this.score(arrow.end) + arrow.score as Cost;
-            this.valued_arrows.push((arrow, val));
+            let val = self.score(arrow.end) + arrow.score as Cost;
+            self.valued_arrows.push((arrow, val));
         }
-        this
+        true
     }
 
     fn score(&self, q: Pos) -> Cost {
@@ -89,6 +89,39 @@ impl Shift {
 }
 
 impl<C: Contour> HintContours<C> {
+    /// Add arrows on top of the existing contours, in the order of `new_with_filter`.
+    fn push_with_filter(
+        &mut self,
+        arrows: impl IntoIterator<Item = Arrow>,
+        mut filter: impl FnMut(&Arrow, Cost) -> bool,
+    ) {
+        // Loop over all arrows from a given positions.
+        for (start, pos_arrows) in &arrows.into_iter().group_by(|a| a.start) {
+            let mut v = 0;
+            let mut l = 0;
+            // TODO: The this.score() could also be implemented using a fenwick tree, as done in LCSk++.
+            for a in pos_arrows {
+                let nv = self.score(a.end) + a.score as Cost;
+                // Filter out arrows where filter returns false.
+                if !filter(&a, nv) {
+                    continue;
+                }
+                v = max(v, nv as Layer);
+                l = max(l, a.score);
+            }
+            if v == 0 {
+                // All arrows at pos filtered out.
+                continue;
+            }
+            if (self.contours.len() as Layer) <= v {
+                let max_len = self.max_len as I;
+                self.contours
+                    .resize_with(v as usize + 1, || C::with_max_len(max_len));
+            }
+            self.contours[v].push(start);
+        }
+    }
+
     fn debug<R: Iterator<Item = Arrow>, F: Fn(&Pos) -> Option<R>>(
         &self,
         pos: Pos,
@@ -208,12 +241,14 @@ fn chain_score<C: Contour, R: Iterator<Item = Arrow>, F: Fn(&Pos) -> Option<R>>(
 }
 
 impl<C: Contour> Contours for HintContours<C> {
+    const CAN_PUSH_ARROWS: bool = true;
+
     // NOTE: Arrows must satisfy the following 'consistency' properties:
     // - If there is an arrow A->B of cost c>1, there is also an arrow A'->B of cost c-1, where A' is an indel away from A.
     fn new_with_filter(
         arrows: impl IntoIterator<Item = Arrow>,
         max_len: Cost,
-        mut filter: impl FnMut(&Arrow, Cost) -> bool,
+        filter: impl FnMut(&Arrow, Cost) -> bool,
     ) -> Self {
         let mut this = HintContours {
             contours: {
@@ -226,34 +261,15 @@ impl<C: Contour> Contours for HintContours<C> {
             layers_removed: 0,
         };
         this.contours[0usize].push(Pos(I::MAX, I::MAX));
-        // Loop over all arrows from a given positions.
-        for (start, pos_arrows) in &arrows.into_iter().group_by(|a| a.start) {
-            let mut v = 0;
-            let mut l = 0;
-            // TODO: The this.score() could also be implemented using a fenwick tree, as done in LCSk++.
-            for a in pos_arrows {
-                let nv = this.score(a.end) + a.score as Cost;
-                // Filter out arrows where filter returns false.
-                if !filter(&a, nv) {
-                    continue;
-                }
-                v = max(v, nv as Layer);
-                l = max(l, a.score);
-            }
-            if v == 0 {
-                // All arrows at pos filtered out.
-                continue;
-            }
-            if (this.contours.len() as Layer) <= v {
-                this.contours
-                    .resize_with(v as usize + 1, || C::with_max_len(max_len));
-            }
-            this.contours[v].push(start);
-        }
-
+        this.push_with_filter(arrows, filter);
         this
     }
 
+    fn push_arrows(&mut self, arrows: impl IntoIterator<Item = Arrow>) -> bool {
+        self.push_with_filter(arrows, |_, _| true);
+        true
+    }
+
     /// The max sum of arrows starting at pos
     fn score(&self, q: Pos) -> Cost {
         // score >= low is known
@@ -631,6 +647,16 @@ impl<C: Contour> Contours for HintContours<C> {
         self.print_stats();
     }
 
+    fn contour_stats(&self) -> ContourStats {
+        let mut stats = ContourStats::default();
+        for c in &self.contours {
+            stats.layers += 1;
+            stats.points += c.len();
+            stats.max_layer_size = max(stats.max_layer_size, c.len());
+        }
+        stats
+    }
+
     #[allow(unreachable_code, unused_variables)]
     fn print_stats(&mut self) {
         if !PRINT {
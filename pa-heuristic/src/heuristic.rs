@@ -6,6 +6,7 @@ pub mod wrappers;
 
 use std::ops::Range;
 
+use crate::matches::exact::SeedIndex;
 use crate::matches::*;
 use crate::prelude::*;
 use crate::seeds::Seeds;
@@ -18,9 +19,13 @@ pub use sh::*;
 
 #[derive(Clone, AddAssign, Default, Copy, Debug)]
 pub struct HeuristicStats {
+    /// Number of seeds in `a`.
     pub num_seeds: I,
+    /// Number of matches found, before filtering.
     pub num_matches: usize,
+    /// Number of matches used by the heuristic.
     pub num_filtered_matches: usize,
+    /// Number of matches pruned so far.
     pub num_pruned: usize,
     pub h0: Cost,
     pub h0_end: Cost,
@@ -36,6 +41,35 @@ pub struct HeuristicStats {
     pub h_calls: usize,
 }
 
+/// The shape of the contours of a heuristic, for diagnosing slow alignments.
+#[derive(Clone, Default, Copy, Debug, PartialEq, Eq)]
+pub struct ContourStats {
+    /// Number of layers, including the layer of the target.
+    pub layers: usize,
+    /// Total number of (dominant and non-dominant) points over all layers.
+    pub points: usize,
+    /// Number of points in the largest layer.
+    pub max_layer_size: usize,
+}
+
+impl ContourStats {
+    /// The average number of points per layer, or 0 when there are no layers.
+    pub fn avg_layer_size(&self) -> f32 {
+        if self.layers == 0 {
+            return 0.;
+        }
+        self.points as f32 / self.layers as f32
+    }
+}
+
+impl std::ops::AddAssign for ContourStats {
+    fn add_assign(&mut self, other: Self) {
+        self.layers += other.layers;
+        self.points += other.points;
+        self.max_layer_size = max(self.max_layer_size, other.max_layer_size);
+    }
+}
+
 /// An object containing the settings for a heuristic.
 pub trait Heuristic: std::fmt::Debug + Copy {
     type Instance<'a>: HeuristicInstance<'a>;
@@ -56,6 +90,23 @@ pub trait Heuristic: std::fmt::Debug + Copy {
         unimplemented!();
     }
 
+    /// The `SeedIndex` of `a` for `build_with_index`, when this heuristic
+    /// finds its matches by hashing the seeds of `a`.
+    fn seed_index(&self, _a: Seq) -> Option<SeedIndex> {
+        None
+    }
+
+    /// As `build`, but finds the matches using the `seed_index` of `a`, so
+    /// that the seeds are hashed only once when aligning many `b` to one `a`.
+    fn build_with_index<'a>(
+        &self,
+        a: Seq<'a>,
+        b: Seq<'a>,
+        _index: &SeedIndex,
+    ) -> Self::Instance<'a> {
+        self.build(a, b)
+    }
+
     // Heuristic properties.
     fn name(&self) -> String;
 }
@@ -147,11 +198,82 @@ pub trait HeuristicInstance<'a> {
     fn prune(&mut self, _pos: Pos, _hint: Self::Hint) -> (Cost, Self::Order) {
         (0, Default::default())
     }
+    /// Prune all matches that start in the block of columns
+    /// `i_range.start+1..=i_range.end` and rows `j_range.start..=j_range.end`
+    /// (both inclusive). This is used by block-based searches like A*PA2.
+    ///
+    /// Preconditions:
+    /// - The heuristic uses `Prune::Start`.
+    /// - The distance to every state in the block is *fixed*, i.e. known to be
+    ///   optimal. Pruning matches from non-fixed states can make the heuristic
+    ///   inadmissible for the remaining search.
+    /// - `j_range.start <= j_range.end`.
+    /// - Repeated calls covering the same columns must use overlapping or
+    ///   adjacent `j_range`s: for each seed, matches between a previous and the
+    ///   current range are pruned as well.
+    ///
+    /// Postcondition: the pruned matches no longer contribute to `h`, but the
+    /// contours are not updated yet, so `h` may still return the old values.
+    /// Call `update_contours` before relying on the increased values.
+    ///
+    /// The default implementation does nothing, which is always correct.
+    ///
+    /// A minimal column-by-column search that prunes each block once it is
+    /// fully computed, since all its states are then fixed:
+    /// ```
+    /// use pa_heuristic::*;
+    /// use pa_types::*;
+    /// let (a, b) = pa_generate::uniform_fixed(1000, 0.05);
+    /// let gcsh = GCSH::new(MatchConfig::exact(10), Pruning::start());
+    /// let mut h = gcsh.build(&a, &b);
+    /// let h0 = h.h(Pos(0, 0));
+    ///
+    /// let block_width = 64;
+    /// let mut col: Vec<Cost> = (0..=b.len() as Cost).collect();
+    /// for i in 1..=a.len() {
+    ///     let mut next = vec![i as Cost; b.len() + 1];
+    ///     for j in 1..=b.len() {
+    ///         let sub = col[j - 1] + (a[i - 1] != b[j - 1]) as Cost;
+    ///         next[j] = sub.min(col[j] + 1).min(next[j - 1] + 1);
+    ///     }
+    ///     col = next;
+    ///     if i % block_width == 0 || i == a.len() {
+    ///         let i_start = ((i - 1) / block_width * block_width) as I;
+    ///         h.prune_block(i_start..i as I, 0..b.len() as I);
+    ///     }
+    /// }
+    /// h.update_contours(Pos(0, 0));
+    /// // Pruning only increases the heuristic.
+    /// assert!(h.h(Pos(0, 0)) >= h0);
+    /// ```
     fn prune_block(&mut self, _i_range: Range<I>, _j_range: Range<I>) {
         //unimplemented!();
     }
 
+    /// Insert new matches and update the contours incrementally.
+    /// This is the dual of `prune`.
+    /// NOTE: Matches must start at the start of a seed of this heuristic.
+    ///
+    /// Returns false, without changing the heuristic, when the matches can not
+    /// be added incrementally, in which case the heuristic should be rebuilt.
+    /// This is the default.
+    fn add_matches(&mut self, _matches: &[Match]) -> bool {
+        false
+    }
+
+    /// Whether `add_matches` would succeed for these matches.
+    fn can_add_matches(&self, _matches: &[Match]) -> bool {
+        false
+    }
+
     /// Update contours from the current minimum changed layer up to the given `_pos`.
+    ///
+    /// This applies all prunes done by `prune_block` since the last call.
+    /// Afterwards, `h` is up to date for all positions with `i >= _pos.0`;
+    /// positions left of `_pos` may keep their old, smaller, values.
+    /// Use `Pos(0, 0)` to update everything.
+    ///
+    /// The default implementation does nothing.
     fn update_contours(&mut self, _pos: Pos) {
         //unimplemented!();
     }
@@ -165,10 +287,28 @@ pub trait HeuristicInstance<'a> {
         Default::default()
     }
 
+    /// The current layers of the contours, after all pruning so far.
+    /// Zero for heuristics without contours.
+    fn contour_stats(&self) -> ContourStats {
+        Default::default()
+    }
+
     fn matches(&self) -> Option<Vec<Match>> {
         None
     }
 
+    /// The matches starting in `i_range × j_range`, including pruned ones.
+    /// Empty for heuristics without matches.
+    ///
+    /// The default implementation filters `matches()`.
+    fn matches_in(&self, i_range: Range<I>, j_range: Range<I>) -> Vec<Match> {
+        self.matches()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|m| i_range.contains(&m.start.0) && j_range.contains(&m.start.1))
+            .collect()
+    }
+
     fn seeds(&self) -> Option<&Seeds> {
         None
     }
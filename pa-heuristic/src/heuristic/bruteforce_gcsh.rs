@@ -181,6 +181,13 @@ where
         Some(self.matches.iter().cloned().collect_vec())
     }
 
+    fn matches_in(&self, i_range: Range<I>, j_range: Range<I>) -> Vec<Match> {
+        self.matches
+            .matches_in(i_range, j_range)
+            .cloned()
+            .collect_vec()
+    }
+
     fn seeds(&self) -> Option<&Seeds> {
         Some(&self.seeds)
     }
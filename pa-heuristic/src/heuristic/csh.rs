@@ -141,7 +141,23 @@ impl<C: Contours> Heuristic for CSH<C> {
         b: Seq<'a>,
         filter: Option<impl FnMut(&Match, Cost) -> bool>,
     ) -> Self::Instance<'a> {
-        CSHI::new(a, b, filter, *self)
+        let matches = find_matches(a, b, self.match_config, self.use_gap_cost);
+        CSHI::new(a, b, matches, filter, *self)
+    }
+
+    fn seed_index(&self, a: Seq) -> Option<SeedIndex> {
+        (self.match_config.r == 1 && self.match_config.length.k().is_some())
+            .then(|| SeedIndex::new(a, self.match_config))
+    }
+
+    fn build_with_index<'a>(
+        &self,
+        a: Seq<'a>,
+        b: Seq<'a>,
+        index: &SeedIndex,
+    ) -> Self::Instance<'a> {
+        let matches = index.find_matches(a, b, self.use_gap_cost);
+        CSHI::new(a, b, matches, None::<fn(&Match, Cost) -> bool>, *self)
     }
 
     fn name(&self) -> String {
@@ -199,11 +215,10 @@ impl<C: Contours> CSHI<C> {
     fn new(
         a: Seq,
         b: Seq,
+        Matches { seeds, mut matches }: Matches,
         filter: Option<impl FnMut(&Match, Cost) -> bool>,
         params: CSH<C>,
     ) -> Self {
-        let Matches { seeds, mut matches } =
-            find_matches(a, b, params.match_config, params.use_gap_cost);
         let target = Pos::target(a, b);
         let t_target = if params.use_gap_cost {
             seeds.transform(target)
@@ -560,15 +575,94 @@ impl<'a, C: Contours> HeuristicInstance<'a> for CSHI<C> {
         self.max_transformed_pos.1 = max(self.max_transformed_pos.1, tpos.1);
     }
 
+    /// Matches are added incrementally when they all start before (in `LexPos`
+    /// order) every active match, e.g. for a growing prefix. The new arrows
+    /// then continue the construction of the contours, and existing layers are
+    /// unchanged. Otherwise, or while block prunes are pending, nothing is
+    /// changed and false is returned.
+    ///
+    /// The match pruner is rebuilt from the active and new matches.
+    fn add_matches(&mut self, new: &[Match]) -> bool {
+        if !self.can_add_matches(new) {
+            return false;
+        }
+
+        let t_target = self.t_target;
+        let new = new
+            .iter()
+            .filter(|m| self.transform(m.start) <= t_target)
+            .cloned()
+            .sorted_by_key(|m| (LexPos(m.start), m.match_cost))
+            .collect_vec();
+
+        // Contours need the arrows reverse sorted by start.
+        let arrows = new
+            .iter()
+            .rev()
+            .map(|m| Arrow {
+                start: Self::transform_2(&self.params, &self.seeds, m.start),
+                end: Self::transform_2(&self.params, &self.seeds, m.end),
+                score: m.score(),
+            })
+            .filter(|a| a.end <= t_target)
+            .collect_vec();
+        assert!(self.contours.push_arrows(arrows));
+        self.stats.num_matches += new.len();
+        self.stats.num_filtered_matches += new.len();
+
+        // Pruned matches are dropped for good.
+        let mut matches = self
+            .matches
+            .iter()
+            .filter(|m| m.is_active())
+            .cloned()
+            .collect_vec();
+        matches.extend(new);
+        self.matches = MatchPruner::new(
+            self.params.pruning,
+            self.params.use_gap_cost,
+            matches,
+            &self.seeds,
+        );
+        true
+    }
+
+    fn can_add_matches(&self, new: &[Match]) -> bool {
+        for m in new {
+            assert!(
+                self.seeds.is_seed_start(m.start),
+                "Match {m:?} does not start at a seed."
+            );
+        }
+        if !C::CAN_PUSH_ARROWS || self.lowest_modified_contour != Layer::MAX {
+            return false;
+        }
+        self.matches
+            .iter()
+            .find(|m| m.is_active())
+            .is_none_or(|first| new.iter().all(|m| LexPos(m.start) < LexPos(first.start)))
+    }
+
     fn stats(&mut self) -> HeuristicStats {
         self.stats.h0_end = self.h(Pos(0, 0));
         self.stats
     }
 
+    fn contour_stats(&self) -> ContourStats {
+        self.contours.contour_stats()
+    }
+
     fn matches(&self) -> Option<Vec<Match>> {
         Some(self.matches.iter().cloned().collect_vec())
     }
 
+    fn matches_in(&self, i_range: Range<I>, j_range: Range<I>) -> Vec<Match> {
+        self.matches
+            .matches_in(i_range, j_range)
+            .cloned()
+            .collect_vec()
+    }
+
     fn seeds(&self) -> Option<&Seeds> {
         Some(&self.seeds)
     }
@@ -577,3 +671,83 @@ impl<'a, C: Contours> HeuristicInstance<'a> for CSHI<C> {
         format!("{:?}", self.params)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pa_generate::uniform_fixed;
+
+    /// Pruning all matches in a prefix and adding them back restores the original heuristic.
+    #[test]
+    fn add_matches() {
+        let (a, b) = uniform_fixed(300, 0.1);
+        let h = GCSH::new(MatchConfig::exact(5), Pruning::start());
+        let full = h.build(&a, &b);
+        let mut hi = h.build(&a, &b);
+        let h_eq = |hi: &CSHI<_>| {
+            (0..=a.len() as I)
+                .cartesian_product(0..=b.len() as I)
+                .all(|(i, j)| full.h(Pos(i, j)) == hi.h(Pos(i, j)))
+        };
+
+        let starts = hi
+            .matches()
+            .unwrap()
+            .iter()
+            .map(|m| m.start)
+            .filter(|s| s.0 < a.len() as I / 2)
+            .dedup()
+            .collect_vec();
+        for &pos in &starts {
+            hi.prune(pos, Default::default());
+        }
+        let (mut pruned, active): (Vec<_>, Vec<_>) = hi
+            .matches()
+            .unwrap()
+            .into_iter()
+            .partition(|m| !m.is_active());
+        assert!(!pruned.is_empty());
+        for m in &mut pruned {
+            m.pruned = MatchStatus::Active;
+        }
+        assert!(!h_eq(&hi));
+
+        // Matches that do not precede all active matches are rejected.
+        let mut late = pruned.clone();
+        late.push(active[0].clone());
+        assert!(!hi.add_matches(&late));
+
+        assert!(hi.add_matches(&pruned));
+        assert!(h_eq(&hi));
+    }
+
+    #[test]
+    fn matches_in() {
+        let (a, b) = uniform_fixed(300, 0.1);
+        let hi = GCSH::new(MatchConfig::exact(5), Pruning::start()).build(&a, &b);
+        let matches = hi.matches().unwrap();
+        assert_eq!(
+            hi.matches_in(0..a.len() as I + 1, 0..b.len() as I + 1),
+            matches
+        );
+        let expected = matches
+            .into_iter()
+            .filter(|m| (100..200).contains(&m.start.0) && (50..150).contains(&m.start.1))
+            .collect_vec();
+        assert!(!expected.is_empty());
+        assert_eq!(hi.matches_in(100..200, 50..150), expected);
+    }
+
+    #[test]
+    fn contour_stats() {
+        let (a, b) = uniform_fixed(300, 0.1);
+        let hi = GCSH::new(MatchConfig::exact(5), Pruning::start()).build(&a, &b);
+        let stats = hi.contour_stats();
+        // Each layer up to h(0, 0) contains at least one point.
+        assert!(stats.layers as Cost > hi.h(Pos(0, 0)));
+        assert!(stats.points >= stats.layers - 1);
+        assert!(stats.max_layer_size as f32 >= stats.avg_layer_size());
+        // Heuristics without contours have no layers.
+        assert_eq!(ContourStats::default().avg_layer_size(), 0.);
+    }
+}
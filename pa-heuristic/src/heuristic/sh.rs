@@ -163,6 +163,10 @@ impl<'a> HeuristicInstance<'a> for SHI {
         Some(self.matches.iter().cloned().collect())
     }
 
+    fn matches_in(&self, i_range: Range<I>, j_range: Range<I>) -> Vec<Match> {
+        self.matches.matches_in(i_range, j_range).cloned().collect()
+    }
+
     fn seeds(&self) -> Option<&Seeds> {
         Some(&self.seeds)
     }
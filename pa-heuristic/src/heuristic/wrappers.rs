@@ -66,6 +66,10 @@ where
         self.h2.matches()
     }
 
+    fn matches_in(&self, i_range: Range<I>, j_range: Range<I>) -> Vec<Match> {
+        self.h2.matches_in(i_range, j_range)
+    }
+
     fn is_seed_start_or_end(&self, pos: Pos) -> bool {
         let s1 = self.h1.is_seed_start_or_end(pos);
         let s2 = self.h2.is_seed_start_or_end(pos);
@@ -79,6 +83,20 @@ where
         (0, ())
     }
 
+    /// Both heuristics are checked first, so that they are never out of sync.
+    fn add_matches(&mut self, matches: &[Match]) -> bool {
+        if !self.can_add_matches(matches) {
+            return false;
+        }
+        assert!(self.h1.add_matches(matches));
+        assert!(self.h2.add_matches(matches));
+        true
+    }
+
+    fn can_add_matches(&self, matches: &[Match]) -> bool {
+        self.h1.can_add_matches(matches) && self.h2.can_add_matches(matches)
+    }
+
     fn h_with_hint(&self, pos: Pos, hint: Self::Hint) -> (Cost, Self::Hint) {
         let (c1, hint1) = self.h1.h_with_hint(pos, hint.0);
         let (c2, hint2) = self.h2.h_with_hint(pos, hint.1);
@@ -98,6 +116,10 @@ where
         self.h2.stats()
     }
 
+    fn contour_stats(&self) -> ContourStats {
+        self.h2.contour_stats()
+    }
+
     fn root_potential(&self) -> Cost {
         self.h2.root_potential()
     }
@@ -180,6 +202,20 @@ where
         (0, ())
     }
 
+    /// Both heuristics are checked first, so that they are never out of sync.
+    fn add_matches(&mut self, matches: &[Match]) -> bool {
+        if !self.can_add_matches(matches) {
+            return false;
+        }
+        assert!(self.h1.add_matches(matches));
+        assert!(self.h2.add_matches(matches));
+        true
+    }
+
+    fn can_add_matches(&self, matches: &[Match]) -> bool {
+        self.h1.can_add_matches(matches) && self.h2.can_add_matches(matches)
+    }
+
     fn h_with_hint(&self, pos: Pos, hint: Self::Hint) -> (Cost, Self::Hint) {
         let (c1, hint1) = self.h1.h_with_hint(pos, hint.0);
         let (c2, hint2) = self.h2.h_with_hint(pos, hint.1);
@@ -190,6 +226,10 @@ where
         self.h2.stats()
     }
 
+    fn contour_stats(&self) -> ContourStats {
+        self.h2.contour_stats()
+    }
+
     fn root_potential(&self) -> Cost {
         max(self.h1.root_potential(), self.h2.root_potential())
     }
@@ -252,6 +292,10 @@ where
         self.h.stats()
     }
 
+    fn contour_stats(&self) -> ContourStats {
+        self.h.contour_stats()
+    }
+
     fn root_potential(&self) -> Cost {
         self.h.root_potential()
     }
@@ -313,3 +357,42 @@ where
         max_config.build(a, b)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pa_generate::uniform_fixed;
+
+    /// When only one of the heuristics can add the matches, neither is changed.
+    #[test]
+    fn add_matches_mixed() {
+        let (a, b) = uniform_fixed(300, 0.1);
+        let h = MaxHeuristic {
+            h1: GCSH::new(MatchConfig::exact(5), Pruning::start()),
+            h2: GapCost,
+        };
+        let mut hi = h.build(&a, &b);
+        let pruned = hi
+            .h1
+            .matches()
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.start.0 < a.len() as I / 2)
+            .collect_vec();
+        for pos in pruned.iter().map(|m| m.start).dedup() {
+            hi.h1.prune(pos, Default::default());
+        }
+        let h1 = |hi: &MaxHeuristicI<_, _>| {
+            (0..=a.len() as I)
+                .cartesian_product(0..=b.len() as I)
+                .map(|(i, j)| hi.h1.h(Pos(i, j)))
+                .collect_vec()
+        };
+        let before = h1(&hi);
+
+        assert!(hi.h1.can_add_matches(&pruned));
+        assert!(!hi.h2.can_add_matches(&pruned));
+        assert!(!hi.add_matches(&pruned));
+        assert_eq!(h1(&hi), before);
+    }
+}
@@ -20,7 +20,7 @@ pub mod util;
 
 pub use cli::*;
 pub use heuristic::*;
-pub use matches::{LengthConfig, MatchConfig};
+pub use matches::{exact::SeedIndex, LengthConfig, MatchConfig};
 pub use prune::{Prune, Pruning};
 pub use seeds::MatchCost;
 
@@ -28,6 +28,10 @@ mod prelude {
     pub use crate::config::*;
     pub use pa_types::*;
 
+    /// `FxHashMap` has no random seed, so its iteration order only depends on
+    /// the inserted keys and results are reproducible between runs.
+    /// Maps are only iterated while building the q-gram index in
+    /// `matches::exact`, and matches are sorted afterwards.
     pub use rustc_hash::FxHashMap as HashMap;
     pub use std::cmp::{max, min};
 }
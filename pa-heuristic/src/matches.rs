@@ -1,6 +1,7 @@
 // Modules are pub for benchmarking.
 pub mod exact;
 pub mod inexact;
+pub mod mask;
 pub mod prepruning;
 pub mod qgrams;
 mod suffix_array;
@@ -21,10 +22,25 @@ pub fn find_matches<'a>(
     transform_filter: bool,
 ) -> Matches {
     if let LengthConfig::Max(_) = match_config.length {
+        assert_eq!(
+            match_config.offset, 0,
+            "A seed offset requires fixed-length seeds."
+        );
+        assert!(
+            match_config.mask_low_complexity.is_none(),
+            "Low-complexity masking requires fixed-length seeds."
+        );
+        assert!(
+            !match_config.respect_softmask,
+            "Soft-masking requires fixed-length seeds."
+        );
         return suffix_array::minimal_unique_matches(a, b, match_config);
     }
     if FIND_MATCHES_HASH {
         return match match_config.r {
+            #[cfg(feature = "parallel")]
+            1 => exact::hash_a_parallel(a, b, match_config, transform_filter),
+            #[cfg(not(feature = "parallel"))]
             1 => exact::hash_a(a, b, match_config, transform_filter),
             2 => inexact::find_matches_qgram_hash_inexact(a, b, match_config, transform_filter),
             _ => unimplemented!("FIND_MATCHES with HashMap only works for r = 1 or r = 2"),
@@ -50,6 +66,14 @@ pub enum MatchStatus {
     Filtered,
 }
 
+/// A match of a single seed: `start.0` and `end.0` are the start and end of the seed.
+///
+/// Matches are not extended through mismatches into neighbouring seeds. Such a
+/// longer match on the same diagonal has score `sum(seed_potential) -
+/// mismatches`, which is never more than the chain of the per-seed matches it
+/// covers, so it does not improve the heuristic. It would also break the
+/// assumption of the contours that a score is at most `r`, and would only be
+/// pruned once its first seed is expanded.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Match {
     pub start: Pos,
@@ -126,6 +150,20 @@ impl<T: Copy> CenteredVec<T> {
     }
 }
 
+/// The start and q-gram of each seed of `a`, in order.
+fn seed_qgrams(a: Seq, seeds: &Seeds) -> Vec<(I, usize)> {
+    seeds
+        .seeds
+        .iter()
+        .map(|s| {
+            (
+                s.start,
+                QGrams::to_qgram(&a[s.start as usize..s.end as usize]),
+            )
+        })
+        .collect()
+}
+
 /// Helper for constructing and filtering matches.
 ///
 /// Note that this requires the seeds to be already determined, since they are
@@ -157,24 +195,8 @@ struct MatchStats {
 impl<'a> MatchBuilder<'a> {
     /// New MatchBuilder with fixed length seeds.
     fn new(qgrams: &'a QGrams<'a>, config: MatchConfig, transform_filter: bool) -> Self {
-        let seeds = Seeds::new(
-            qgrams.a,
-            qgrams.fixed_length_seeds(config.length.k().unwrap(), config.r),
-        );
-        let transform_target = seeds.transform(Pos::target(qgrams.a, qgrams.b));
-        let d = transform_target.0 - transform_target.1;
-        Self {
-            qgrams,
-            config,
-            seeds,
-            matches: Vec::new(),
-            transform_target,
-            transform_filter,
-            local_pruning_cache: Default::default(),
-            stats: MatchStats::default(),
-            // Make space for the 0 and target diagonal, and 10 padding on each side.
-            next_match_per_diag: CenteredVec::new(d, I::MAX),
-        }
+        let seeds = qgrams.fixed_length_seeds(config.length.k().unwrap(), config.r, config.offset);
+        Self::new_with_seeds(qgrams, config, transform_filter, seeds)
     }
 
     fn new_with_seeds(
@@ -183,7 +205,17 @@ impl<'a> MatchBuilder<'a> {
         transform_filter: bool,
         seeds: Vec<Seed>,
     ) -> Self {
-        let seeds = Seeds::new(qgrams.a, seeds);
+        let seeds = Seeds::new(qgrams.a, mask::mask_seeds(qgrams.a, &config, seeds));
+        Self::with_masked_seeds(qgrams, config, transform_filter, seeds)
+    }
+
+    /// New MatchBuilder with seeds that were already masked.
+    fn with_masked_seeds(
+        qgrams: &'a QGrams<'a>,
+        config: MatchConfig,
+        transform_filter: bool,
+        seeds: Seeds,
+    ) -> Self {
         let transform_target = seeds.transform(Pos::target(qgrams.a, qgrams.b));
         let d = transform_target.0 - transform_target.1;
         Self {
@@ -200,6 +232,12 @@ impl<'a> MatchBuilder<'a> {
         }
     }
 
+    /// The start and q-gram of each seed, in order.
+    /// Seeds dropped by masking are skipped, so they are never looked up.
+    fn seed_qgrams(&self) -> Vec<(I, usize)> {
+        seed_qgrams(self.qgrams.a, &self.seeds)
+    }
+
     /// Add a new match. If enabled, filters for m.start <=_T end and/or local pruning.
     /// Returns whether the match was added.
     fn push(&mut self, mut m: Match) {
@@ -385,6 +423,8 @@ impl LengthConfig {
     }
 }
 
+/// Match finding is deterministic given `(a, b, MatchConfig)`: hashing only
+/// uses `FxHashMap`, which has no random seed, and matches are sorted afterwards.
 #[derive(Clone, Copy, Debug)]
 pub struct MatchConfig {
     /// The length of each seed, either a fixed `k`, or variable such that the
@@ -392,10 +432,23 @@ pub struct MatchConfig {
     // TODO: Add settings for variable length matches in here.
     pub length: LengthConfig,
     /// The maximal cost per match, i.e. `r-1`.
+    /// Errors inside seeds are counted with unit costs, independent of the cost
+    /// model of the alignment. See `admissible_for`.
     // TODO: Move r into MatchLength.
     pub r: MatchCost,
     /// The number of seeds to 'look ahead' in local pruning.
     pub local_pruning: usize,
+    /// The start of the first seed in `a`. Fixed-length seeds are placed at
+    /// `offset, offset + k, ...`, and the prefix before `offset` is not seeded.
+    /// Must be 0 for variable-length seeds.
+    pub offset: I,
+    /// When set, no seeds are placed in windows of `a` whose entropy (in bits,
+    /// at most 2) is below this threshold. Must be `None` for variable-length seeds.
+    pub mask_low_complexity: Option<f32>,
+    /// When set, no seeds are placed over lowercase (soft-masked) characters of
+    /// `a`, e.g. repeats in a reference genome. Alignment is not affected.
+    /// Must be `false` for variable-length seeds.
+    pub respect_softmask: bool,
 }
 
 impl MatchConfig {
@@ -404,6 +457,9 @@ impl MatchConfig {
             length: Fixed(k),
             r,
             local_pruning: 0,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: false,
         }
     }
     pub fn exact(k: I) -> Self {
@@ -411,6 +467,9 @@ impl MatchConfig {
             length: Fixed(k),
             r: 1,
             local_pruning: 0,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: false,
         }
     }
     pub fn inexact(k: I) -> Self {
@@ -418,8 +477,28 @@ impl MatchConfig {
             length: Fixed(k),
             r: 2,
             local_pruning: 0,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: false,
         }
     }
+
+    /// Whether the heuristic is a lower bound on the distance under the
+    /// alignment cost model `cm`, i.e. each edit costs at least as much in
+    /// `cm` as the unit cost used for errors in seeds.
+    ///
+    /// ```
+    /// # use pa_heuristic::MatchConfig;
+    /// # use pa_types::CostModel;
+    /// let config = MatchConfig::inexact(10);
+    /// assert!(config.admissible_for(&CostModel::unit()));
+    /// assert!(config.admissible_for(&CostModel { sub: 2, open: 3, extend: 1 }));
+    /// assert!(!config.admissible_for(&CostModel { sub: 1, open: 1, extend: 0 }));
+    /// ```
+    pub fn admissible_for(&self, cm: &CostModel) -> bool {
+        let seed = CostModel::unit();
+        cm.sub >= seed.sub && cm.open >= seed.open && cm.extend >= seed.extend
+    }
 }
 
 impl Default for MatchConfig {
@@ -428,6 +507,9 @@ impl Default for MatchConfig {
             length: Fixed(0),
             r: 1,
             local_pruning: 0,
+            offset: 0,
+            mask_low_complexity: None,
+            respect_softmask: false,
         }
     }
 }
@@ -17,9 +17,14 @@ pub fn hash_a<'a>(a: Seq<'a>, b: Seq<'a>, config: MatchConfig, transform_filter:
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    hash_to_smallvec(q.a_qgrams(k), q.b_qgrams_rev(k), &mut matches, k, |i, j| {
-        Pos(i, j)
-    });
+    let seed_qgrams = matches.seed_qgrams();
+    hash_to_smallvec(
+        seed_qgrams.into_iter(),
+        q.b_qgrams_rev(k),
+        &mut matches,
+        k,
+        |i, j| Pos(i, j),
+    );
     matches.sort();
     matches.finish()
 }
@@ -30,28 +35,149 @@ pub fn hash_b<'a>(a: Seq<'a>, b: Seq<'a>, config: MatchConfig, transform_filter:
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    hash_to_smallvec(q.b_qgrams(k), q.a_qgrams_rev(k), &mut matches, k, |j, i| {
-        Pos(i, j)
-    });
+    let seed_qgrams = matches.seed_qgrams();
+    hash_to_smallvec(
+        q.b_qgrams(k),
+        seed_qgrams.into_iter().rev(),
+        &mut matches,
+        k,
+        |j, i| Pos(i, j),
+    );
     matches.sort();
     matches.finish()
 }
 
+/// As `hash_a`, but the k-mers of `b` are looked up in parallel.
+///
+/// The hashmap over the seeds of `a` is built once and shared. `b` is split
+/// into one contiguous chunk per thread, and the matches of all chunks are then
+/// pushed in the same order as `hash_a`, so the result is identical.
+#[cfg(feature = "parallel")]
+pub fn hash_a_parallel<'a>(
+    a: Seq<'a>,
+    b: Seq<'a>,
+    config: MatchConfig,
+    transform_filter: bool,
+) -> Matches {
+    use rayon::prelude::*;
+    type Key = u32;
+
+    assert!(config.r == 1);
+    let k = config.length.k().unwrap();
+    let q = QGrams::new(a, b);
+    let mut matches = MatchBuilder::new(&q, config, transform_filter);
+
+    let mut h = HashMap::<Key, SmallVec<[I; 2]>>::default();
+    for (i, q) in matches.seed_qgrams() {
+        h.entry(q as Key).or_default().push(i as I);
+    }
+
+    // Reversed, since matches must be pushed right-to-left on each diagonal.
+    let qgrams_b = q.b_qgrams_rev(k).collect::<Vec<_>>();
+    let chunk_size = qgrams_b.len().div_ceil(rayon::current_num_threads()).max(1);
+    let chunks = qgrams_b
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut chunk_matches = vec![];
+            for &(j, q) in chunk {
+                if let Some(is) = h.get(&(q as Key)) {
+                    for &i in is {
+                        let start = Pos(i, j);
+                        chunk_matches.push(Match {
+                            start,
+                            end: start + Pos(k, k),
+                            match_cost: 0,
+                            seed_potential: 1,
+                            pruned: MatchStatus::Active,
+                        });
+                    }
+                }
+            }
+            chunk_matches
+        })
+        .collect::<Vec<_>>();
+    // `collect` keeps the chunks in order.
+    for m in chunks.into_iter().flatten() {
+        matches.push(m);
+    }
+    matches.sort();
+    matches.finish()
+}
+
+/// The hashmap of `hash_a` over the seeds of `a`, so that the matches of many
+/// sequences `b` against the same `a` can be found without hashing its seeds again.
+pub struct SeedIndex {
+    config: MatchConfig,
+    seeds: Seeds,
+    index: QGramHashMap,
+}
+
+impl SeedIndex {
+    /// Hash the seeds of `a`. Only exact matches of fixed-length seeds are supported.
+    pub fn new(a: Seq, config: MatchConfig) -> Self {
+        assert!(config.r == 1);
+        let k = config.length.k().unwrap();
+        let seeds = QGrams::new(a, &[]).fixed_length_seeds(k, config.r, config.offset);
+        let seeds = Seeds::new(a, mask::mask_seeds(a, &config, seeds));
+        let index = hash_qgrams(seed_qgrams(a, &seeds).into_iter());
+        Self {
+            config,
+            seeds,
+            index,
+        }
+    }
+
+    /// The same matches as `hash_a`, for the `a` this index was built for.
+    pub fn find_matches<'a>(&self, a: Seq<'a>, b: Seq<'a>, transform_filter: bool) -> Matches {
+        assert_eq!(
+            self.seeds.potential.len(),
+            a.len() + 1,
+            "The index was built for another sequence."
+        );
+        let k = self.config.length.k().unwrap();
+        let q = QGrams::new(a, b);
+        let seeds = self.seeds.clone();
+        let mut matches = MatchBuilder::with_masked_seeds(&q, self.config, transform_filter, seeds);
+        lookup_qgrams(&self.index, q.b_qgrams_rev(k), &mut matches, k, Pos);
+        matches.sort();
+        matches.finish()
+    }
+}
+
+type QGramHashMap = HashMap<u32, SmallVec<[I; 2]>>;
+
+/// Hash the positions of each q-gram.
+fn hash_qgrams(qgrams: impl Iterator<Item = (i32, usize)>) -> QGramHashMap {
+    // TODO: See if we can get rid of the Vec alltogether.
+    let mut h = QGramHashMap::default();
+    h.reserve(qgrams.size_hint().0);
+    for (i, q) in qgrams {
+        h.entry(q as u32).or_default().push(i as I);
+    }
+    h
+}
+
 fn hash_to_smallvec(
     qgrams_hashed: impl Iterator<Item = (i32, usize)>,
     qgrams_lookup: impl Iterator<Item = (i32, usize)>,
     matches: &mut MatchBuilder,
     k: i32,
     to_pos: impl Fn(I, I) -> Pos,
+) {
+    let h = hash_qgrams(qgrams_hashed);
+    lookup_qgrams(&h, qgrams_lookup, matches, k, to_pos);
+}
+
+/// Add a match for each position of `h` with a q-gram in `qgrams_lookup`.
+fn lookup_qgrams(
+    h: &QGramHashMap,
+    qgrams_lookup: impl Iterator<Item = (i32, usize)>,
+    matches: &mut MatchBuilder,
+    k: i32,
+    to_pos: impl Fn(I, I) -> Pos,
 ) {
     type Key = u32;
 
-    // TODO: See if we can get rid of the Vec alltogether.
-    let mut h = HashMap::<Key, SmallVec<[I; 2]>>::default();
-    h.reserve(qgrams_hashed.size_hint().0);
-    for (i, q) in qgrams_hashed {
-        h.entry(q as Key).or_default().push(i as I);
-    }
     for (j, q) in qgrams_lookup {
         if let Some(is) = h.get(&(q as Key)) {
             for &i in is {
@@ -79,7 +205,14 @@ pub fn hash_a_single<'a>(
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    hash_to_single_vec(q.a_qgrams(k), q.b_qgrams_rev(k), &mut matches, k, Pos);
+    let seed_qgrams = matches.seed_qgrams();
+    hash_to_single_vec(
+        seed_qgrams.into_iter(),
+        q.b_qgrams_rev(k),
+        &mut matches,
+        k,
+        Pos,
+    );
     matches.sort();
     matches.finish()
 }
@@ -95,9 +228,14 @@ pub fn hash_b_single<'a>(
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    hash_to_single_vec(q.b_qgrams(k), q.a_qgrams_rev(k), &mut matches, k, |j, i| {
-        Pos(i, j)
-    });
+    let seed_qgrams = matches.seed_qgrams();
+    hash_to_single_vec(
+        q.b_qgrams(k),
+        seed_qgrams.into_iter().rev(),
+        &mut matches,
+        k,
+        |j, i| Pos(i, j),
+    );
     matches.sort();
     matches.finish()
 }
@@ -167,7 +305,14 @@ pub fn hash_a_qgram_index<'a>(
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    qgram_index(q.a_qgrams(k), q.b_qgrams_rev(k), &mut matches, k, Pos);
+    let seed_qgrams = matches.seed_qgrams();
+    qgram_index(
+        seed_qgrams.into_iter(),
+        q.b_qgrams_rev(k),
+        &mut matches,
+        k,
+        Pos,
+    );
     matches.sort();
     matches.finish()
 }
@@ -183,9 +328,14 @@ pub fn hash_b_qgram_index<'a>(
     let k = config.length.k().unwrap();
     let q = QGrams::new(a, b);
     let mut matches = MatchBuilder::new(&q, config, transform_filter);
-    qgram_index(q.b_qgrams(k), q.a_qgrams_rev(k), &mut matches, k, |j, i| {
-        Pos(i, j)
-    });
+    let seed_qgrams = matches.seed_qgrams();
+    qgram_index(
+        q.b_qgrams(k),
+        seed_qgrams.into_iter().rev(),
+        &mut matches,
+        k,
+        |j, i| Pos(i, j),
+    );
     matches.sort();
     matches.finish()
 }
@@ -279,8 +429,8 @@ pub fn find_matches_qgramindex<'a>(
 
     let seeds = {
         let mut v: Vec<Seed> = Vec::default();
-        let mut a = &a[..];
-        let mut i = 0 as I;
+        let mut i = config.offset.min(a.len() as I);
+        let mut a = &a[i as usize..];
         loop {
             // TODO: Clever seed choice, using variable k and m.
             let seed_len = {
@@ -407,15 +557,10 @@ pub fn hash_a_sliding_window<'a>(
         )
     };
 
-    // Iterators pointing to the next i to be inserted to/removed from the hashmap.
-    let mut to_remove = (0..a.len() + 1 - k as usize)
-        .step_by(k as usize)
-        .rev()
-        .peekable();
-    let mut to_insert = (0..a.len() + 1 - k as usize)
-        .step_by(k as usize)
-        .rev()
-        .peekable();
+    // Iterators pointing to the next seed to be inserted to/removed from the hashmap.
+    let seed_qgrams = matches.seed_qgrams();
+    let mut to_remove = seed_qgrams.iter().rev().peekable();
+    let mut to_insert = seed_qgrams.iter().rev().peekable();
     let mut qb = 0usize;
     let prepend_qgram_b = |j: usize, qb: &mut usize| {
         *qb = (*qb >> width) | ((rank_transform.get(b[j]) as usize) << ((k - 1) as usize * width))
@@ -425,19 +570,18 @@ pub fn hash_a_sliding_window<'a>(
         if (b.len() - 1 - j) as Cost & ((1 << CHECK_EACH_J_LAYERS) - 1) == 0 {
             let (new_start, new_end) = i_range_for_j(j as Cost);
             // Remove elements after new_end.
-            while let Some(&i) = to_remove.peek() {
-                if (i as Cost) > new_end {
-                    let wi = QGrams::to_qgram(&a[i..i + k as usize]);
+            while let Some(&&(i, wi)) = to_remove.peek() {
+                if i > new_end {
                     to_remove.next();
                     let v = m.get_mut(&(wi as Key)).unwrap();
                     assert!(!v.is_empty());
                     // If last element in the smallvec, remove entirely. Else only remove from vector.
                     if v.len() == 1 {
-                        assert_eq!(v[0], i as Cost);
+                        assert_eq!(v[0], i);
                         m.remove(&(wi as Key)).unwrap();
                     } else {
                         // NOTE: This removes in O(1), but changes the order of the elements.
-                        v.swap_remove(v.iter().position(|x| *x == i as Cost).unwrap());
+                        v.swap_remove(v.iter().position(|x| *x == i).unwrap());
                         assert!(v.len() > 0);
                     }
                 } else {
@@ -445,11 +589,10 @@ pub fn hash_a_sliding_window<'a>(
                 }
             }
             // Insert new elements after new_start
-            while let Some(&i) = to_insert.peek() {
-                if (i as Cost) >= new_start.saturating_sub(2 * (1 << CHECK_EACH_J_LAYERS)) {
+            while let Some(&&(i, wi)) = to_insert.peek() {
+                if i >= new_start.saturating_sub(2 * (1 << CHECK_EACH_J_LAYERS)) {
                     to_insert.next();
-                    let wi = QGrams::to_qgram(&a[i..i + k as usize]);
-                    m.entry(wi as Key).or_default().push(i as I);
+                    m.entry(wi as Key).or_default().push(i);
                 } else {
                     break;
                 }
@@ -517,6 +660,89 @@ mod test {
                     assert_eq!(b1.matches, m.matches);
                     assert_eq!(b2.matches, m.matches);
                     assert_eq!(b3.matches, m.matches);
+                    #[cfg(feature = "parallel")]
+                    assert_eq!(
+                        hash_a_parallel(&a, &b, matchconfig, true).matches,
+                        m.matches
+                    );
+                }
+            }
+        }
+    }
+
+    /// With an offset, seeds start at `offset + k * i` and all methods still agree.
+    #[test]
+    fn seed_offset() {
+        let (a, b) = uniform_fixed(1000, 0.1);
+        let k = 5;
+        for offset in [0, 1, 3, 12] {
+            let config = MatchConfig {
+                offset,
+                ..MatchConfig::exact(k)
+            };
+            let m = hash_a(&a, &b, config, true);
+            let seeds = &m.seeds.seeds;
+            assert_eq!(seeds[0].start, offset);
+            assert!(seeds.iter().all(|s| (s.start - offset) % k == 0));
+            assert!(m.matches.iter().all(|m| (m.start.0 - offset) % k == 0));
+            assert_eq!(hash_b(&a, &b, config, true).matches, m.matches);
+            assert_eq!(hash_a_single(&a, &b, config, true).matches, m.matches);
+            assert_eq!(hash_b_qgram_index(&a, &b, config, true).matches, m.matches);
+        }
+    }
+
+    /// Masked seeds are never looked up, so all methods only push matches of kept seeds.
+    #[test]
+    fn masked_seeds() {
+        let (prefix, _) = uniform_fixed(200, 0.);
+        let mut a = prefix.clone();
+        a.extend(b"AC".repeat(100));
+        a.extend(&prefix);
+        let b = a.clone();
+        let k = 10;
+        let config = MatchConfig {
+            mask_low_complexity: Some(1.5),
+            ..MatchConfig::exact(k)
+        };
+
+        let q = QGrams::new(&a, &b);
+        let builder = MatchBuilder::new(&q, config, true);
+        let seed_qgrams = builder.seed_qgrams();
+        assert_eq!(seed_qgrams.len(), builder.seeds.seeds.len());
+        assert!(seed_qgrams.iter().all(|&(i, _)| i + k <= 200 || i >= 400));
+
+        let m = hash_a(&a, &b, config, true);
+        assert!(m
+            .matches
+            .iter()
+            .all(|m| m.start.0 + k <= 200 || m.start.0 >= 400));
+        assert_eq!(hash_b(&a, &b, config, true).matches, m.matches);
+        assert_eq!(hash_a_single(&a, &b, config, true).matches, m.matches);
+        assert_eq!(hash_b_single(&a, &b, config, true).matches, m.matches);
+        assert_eq!(hash_a_qgram_index(&a, &b, config, true).matches, m.matches);
+        assert_eq!(hash_b_qgram_index(&a, &b, config, true).matches, m.matches);
+    }
+
+    /// One `SeedIndex` gives the same matches as `hash_a` for each `b`.
+    #[test]
+    fn seed_index() {
+        let (a, _) = uniform_fixed(1000, 0.);
+        for config in [
+            MatchConfig::exact(5),
+            MatchConfig {
+                offset: 2,
+                local_pruning: 1,
+                ..MatchConfig::exact(7)
+            },
+        ] {
+            let index = SeedIndex::new(&a, config);
+            for e in [0.01, 0.1, 0.3] {
+                let (_, b) = uniform_fixed(1000, e);
+                for transform_filter in [false, true] {
+                    let m = hash_a(&a, &b, config, transform_filter);
+                    let indexed = index.find_matches(&a, &b, transform_filter);
+                    assert_eq!(indexed.matches, m.matches);
+                    assert_eq!(indexed.seeds.seeds.len(), m.seeds.seeds.len());
                 }
             }
         }
@@ -137,8 +137,8 @@ pub fn find_matches_qgramindex<'a>(
 
     let seeds = {
         let mut v: Vec<Seed> = Vec::default();
-        let mut a = &a[..];
-        let mut i = 0 as I;
+        let mut i = config.offset.min(a.len() as I);
+        let mut a = &a[i as usize..];
         loop {
             // TODO: Clever seed choice, using variable k and m.
             let seed_len = {
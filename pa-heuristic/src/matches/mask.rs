@@ -0,0 +1,134 @@
+//! Masking of low-complexity and soft-masked regions of `a`, in which no seeds are placed.
+//!
+//! Repeats like `ACACAC...` have many spurious matches, which make building and
+//! pruning the heuristic slow. Seeds overlapping a window with low entropy, or
+//! lowercase characters when `respect_softmask` is set, are dropped. This only
+//! lowers the potential, so the heuristic stays admissible.
+use super::qgrams::QGrams;
+use crate::{prelude::*, seeds::*};
+
+/// The length of the windows in which the entropy is computed.
+pub const WINDOW: usize = 64;
+
+/// The Shannon entropy in bits of the character counts of a window of length
+/// `len`. Between 0 (a single character) and 2 (uniform over `ACGT`).
+fn entropy(counts: &[usize; 4], len: usize) -> f32 {
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f32 / len as f32;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// For each position of `a`, whether it is inside a window of length `WINDOW`
+/// (or all of `a` when it is shorter) with entropy below `min_entropy`.
+pub fn low_complexity_mask(a: Seq, min_entropy: f32) -> Vec<bool> {
+    let w = min(WINDOW, a.len());
+    let mut mask = vec![false; a.len()];
+    if w == 0 {
+        return mask;
+    }
+    let mut counts = [0; 4];
+    for &c in &a[..w] {
+        counts[QGrams::char_to_bits(c)] += 1;
+    }
+    // Positions before this are already masked.
+    let mut masked_until = 0;
+    for start in 0..=a.len() - w {
+        if start > 0 {
+            counts[QGrams::char_to_bits(a[start - 1])] -= 1;
+            counts[QGrams::char_to_bits(a[start + w - 1])] += 1;
+        }
+        if entropy(&counts, w) < min_entropy {
+            mask[max(start, masked_until)..start + w].fill(true);
+            masked_until = start + w;
+        }
+    }
+    mask
+}
+
+/// For each position of `a`, whether it is soft-masked, i.e. lowercase.
+pub fn softmask(a: Seq) -> Vec<bool> {
+    a.iter().map(|c| c.is_ascii_lowercase()).collect()
+}
+
+/// Drop the seeds overlapping a low-complexity region, when
+/// `mask_low_complexity` is set, or a soft-masked region, when
+/// `respect_softmask` is set.
+pub fn mask_seeds(a: Seq, config: &MatchConfig, seeds: Vec<Seed>) -> Vec<Seed> {
+    let mut mask = match config.mask_low_complexity {
+        Some(min_entropy) => low_complexity_mask(a, min_entropy),
+        None if config.respect_softmask => vec![false; a.len()],
+        None => return seeds,
+    };
+    if config.respect_softmask {
+        for (m, s) in mask.iter_mut().zip(softmask(a)) {
+            *m |= s;
+        }
+    }
+    seeds
+        .into_iter()
+        .filter(|s| !mask[s.start as usize..s.end as usize].contains(&true))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mask_repeat() {
+        let (prefix, _) = pa_generate::uniform_fixed(200, 0.);
+        let mut a = prefix.clone();
+        a.extend(b"AC".repeat(100));
+        a.extend(&prefix);
+
+        // Random sequence has entropy close to 2; the repeat has entropy 1.
+        let mask = low_complexity_mask(&a, 1.5);
+        assert!(!mask[..150].contains(&true));
+        assert!(!mask[450..].contains(&true));
+        assert!(mask[200..400].iter().all(|&m| m));
+
+        // No seeds are placed in the repeat, and all matches are on seeds.
+        let b = a.clone();
+        let config = MatchConfig {
+            mask_low_complexity: Some(1.5),
+            ..MatchConfig::exact(10)
+        };
+        let matches = find_matches(&a, &b, config, false);
+        assert!(matches.seeds.seeds.len() < a.len() / 10);
+        assert!(matches
+            .seeds
+            .seeds
+            .iter()
+            .all(|s| s.end <= 200 || s.start >= 400));
+        assert!(matches
+            .matches
+            .iter()
+            .all(|m| matches.seeds.is_seed_start(m.start)));
+    }
+    #[test]
+    fn mask_lowercase() {
+        let (a, _) = pa_generate::uniform_fixed(300, 0.);
+        let b = a.clone();
+        let mut a = a;
+        a[100..200].make_ascii_lowercase();
+        let config = MatchConfig {
+            respect_softmask: true,
+            ..MatchConfig::exact(10)
+        };
+        let matches = find_matches(&a, &b, config, false);
+        assert_eq!(matches.seeds.seeds.len(), 20);
+        assert!(matches
+            .seeds
+            .seeds
+            .iter()
+            .all(|s| s.end <= 100 || s.start >= 200));
+        // Without soft-masking, the case is ignored.
+        let matches = find_matches(&a, &b, MatchConfig::exact(10), false);
+        assert_eq!(matches.seeds.seeds.len(), 30);
+    }
+}
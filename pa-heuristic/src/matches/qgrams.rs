@@ -42,20 +42,26 @@ impl<'a> QGrams<'a> {
         q
     }
 
-    pub fn a_qgrams(&self, k: I) -> impl '_ + Iterator<Item = (I, usize)> + Clone {
+    /// The suffix of `a` starting at the first seed.
+    fn a_from(&self, offset: I) -> Seq<'a> {
+        &self.a[(offset as usize).min(self.a.len())..]
+    }
+
+    /// The qgrams of the seeds of `a`, starting at `offset`.
+    pub fn a_qgrams(&self, k: I, offset: I) -> impl '_ + Iterator<Item = (I, usize)> + Clone {
         // NOTE: Computing each k-mer separately is 3x faster than doing a rolling window with `step_by(k)`.
-        (0..).step_by(k as _).zip(
-            self.a
+        (offset..).step_by(k as _).zip(
+            self.a_from(offset)
                 .chunks_exact(k as _)
                 .map(move |seed| Self::to_qgram(seed)),
         )
     }
 
-    pub fn a_qgrams_rev(&self, k: I) -> impl '_ + Iterator<Item = (I, usize)> + Clone {
-        self.a
+    pub fn a_qgrams_rev(&self, k: I, offset: I) -> impl '_ + Iterator<Item = (I, usize)> + Clone {
+        self.a_from(offset)
             .chunks_exact(k as _)
             .enumerate()
-            .map(move |(i, seed)| (k * i as I, Self::to_qgram(seed)))
+            .map(move |(i, seed)| (offset + k * i as I, Self::to_qgram(seed)))
             .rev()
     }
 
@@ -96,8 +102,9 @@ impl<'a> QGrams<'a> {
         .into_iter()
     }
 
-    pub fn fixed_length_seeds(&self, k: I, r: MatchCost) -> Vec<Seed> {
-        (0..self.a.len() as I - k + 1)
+    /// Seeds of length `k` starting at `offset, offset + k, ...`.
+    pub fn fixed_length_seeds(&self, k: I, r: MatchCost, offset: I) -> Vec<Seed> {
+        (offset..self.a.len() as I - k + 1)
             .step_by(k as _)
             .map(|i| Seed {
                 start: i as I,
@@ -128,9 +135,13 @@ mod test {
         let a = b"ACGT";
         let b = b"ACGT";
         let qgrams = QGrams::new(a, b);
-        assert_eq!(qgrams.a_qgrams(2).collect_vec(), [(0, 0b0001), (2, 0b1110)]);
         assert_eq!(
-            qgrams.a_qgrams_rev(2).collect_vec(),
+            qgrams.a_qgrams(2, 0).collect_vec(),
+            [(0, 0b0001), (2, 0b1110)]
+        );
+        assert_eq!(qgrams.a_qgrams(2, 1).collect_vec(), [(1, 0b0111)]);
+        assert_eq!(
+            qgrams.a_qgrams_rev(2, 0).collect_vec(),
             [(2, 0b1110), (0, 0b0001)]
         );
         assert_eq!(
@@ -209,6 +209,20 @@ impl MatchPruner {
         self.by_start.iter()
     }
 
+    /// Iterates over all matches starting in `i_range × j_range`, sorted by `LexPos(start)`.
+    /// Columns are found by binary search.
+    pub fn matches_in(
+        &self,
+        i_range: Range<I>,
+        j_range: Range<I>,
+    ) -> impl '_ + Iterator<Item = &Match> {
+        let start = self.by_start.partition_point(|m| m.start.0 < i_range.start);
+        let end = self.by_start.partition_point(|m| m.start.0 < i_range.end);
+        self.by_start[start..end.max(start)]
+            .iter()
+            .filter(move |m| j_range.contains(&m.start.1))
+    }
+
     /// Returns number of matches pruned by start (succeeding this pos) and by end (preceding this pos).
     pub fn prune(&mut self, seeds: &Seeds, pos: Pos, mut f: impl FnMut(&Match)) -> (usize, usize) {
         let mut cnt = (0, 0);
@@ -16,7 +16,7 @@ pub struct Seed {
     pub seed_cost: MatchCost,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Seeds {
     /// Sorted by start.
     pub seeds: Vec<Seed>,
@@ -1,7 +1,52 @@
 use instant::Instant;
+use pa_types::{Seq, I};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use crate::config::TIME;
 
+/// Check that positions and costs for `a` and `b` fit in `I`.
+///
+/// `Pos` and `Cost` are 32-bit, so `len() as I` would silently wrap for
+/// sequences (or pairs) longer than `I::MAX`. Call this at the entry point of
+/// each aligner to fail with a clear message instead.
+pub fn assert_input_len(a: Seq, b: Seq) {
+    assert!(
+        a.len().saturating_add(b.len()) <= I::MAX as usize,
+        "Input too long: |a| + |b| = {} + {} exceeds the maximum of {} supported by 32-bit coordinates.",
+        a.len(),
+        b.len(),
+        I::MAX
+    );
+}
+
+/// A flag for cooperative cancellation, shared between an aligner and e.g. a GUI.
+///
+/// Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Two tokens are equal when they share the same flag.
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 // Time the duration once every n iterations.
 const TIME_EACH: usize = 64;
 
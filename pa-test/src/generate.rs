@@ -0,0 +1,287 @@
+//! Extra generators on top of `pa_generate`, for building test inputs with
+//! properties the plain error models do not guarantee.
+use pa_generate::ErrorModel;
+use pa_types::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::{self, Write};
+
+const BASES: &[u8; 4] = b"ACGT";
+
+/// A uniformly random base.
+fn rand_base(rng: &mut impl Rng) -> u8 {
+    BASES[rng.random_range(0..4)]
+}
+
+/// A random base that is `G` or `C` with probability `gc_content`, and `A` or `T` otherwise.
+pub fn rand_base_gc(gc_content: f32, rng: &mut impl Rng) -> u8 {
+    let gc = rng.random_bool(gc_content.clamp(0., 1.) as f64);
+    match (gc, rng.random_bool(0.5)) {
+        (true, false) => b'G',
+        (true, true) => b'C',
+        (false, false) => b'A',
+        (false, true) => b'T',
+    }
+}
+
+/// Trim `seq` or pad it with random bases so that its length is exactly `n`.
+pub fn fix_length(seq: &mut Sequence, n: usize, rng: &mut impl Rng) {
+    seq.truncate(n);
+    while seq.len() < n {
+        seq.push(rand_base(rng));
+    }
+}
+
+/// As `pa_generate::generate_model`, but both sequences have length exactly `n`.
+///
+/// Models like `NoisyInsert` change the length of `b` by about `e*n`. Here both
+/// sequences are trimmed or padded with random bases back to `n` afterwards,
+/// which is useful for fixed-size test matrices.
+pub fn generate_model_exact(
+    n: usize,
+    e: f32,
+    error_model: ErrorModel,
+    seed: u64,
+) -> (Sequence, Sequence) {
+    let (mut a, mut b) = pa_generate::generate_model(n, e, error_model, seed);
+    let rng = &mut StdRng::seed_from_u64(seed);
+    fix_length(&mut a, n, rng);
+    fix_length(&mut b, n, rng);
+    (a, b)
+}
+
+/// A uniformly random sequence of length `n`.
+pub fn random_sequence(n: usize, rng: &mut impl Rng) -> Sequence {
+    (0..n).map(|_| rand_base(rng)).collect()
+}
+
+/// A random sequence of length `n` with the given expected GC content.
+///
+/// AT- or GC-rich sequences change the rate of spurious seed matches, which is
+/// useful to stress-test heuristics.
+pub fn random_sequence_gc(n: usize, gc_content: f32, rng: &mut impl Rng) -> Sequence {
+    (0..n).map(|_| rand_base_gc(gc_content, rng)).collect()
+}
+
+/// Apply `round(e * a.len())` uniformly random substitutions, insertions and deletions to `a`.
+pub fn mutate(a: Seq, e: f32, rng: &mut impl Rng) -> Sequence {
+    mutate_with_quality(a, e, &QualityModel::default(), rng).0
+}
+
+/// Phred qualities assigned to the bases of a mutated sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QualityModel {
+    /// The quality of unmodified bases.
+    pub baseline: u8,
+    /// The quality of substituted and inserted bases, and of the base
+    /// following a deletion.
+    pub mutated: u8,
+}
+
+impl Default for QualityModel {
+    fn default() -> Self {
+        Self {
+            baseline: 40,
+            mutated: 10,
+        }
+    }
+}
+
+impl QualityModel {
+    /// The FASTQ character (Phred+33) for the given quality.
+    fn char(q: u8) -> u8 {
+        q.min(93) + 33
+    }
+}
+
+/// As `mutate`, but also returns a FASTQ (Phred+33) quality string for `b`
+/// where mutated positions have low quality.
+///
+/// Uses the same random choices as `mutate`, so `b` is identical for the same `rng`.
+pub fn mutate_with_quality(
+    a: Seq,
+    e: f32,
+    quality: &QualityModel,
+    rng: &mut impl Rng,
+) -> (Sequence, Vec<u8>) {
+    let mut b = a.to_vec();
+    let mut q = vec![QualityModel::char(quality.baseline); b.len()];
+    let low = QualityModel::char(quality.mutated);
+    let edits = (e * a.len() as f32).round() as usize;
+    for _ in 0..edits {
+        match rng.random_range(0..3) {
+            // Substitution: always change the base.
+            0 if !b.is_empty() => {
+                let i = rng.random_range(0..b.len());
+                let old = b[i];
+                while b[i] == old {
+                    b[i] = rand_base(rng);
+                }
+                q[i] = low;
+            }
+            // Deletion
+            1 if !b.is_empty() => {
+                let i = rng.random_range(0..b.len());
+                b.remove(i);
+                q.remove(i);
+                if let Some(q) = q.get_mut(i) {
+                    *q = low;
+                }
+            }
+            // Insertion
+            _ => {
+                let i = rng.random_range(0..=b.len());
+                b.insert(i, rand_base(rng));
+                q.insert(i, low);
+            }
+        }
+    }
+    (b, q)
+}
+
+/// Generate three related sequences `(a, b, c)`.
+///
+/// `a` is a random ancestor of length `n`, and `b` and `c` are derived from it
+/// independently with `e/2` errors each, so that `d(b, c) ≈ d(a, b) + d(a, c)`.
+/// Useful to test triangle-inequality and consistency properties of distances.
+pub fn generate_triple(n: usize, e: f32, rng: &mut impl Rng) -> (Sequence, Sequence, Sequence) {
+    let a = random_sequence(n, rng);
+    let b = mutate(&a, e / 2., rng);
+    let c = mutate(&a, e / 2., rng);
+    (a, b, c)
+}
+
+/// Options for `generate_pair`.
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOptions {
+    /// `(count, length)`: splice `count` runs of `length` `N` characters into
+    /// both sequences, at independent random positions.
+    ///
+    /// Mimics the gaps between contigs in reference assemblies.
+    pub n_run: Option<(usize, usize)>,
+}
+
+/// Insert `count` runs of `len` `N` characters at random positions in `seq`.
+pub fn insert_n_runs(seq: &mut Sequence, count: usize, len: usize, rng: &mut impl Rng) {
+    insert_n_runs_with_quality(seq, None, count, len, rng);
+}
+
+/// As `insert_n_runs`, and also insert the lowest quality `!` into `quality`.
+fn insert_n_runs_with_quality(
+    seq: &mut Sequence,
+    mut quality: Option<&mut Vec<u8>>,
+    count: usize,
+    len: usize,
+    rng: &mut impl Rng,
+) {
+    for _ in 0..count {
+        let i = rng.random_range(0..=seq.len());
+        seq.splice(i..i, std::iter::repeat_n(b'N', len));
+        if let Some(q) = &mut quality {
+            q.splice(i..i, std::iter::repeat_n(b'!', len));
+        }
+    }
+}
+
+/// A random sequence `a` of length `n` and `b = mutate(a, e)`, modified
+/// according to `options`.
+pub fn generate_pair(
+    n: usize,
+    e: f32,
+    options: &GenerateOptions,
+    rng: &mut impl Rng,
+) -> (Sequence, Sequence) {
+    let (a, b, _quality) = generate_pair_with_quality(n, e, options, &QualityModel::default(), rng);
+    (a, b)
+}
+
+/// As `generate_pair`, but also returns a FASTQ quality string for `b`, as
+/// in `mutate_with_quality`. Bases in `N` runs get the lowest quality.
+pub fn generate_pair_with_quality(
+    n: usize,
+    e: f32,
+    options: &GenerateOptions,
+    quality: &QualityModel,
+    rng: &mut impl Rng,
+) -> (Sequence, Sequence, Vec<u8>) {
+    let mut a = random_sequence(n, rng);
+    let (mut b, mut q) = mutate_with_quality(&a, e, quality, rng);
+    if let Some((count, len)) = options.n_run {
+        insert_n_runs(&mut a, count, len, rng);
+        insert_n_runs_with_quality(&mut b, Some(&mut q), count, len, rng);
+    }
+    (a, b, q)
+}
+
+/// Write a single FASTQ record.
+pub fn write_fastq(w: &mut impl Write, id: &str, seq: Seq, quality: &[u8]) -> io::Result<()> {
+    assert_eq!(
+        seq.len(),
+        quality.len(),
+        "Sequence and quality lengths differ."
+    );
+    writeln!(w, "@{id}")?;
+    w.write_all(seq)?;
+    writeln!(w, "\n+")?;
+    w.write_all(quality)?;
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gc_content() {
+        let rng = &mut StdRng::seed_from_u64(31415);
+        let n = 100_000;
+        for gc_content in [0.0, 0.2, 0.5, 0.8, 1.0] {
+            let seq = random_sequence_gc(n, gc_content, rng);
+            let gc = seq.iter().filter(|&&c| c == b'G' || c == b'C').count();
+            let observed = gc as f32 / n as f32;
+            assert!(
+                (observed - gc_content).abs() < 0.01,
+                "Expected GC content {gc_content}, got {observed}"
+            );
+        }
+    }
+
+    #[test]
+    fn n_runs() {
+        let rng = &mut StdRng::seed_from_u64(31415);
+        let options = GenerateOptions {
+            n_run: Some((3, 50)),
+        };
+        let (a, b) = generate_pair(1000, 0.1, &options, rng);
+        for seq in [a, b] {
+            assert_eq!(seq.iter().filter(|&&c| c == b'N').count(), 150);
+        }
+    }
+
+    #[test]
+    fn quality() {
+        let options = GenerateOptions {
+            n_run: Some((2, 10)),
+        };
+        let model = QualityModel::default();
+        let (a, b) = generate_pair(1000, 0.1, &options, &mut StdRng::seed_from_u64(31415));
+        let (a2, b2, q) = generate_pair_with_quality(
+            1000,
+            0.1,
+            &options,
+            &model,
+            &mut StdRng::seed_from_u64(31415),
+        );
+        // The quality string does not change the sequences.
+        assert_eq!((a, b), (a2, b2.clone()));
+        assert_eq!(b2.len(), q.len());
+        let low = q.iter().filter(|&&c| c == model.mutated + 33).count();
+        assert!(0 < low && low <= 100, "{low} low-quality bases");
+        assert_eq!(q.iter().filter(|&&c| c == b'!').count(), 20);
+
+        // Round trip through FASTQ.
+        let mut out = vec![];
+        write_fastq(&mut out, "b", &b2, &q).unwrap();
+        let lines: Vec<&[u8]> = out.split(|&c| c == b'\n').collect();
+        assert_eq!(lines, [&b"@b"[..], &b2[..], &b"+"[..], &q[..], &b""[..]]);
+    }
+}
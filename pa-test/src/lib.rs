@@ -4,6 +4,8 @@ use rand::{rng, seq::IteratorRandom, Rng};
 use pa_generate::ErrorModel;
 use pa_types::*;
 
+pub mod generate;
+
 fn test_sequences() -> Vec<(Seq<'static>, Seq<'static>)> {
     vec![
         (b"TTGGGTCAATCAGCCAGTTTTTA", b"TTTGAGTGGGTCATCACCGATTTTAT"),
@@ -21,6 +23,14 @@ fn test_sequences() -> Vec<(Seq<'static>, Seq<'static>)> {
 
 const FIXED: bool = false;
 
+/// The error models that all tests run on.
+const ERROR_MODELS: [ErrorModel; 4] = [
+    ErrorModel::Uniform,
+    ErrorModel::NoisyInsert,
+    ErrorModel::NoisyDelete,
+    ErrorModel::SymmetricRepeat,
+];
+
 pub fn gen_seqs() -> impl Iterator<Item = ((Sequence, Sequence), (usize, f32, ErrorModel, u64))> {
     let rng = &mut rng();
     let mut ns = vec![
@@ -40,12 +50,6 @@ pub fn gen_seqs() -> impl Iterator<Item = ((Sequence, Sequence), (usize, f32, Er
         es = es.into_iter().choose_multiple(rng, el / 4);
     }
 
-    let models = [
-        ErrorModel::Uniform,
-        ErrorModel::NoisyInsert,
-        ErrorModel::NoisyDelete,
-        ErrorModel::SymmetricRepeat,
-    ];
     // Run each test on a new random seed for increased coverage over time.
     let seeds = if FIXED {
         [31415]
@@ -54,7 +58,7 @@ pub fn gen_seqs() -> impl Iterator<Item = ((Sequence, Sequence), (usize, f32, Er
     };
     ns.into_iter()
         .cartesian_product(es)
-        .cartesian_product(models)
+        .cartesian_product(ERROR_MODELS)
         .cartesian_product(seeds)
         .map(|(((n, e), error_model), seed)| {
             let (a, b) = pa_generate::generate_model(n, e, error_model, seed);
@@ -135,3 +139,100 @@ pub fn test_aligner_up_to(mut aligner: impl Aligner, max_n: usize) {
         );
     }
 }
+
+/// Unit-cost edit distance computed by `triple_accel`, as an external oracle.
+///
+/// Only returns the cost, not a cigar.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TripleAccel;
+
+impl Aligner for TripleAccel {
+    fn align(&mut self, a: Seq, b: Seq) -> (Cost, Option<Cigar>) {
+        (triple_accel::levenshtein_exp(a, b) as Cost, None)
+    }
+}
+
+/// Compare the cost of `aligner` against `TripleAccel` on random inputs.
+///
+/// Unlike `gen_seqs`, the inputs are fully determined by the seeds: each seed
+/// picks a length and error rate and is used for all error models, so that
+/// any failure can be reproduced from the seed in the message.
+/// Returned cigars are verified as well.
+pub fn validate_aligner(mut aligner: impl Aligner, seeds: std::ops::Range<u64>) {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    for seed in seeds {
+        // Unlike `StdRng`, `ChaCha8Rng` is stable across versions of `rand`.
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        let n = rng.random_range(0..=1000);
+        let e = rng.random_range(0.0..=0.5f32);
+        for error_model in ERROR_MODELS {
+            let (a, b) = pa_generate::generate_model(n, e, error_model, seed);
+            let params = format!("seed {seed} n {n} e {e:.3} error_model {error_model:?}");
+            let expected = TripleAccel.align(&a, &b).0;
+            let (cost, cigar) = aligner.align(&a, &b);
+            assert_eq!(
+                cost, expected,
+                "Wrong cost for {params}\nAligner\n{aligner:?}"
+            );
+            if let Some(cigar) = cigar {
+                assert_eq!(
+                    cigar.verify(&CostModel::unit(), &a, &b),
+                    cost,
+                    "Wrong cigar for {params}"
+                );
+            }
+        }
+    }
+}
+
+/// The naive DP matrix for linear costs: `d[i][j]` is the cost of aligning
+/// `a[..i]` and `b[..j]`. Takes `O(nm)` time, so only use this as a reference
+/// on small inputs.
+pub fn dp_matrix(a: Seq, b: Seq, cm: &CostModel) -> Vec<Vec<Cost>> {
+    assert_eq!(cm.open, 0, "dp_matrix only supports linear costs.");
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            d[i][j] = if i == 0 || j == 0 {
+                (i + j) as Cost * cm.extend
+            } else {
+                (d[i - 1][j - 1] + cm.sub * (a[i - 1] != b[j - 1]) as Cost)
+                    .min(d[i - 1][j] + cm.extend)
+                    .min(d[i][j - 1] + cm.extend)
+            };
+        }
+    }
+    d
+}
+
+/// Swap insertions and deletions, turning an alignment of `(b, a)` into one of `(a, b)`.
+pub fn transpose_cigar(cigar: &Cigar) -> Cigar {
+    let mut cigar = cigar.clone();
+    for elem in &mut cigar.ops {
+        elem.op = match elem.op {
+            CigarOp::Ins => CigarOp::Del,
+            CigarOp::Del => CigarOp::Ins,
+            op => op,
+        };
+    }
+    cigar
+}
+
+/// Test that aligning `(b, a)` gives the same cost as `(a, b)`, and that the
+/// transposed cigar is a valid alignment of `(a, b)`.
+///
+/// The cigars themselves may differ, since ties between equally good
+/// alignments can be broken differently in both directions.
+pub fn test_aligner_symmetric(mut aligner: impl Aligner) {
+    for ((a, b), (n, e, error_model, seed)) in gen_seqs() {
+        let params = format!("seed {seed:>10} n {n:>5} e {e:>.2} error_model {error_model:?}");
+        let (cost, _) = aligner.align(&a, &b);
+        let (cost_t, cigar_t) = aligner.align(&b, &a);
+        assert_eq!(cost, cost_t, "Asymmetric cost for {params}");
+        if let Some(cigar_t) = cigar_t {
+            let cigar = transpose_cigar(&cigar_t);
+            assert_eq!(cigar.verify(&CostModel::unit(), &a, &b), cost, "{params}");
+        }
+    }
+}
@@ -88,6 +88,12 @@ pub enum KeyboardAction {
     Slower,
     ToEnd,
     Exit,
+    /// Save the current canvas to a file.
+    Screenshot,
+    /// Zoom in (positive) or out (negative).
+    Zoom(i32),
+    /// Move the view by the given number of pixels.
+    Pan(CPos),
     None,
 }
 
@@ -109,6 +115,10 @@ pub trait Canvas {
 
     fn save(&mut self, _path: &Path) {}
     fn save_transparent(&mut self, _path: &Path, _bg_color: Color) {}
+    /// Like `save_transparent`, but only the background inside `rects` is transparent.
+    fn save_transparent_rects(&mut self, path: &Path, _bg_color: Color, _rects: &[(CPos, I, I)]) {
+        self.save(path)
+    }
     fn present(&mut self) {}
 
     fn wait(&mut self, timeout: Duration) -> KeyboardAction;
@@ -1,9 +1,10 @@
-use crate::visualizer::{Config, VisualizerStyle, When};
+use crate::visualizer::{Config, Gradient, NpyValue, VisualizerStyle, When};
 use super::{canvas::*, VisualizerT};
 use clap::{value_parser, Parser};
 use pa_types::I;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Serialize, Deserialize)]
 #[clap(next_help_heading = "Visualizer")]
@@ -36,6 +37,7 @@ pub struct VisualizerArgs {
     pub pause: bool,
 
     /// Which frames to save.
+    /// Files are numbered `0.bmp, 1.bmp, ...` in a directory at --save-path, independent of --visualize.
     #[clap(long, display_order = 3, value_enum, value_name = "WHEN", default_value_t = When::None, hide_short_help = true)]
     pub save: When,
 
@@ -43,6 +45,33 @@ pub struct VisualizerArgs {
     #[clap(long, display_order = 3, hide_short_help = true)]
     pub each: Option<usize>,
 
+    /// Show or save at most this many frames, by increasing --each based on
+    /// the input size. Frames beyond this are dropped, except the last.
+    #[clap(long, display_order = 3, value_name = "N", hide_short_help = true)]
+    pub max_frames: Option<usize>,
+
+    /// Show or save only frames in the range A..B (end exclusive).
+    /// Only replaces --visualize and --save `all`, also with --each; `first`,
+    /// `last` and `layers` are kept, and the last frame is still saved.
+    #[clap(long, display_order = 3, value_name = "A..B", value_parser = parse_range, hide_short_help = true)]
+    pub frames: Option<(usize, usize)>,
+
+    /// Frames per second when playing. Overrides the delay of the style.
+    /// Use the same value for `ffmpeg -framerate` when encoding saved frames.
+    #[clap(long, display_order = 3, value_parser = parse_fps, hide_short_help = true)]
+    pub fps: Option<f32>,
+
+    /// Also save the expanded states of the last frame as a NumPy array at
+    /// `<save-path>.npy`, holding the expansion order or f of each state.
+    #[clap(
+        long,
+        display_order = 3,
+        value_enum,
+        value_name = "VALUE",
+        hide_short_help = true
+    )]
+    pub save_npy: Option<NpyValue>,
+
     /// Where to save. Implies --save [last].
     #[clap(long, display_order = 4, value_name = "PATH", value_parser = value_parser!(PathBuf), hide_short_help = true)]
     pub save_path: Option<PathBuf>,
@@ -69,9 +98,128 @@ pub struct VisualizerArgs {
     #[clap(long, display_order = 10, hide_short_help = true)]
     pub no_draw_tree: bool,
 
+    /// Color of matches in the tree. Implies --draw-tree.
+    /// Either `#RRGGBB` or a name like `red`.
+    #[clap(long, display_order = 10, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub tree_match: Option<Color>,
+
+    /// Color of substitutions in the tree. Implies --draw-tree.
+    #[clap(long, display_order = 10, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub tree_substitution: Option<Color>,
+
+    /// Color of changes of direction in the tree. Implies --draw-tree.
+    #[clap(long, display_order = 10, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub tree_direction_change: Option<Color>,
+
+    /// Color of affine gap opens in the tree. Implies --draw-tree.
+    #[clap(long, display_order = 10, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub tree_affine_open: Option<Color>,
+
     /// Draw parents for the chaining computation.
     #[clap(long, display_order = 10, hide_short_help = true)]
     pub draw_parents: bool,
+
+    /// Background color.
+    #[clap(long, display_order = 11, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub bg_color: Option<Color>,
+
+    /// Color of the final path.
+    #[clap(long, display_order = 11, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub path_color: Option<Color>,
+
+    /// Color of the contours of the heuristic.
+    #[clap(long, display_order = 11, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub contour_color: Option<Color>,
+
+    /// Color of active matches.
+    #[clap(long, display_order = 11, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub active_match_color: Option<Color>,
+
+    /// Color of pruned matches.
+    #[clap(long, display_order = 11, value_name = "COLOR", value_parser = parse_color, hide_short_help = true)]
+    pub pruned_match_color: Option<Color>,
+
+    /// Colors of expanded states: a single COLOR, a gradient `COLOR..COLOR`,
+    /// or a part of the turbo colormap `turbo(0.2,0.95)`.
+    #[clap(long, display_order = 11, value_name = "GRADIENT", value_parser = parse_gradient, hide_short_help = true)]
+    pub expanded_gradient: Option<Gradient>,
+}
+
+fn parse_range(s: &str) -> Result<(usize, usize), String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("Expected a range A..B, got {s}"))?;
+    let parse = |x: &str| {
+        x.parse::<usize>()
+            .map_err(|e| format!("Bad frame {x}: {e}"))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start >= end {
+        return Err(format!(
+            "Expected a non-empty range A..B with A < B, got {s}"
+        ));
+    }
+    Ok((start, end))
+}
+
+/// Parse a positive and finite number of frames per second.
+fn parse_fps(s: &str) -> Result<f32, String> {
+    let fps = s.parse::<f32>().map_err(|e| format!("Bad fps {s}: {e}"))?;
+    // Very small values give a delay that does not fit in a `Duration`.
+    if !(fps > 0.0 && fps.is_finite()) || Duration::try_from_secs_f32(1.0 / fps).is_err() {
+        return Err(format!("Expected a positive and finite fps, got {s}"));
+    }
+    Ok(fps)
+}
+
+/// Parse a color given as `#RRGGBB` or as the name of one of the predefined colors.
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(format!("Expected a color #RRGGBB, got {s}"));
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("Bad color {s}: {e}"))
+        };
+        return Ok((channel(0)?, channel(2)?, channel(4)?, 0));
+    }
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "black" => BLACK,
+        "gray" | "grey" => GRAY,
+        "white" => WHITE,
+        "red" => RED,
+        "purple" => PURPLE,
+        "green" => GREEN,
+        "blue" => BLUE,
+        "cyan" => CYAN,
+        _ => return Err(format!("Unknown color {s}")),
+    })
+}
+
+/// Parse a gradient: a single color, `COLOR..COLOR`, or `turbo(START,END)`
+/// with `0 <= START < END <= 1`.
+pub fn parse_gradient(s: &str) -> Result<Gradient, String> {
+    if let Some(args) = s.strip_prefix("turbo(").and_then(|s| s.strip_suffix(')')) {
+        let (start, end) = args
+            .split_once(',')
+            .ok_or_else(|| format!("Expected turbo(START,END), got {s}"))?;
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<f64>()
+                .map_err(|e| format!("Bad turbo bound {x}: {e}"))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start < 0. || start >= end || end > 1. {
+            return Err(format!(
+                "Turbo bounds must satisfy 0 <= START < END <= 1, got {s}"
+            ));
+        }
+        return Ok(Gradient::TurboGradient(start..end));
+    }
+    if let Some((start, end)) = s.split_once("..") {
+        return Ok(Gradient::Gradient(parse_color(start)?..parse_color(end)?));
+    }
+    Ok(Gradient::Fixed(parse_color(s)?))
 }
 
 pub trait VisualizerRunner {
@@ -86,7 +234,7 @@ pub enum VisualizerType {
 
 impl VisualizerArgs {
     pub fn make_visualizer(&self) -> VisualizerType {
-        if self.visualize == When::None && self.save == When::None {
+        if self.visualize == When::None && self.save == When::None && self.save_npy.is_none() {
             return VisualizerType::NoVisualizer;
         }
 
@@ -100,10 +248,13 @@ impl VisualizerArgs {
             if config.save == When::Last {
                 config.save = When::None;
             }
+        }
+        config.save_npy = self.save_npy;
+        if config.save_last || config.save_npy.is_some() {
             config.filepath = self
                 .save_path
                 .clone()
-                .expect("--save-path must be set when --save is set");
+                .expect("--save-path must be set when --save or --save-npy is set");
         }
         let update = |when: &mut When| {
             if let Some(step) = self.each {
@@ -117,8 +268,19 @@ impl VisualizerArgs {
         };
         update(&mut config.draw);
         update(&mut config.save);
+        if let Some((start, end)) = self.frames {
+            for when in [&mut config.draw, &mut config.save] {
+                if matches!(when, When::All | When::StepBy(_)) {
+                    *when = When::Range(start, end);
+                }
+            }
+        }
 
+        config.max_frames = self.max_frames;
         config.paused = self.pause;
+        if let Some(fps) = self.fps {
+            config.delay = Duration::from_secs_f32(1.0 / fps);
+        }
 
         // Apply CLI flag customizations to the style.
         config.cell_size = self.cell_size.unwrap_or(0);
@@ -128,10 +290,43 @@ impl VisualizerArgs {
         if self.draw_tree {
             config.style.tree = Some(BLACK);
         }
+        for (arg, color) in [
+            (self.tree_match, &mut config.style.tree_match),
+            (self.tree_substitution, &mut config.style.tree_substitution),
+            (
+                self.tree_direction_change,
+                &mut config.style.tree_direction_change,
+            ),
+            (self.tree_affine_open, &mut config.style.tree_affine_open),
+        ] {
+            if arg.is_some() {
+                *color = arg;
+                config.style.tree.get_or_insert(BLACK);
+            }
+        }
         if self.no_draw_tree {
             config.style.tree = None;
         }
 
+        if let Some(c) = self.bg_color {
+            config.style.bg_color = c;
+        }
+        if let Some(c) = self.path_color {
+            config.style.path = Some(c);
+        }
+        if let Some(c) = self.contour_color {
+            config.style.contour = c;
+        }
+        if let Some(c) = self.active_match_color {
+            config.style.active_match = c;
+        }
+        if let Some(c) = self.pruned_match_color {
+            config.style.pruned_match = c;
+        }
+        if let Some(g) = &self.expanded_gradient {
+            config.style.expanded = g.clone();
+        }
+
         if self.draw_parents {
             config.style.draw_dt = false;
             config.style.draw_f = false;
@@ -150,3 +345,26 @@ impl VisualizerArgs {
         VisualizerType::Visualizer(config)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fps() {
+        assert_eq!(parse_fps("30"), Ok(30.));
+        assert_eq!(parse_fps("0.5"), Ok(0.5));
+        for s in ["0", "-1", "NaN", "inf", "1e-30", "fast"] {
+            assert!(parse_fps(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(parse_range("0..10"), Ok((0, 10)));
+        assert_eq!(parse_range("3..4"), Ok((3, 4)));
+        for s in ["5..2", "3..3", "3", "..4", "a..b", "-1..2"] {
+            assert!(parse_range(s).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! A `VisualizerT` that records the computed `j_range` of each block, for
+//! plotting the search frontier with external tools.
+use super::*;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The `j_range` of each block, as `(i, j_start, j_end)` where `i` is the last column of the block.
+pub type Layer = Vec<(I, I, I)>;
+
+/// Records the `j_range` reported for each block, grouped by layer
+/// (i.e. by band doubling iteration).
+///
+/// Clones share the same recording, so keep a clone of the config to read the
+/// frontier after aligning.
+#[derive(Clone, Default, Debug)]
+pub struct FrontierRecorder {
+    layers: Arc<Mutex<Vec<Layer>>>,
+}
+
+impl PartialEq for FrontierRecorder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.layers, &other.layers)
+    }
+}
+
+impl FrontierRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All completed layers recorded so far.
+    pub fn layers(&self) -> Vec<Layer> {
+        let mut layers = self.layers.lock().unwrap().clone();
+        // Drop the trailing layer that is still being filled.
+        if layers.last().is_some_and(|l| l.is_empty()) {
+            layers.pop();
+        }
+        layers
+    }
+
+    /// The given layer as a closed polygon: the top boundary from left to
+    /// right, followed by the bottom boundary from right to left.
+    pub fn polygon(layer: &Layer) -> Vec<Pos> {
+        let top = layer.iter().map(|&(i, j_start, _)| Pos(i, j_start));
+        let bottom = layer.iter().rev().map(|&(i, _, j_end)| Pos(i, j_end));
+        top.chain(bottom).collect()
+    }
+
+    /// Write all layers as tab-separated `layer i j_start j_end` lines.
+    pub fn write_tsv(&self, path: &Path) -> std::io::Result<()> {
+        let mut f = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(f, "layer\ti\tj_start\tj_end")?;
+        for (l, layer) in self.layers().iter().enumerate() {
+            for (i, j_start, j_end) in layer {
+                writeln!(f, "{l}\t{i}\t{j_start}\t{j_end}")?;
+            }
+        }
+        f.flush()
+    }
+}
+
+impl VisualizerT for FrontierRecorder {
+    type Instance = Self;
+    fn build(&self, _a: Seq, _b: Seq) -> Self::Instance {
+        self.layers.lock().unwrap().clear();
+        self.clone()
+    }
+    fn build_from_factory<CF: CanvasFactory>(&self, a: Seq, b: Seq) -> Self::Instance {
+        self.build(a, b)
+    }
+}
+
+impl VisualizerInstance for FrontierRecorder {
+    fn j_range(&mut self, start: Pos, end: Pos) {
+        let mut layers = self.layers.lock().unwrap();
+        if layers.is_empty() {
+            layers.push(vec![]);
+        }
+        layers.last_mut().unwrap().push((end.0, start.1, end.1));
+    }
+
+    fn new_layer<'a, HI: HeuristicInstance<'a>>(&mut self, _h: Option<&HI>) {
+        let mut layers = self.layers.lock().unwrap();
+        if layers.last().is_some_and(|l| !l.is_empty()) {
+            layers.push(vec![]);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![feature(let_chains, int_roundings, never_type)]
 
 pub mod cli;
+pub mod frontier;
 #[cfg(feature = "sdl")]
 mod sdl;
 pub mod visualizer;
@@ -25,6 +26,8 @@ pub trait CanvasFactory {
 /// The `Visualizer` configuration is `build` into a corresponding `VisualizerInstance` for each input pair.
 pub trait VisualizerT: Clone + Default + Debug + PartialEq {
     type Instance: VisualizerInstance;
+    /// True for `NoVis`, which ignores all callbacks.
+    const IS_DEFAULT: bool = false;
     // Build using an sdl2 canvas.
     fn build(&self, a: Seq, b: Seq) -> Self::Instance;
     fn build_from_factory<CF: CanvasFactory>(&self, a: Seq, b: Seq) -> Self::Instance;
@@ -55,6 +58,9 @@ pub trait VisualizerInstance {
         _h: Option<&HI>,
     ) {
     }
+    /// A diagonal transition state in an affine layer of the given type.
+    /// States in the main layer are reported via `expand` and `extend`.
+    fn expand_affine(&mut self, _pos: Pos, _layer: AffineLayerType, _g: Cost) {}
     fn expand_preprune(&mut self, _pos: Pos) {}
     fn extend_preprune(&mut self, _pos: Pos) {}
     fn expand_trace(&mut self, _pos: Pos) {}
@@ -69,6 +75,9 @@ pub trait VisualizerInstance {
     ) {
     }
     fn expand_block_trace(&mut self, _pos: Pos, _size: Pos) {}
+    /// Diagonal runs of matches in a computed block, as `(start, end)` states.
+    /// The states after `start` up to and including `end` are extended.
+    fn extend_block(&mut self, _runs: &[(Pos, Pos)]) {}
     fn expand_blocks<'a, HI: HeuristicInstance<'a>>(
         &mut self,
         _poss: [Pos; 4],
@@ -118,6 +127,7 @@ pub struct NoVis;
 
 impl VisualizerT for NoVis {
     type Instance = Self;
+    const IS_DEFAULT: bool = true;
     fn build(&self, _a: Seq, _b: Seq) -> Self::Instance {
         Self
     }
@@ -9,7 +9,7 @@ use sdl2::{
     video::Window,
     Sdl,
 };
-use std::{path::Path, time::Duration};
+use std::{cell::Cell, path::Path, time::Duration};
 pub struct SdlCanvas(sdl2::render::Canvas<Window>);
 
 lazy_static! {
@@ -17,8 +17,19 @@ lazy_static! {
 }
 
 thread_local! {
+    /// Video driver for this thread, set by `use_video_driver`.
+    static VIDEO_DRIVER: Cell<Option<&'static str>> = const { Cell::new(None) };
     static SDL_CONTEXT: Sdl = {
-        assert!(sdl2::hint::set("SDL_VIDEODRIVER", "wayland,x11"));
+        if let Some(driver) = VIDEO_DRIVER.get() {
+            assert!(sdl2::hint::set_with_priority(
+                "SDL_VIDEODRIVER",
+                driver,
+                &sdl2::hint::Hint::Override
+            ));
+        } else if std::env::var_os("SDL_VIDEODRIVER").is_none() {
+            // Respect an explicit driver, e.g. `offscreen` for headless rendering.
+            assert!(sdl2::hint::set("SDL_VIDEODRIVER", "wayland,x11"));
+        }
         sdl2::init().unwrap()
     };
     static FONT: Font<'static, 'static> = 'font: {
@@ -31,6 +42,13 @@ thread_local! {
     }
 }
 
+/// Use the given SDL video driver, e.g. `offscreen`, instead of the environment or default.
+/// Must be called before the first canvas is created on this thread.
+#[cfg(test)]
+pub(crate) fn use_video_driver(driver: &'static str) {
+    VIDEO_DRIVER.set(Some(driver));
+}
+
 fn to_point(CPos(x, y): CPos) -> Point {
     Point::new(x as i32, y as i32)
 }
@@ -55,7 +73,13 @@ impl CanvasFactory for SdlCanvasFactory {
     }
 }
 
-fn save_transparent(canvas: &SdlCanvas, path: &Path, bg_color: Option<Color>) {
+/// When `rects` is given, only the background inside these rectangles becomes transparent.
+fn save_transparent(
+    canvas: &SdlCanvas,
+    path: &Path,
+    bg_color: Option<Color>,
+    rects: Option<&[(CPos, I, I)]>,
+) {
     let pixel_format = canvas.0.default_pixel_format();
     let mut pixels = canvas
         .0
@@ -75,6 +99,35 @@ fn save_transparent(canvas: &SdlCanvas, path: &Path, bg_color: Option<Color>) {
     // Make the given colour transparent.
     if let Some(bg_color) = bg_color {
         surf.set_color_key(true, bg_color.into()).unwrap();
+
+        // Outside the transparent rects, change background pixels to a
+        // slightly different color so the color key does not apply to them.
+        if let Some(rects) = rects {
+            let format = surf.pixel_format();
+            assert_eq!(pixel_format.byte_size_per_pixel(), 4);
+            let key = sdl2::pixels::Color::from(bg_color).to_u32(&format);
+            let (r, g, b, a) = bg_color;
+            let opaque = sdl2::pixels::Color::RGBA(r ^ 1, g, b, a).to_u32(&format);
+            let inside = |x: i32, y: i32| {
+                rects
+                    .iter()
+                    .any(|&(CPos(rx, ry), w, h)| rx <= x && x < rx + w && ry <= y && y < ry + h)
+            };
+            surf.with_lock_mut(|px| {
+                for y in 0..height as i32 {
+                    for x in 0..width as i32 {
+                        if inside(x, y) {
+                            continue;
+                        }
+                        let idx = y as usize * pitch + x as usize * 4;
+                        let p: &mut [u8; 4] = (&mut px[idx..idx + 4]).try_into().unwrap();
+                        if u32::from_ne_bytes(*p) == key {
+                            *p = opaque.to_ne_bytes();
+                        }
+                    }
+                }
+            });
+        }
     }
 
     eprintln!("Saving: {}", path.display());
@@ -157,11 +210,15 @@ impl Canvas for SdlCanvas {
     }
 
     fn save(&mut self, path: &Path) {
-        save_transparent(self, path, None);
+        save_transparent(self, path, None, None);
     }
 
     fn save_transparent(&mut self, path: &Path, bg_color: Color) {
-        save_transparent(self, path, Some(bg_color));
+        save_transparent(self, path, Some(bg_color), None);
+    }
+
+    fn save_transparent_rects(&mut self, path: &Path, bg_color: Color, rects: &[(CPos, I, I)]) {
+        save_transparent(self, path, Some(bg_color), Some(rects));
     }
 
     fn present(&mut self) {
@@ -192,8 +249,16 @@ impl Canvas for SdlCanvas {
                                 return KeyboardAction::Slower
                             }
                             Keycode::Escape | Keycode::Q => return KeyboardAction::ToEnd,
+                            Keycode::W => return KeyboardAction::Screenshot,
                             _ => {}
                         },
+                        Event::MouseWheel { y, .. } if y != 0 => return KeyboardAction::Zoom(y),
+                        Event::MouseMotion {
+                            mousestate,
+                            xrel,
+                            yrel,
+                            ..
+                        } if mousestate.left() => return KeyboardAction::Pan(CPos(xrel, yrel)),
                         _ => {}
                     }
                 }
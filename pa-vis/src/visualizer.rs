@@ -8,11 +8,17 @@
 //! ffmpeg -framerate 20 -i %d.bmp -vf "pad=ceil(iw/2)*2:ceil(ih/2)*2" output.mp4
 //! ```
 
+mod log;
+mod npy;
+
+pub use npy::NpyValue;
+
 use super::{canvas::*, *};
 use clap::ValueEnum;
 use itertools::Itertools;
 use pa_affine_types::*;
 use pa_heuristic::matches::MatchStatus;
+use pa_heuristic::util::CancelToken;
 use pa_types::*;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -22,11 +28,20 @@ use std::process::exit;
 use std::{
     cell::{RefCell, RefMut},
     cmp::{max, min},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::Range,
     time::Duration,
 };
 
+/// Groups states by a value, e.g. their heuristic, to draw each group in one color.
+/// The iteration order is the drawing order, which matters when states share a
+/// downscaled cell. `HashMap` iterates in a random order, so with the
+/// `deterministic` feature a `BTreeMap` is used instead.
+#[cfg(not(feature = "deterministic"))]
+type DrawMap<K, V> = HashMap<K, V>;
+#[cfg(feature = "deterministic")]
+type DrawMap<K, V> = std::collections::BTreeMap<K, V>;
+
 #[derive(Debug, PartialEq, Default, Clone, Copy, ValueEnum, Serialize, Deserialize)]
 pub enum VisualizerStyle {
     #[default]
@@ -53,6 +68,9 @@ pub enum When {
     LayersStepBy(usize),
     #[clap(skip)]
     Frames(Vec<usize>),
+    // Show/save frames in the half-open range [start, end).
+    #[clap(skip)]
+    Range(usize, usize),
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -107,17 +125,30 @@ pub struct Visualizer {
     // The last DP state (a.len(), b.len()).
     target: Pos,
 
-    // Number of calls to draw().
+    // Number of calls to draw(). `config.draw` and `config.save` are evaluated on this.
     frame_number: usize,
     // Number of calls to draw() for a new layer.
     layer_number: usize,
-    // Number of saved frames.
+    // Number of frames saved because of `config.save`, and the name of the next file.
+    // Independent of `config.draw`, so that saved files are always numbered `0, 1, 2, ...`.
     file_number: usize,
-    // Number of times config.draw triggers.
+    // Number of times `config.draw` triggers, independent of `config.save`.
+    // Used to select `config.draw_single_frame`.
     drawn_frame_number: usize,
+    // Number of screenshots taken with the screenshot key.
+    screenshot_number: usize,
+    // Whether the frame being drawn is the last one.
+    is_last_frame: bool,
+    // Zoom factor of the NW panel. Changed with the mouse wheel.
+    zoom: I,
+    // Top-left of the visible part of the NW panel, in unzoomed canvas pixels.
+    // Changed by dragging with the mouse.
+    pan: CPos,
 
     // Type, Pos, g, f
     pub expanded: Vec<(Type, ExpandPos, Cost, Cost)>,
+    // DT states in affine layers: Pos, layer type, g.
+    dt_affine: Vec<(Pos, AffineLayerType, Cost)>,
     pub trace: Vec<ExpandPos>,
     pub preprune: Vec<Pos>,
     // Calls to the heuristic.
@@ -133,6 +164,8 @@ pub struct Visualizer {
     expanded_layers: Vec<usize>,
     // Partial path for divide-and-conquer.
     meeting_points: Vec<Pos>,
+    // The cigar passed to the last frame, kept for `save_log`.
+    last_cigar: Option<AffineCigar>,
 }
 
 impl VisualizerInstance for Visualizer {
@@ -163,6 +196,12 @@ impl VisualizerInstance for Visualizer {
         self.draw(false, None, false, h, None);
     }
 
+    fn expand_affine(&mut self, pos: Pos, layer: AffineLayerType, g: Cost) {
+        if self.config.style.dt_layer_colors.is_some() && pos <= self.target {
+            self.dt_affine.push((pos, layer, g));
+        }
+    }
+
     fn expand_preprune(&mut self, pos: Pos) {
         if self.config.style.preprune.is_some() {
             self.preprune.push(pos);
@@ -258,6 +297,7 @@ impl VisualizerInstance for Visualizer {
         self.meeting_points.push(pos);
         if self.config.clear_after_meeting_point {
             self.expanded.clear();
+            self.dt_affine.clear();
         }
         self.draw::<HI>(false, None, true, None, None);
     }
@@ -268,7 +308,12 @@ impl VisualizerInstance for Visualizer {
         parent: ParentFn<'_>,
         h: Option<&H>,
     ) {
+        self.last_cigar = cigar.map(log::copy_cigar);
         self.draw(true, cigar, false, h, parent);
+        if let Some(value) = self.config.save_npy {
+            let path = self.config.filepath.with_extension("npy");
+            self.save_npy(&path, value).unwrap();
+        }
     }
 
     fn expand_block<'a, HI: HeuristicInstance<'a>>(
@@ -295,6 +340,20 @@ impl VisualizerInstance for Visualizer {
         }
     }
 
+    fn extend_block(&mut self, runs: &[(Pos, Pos)]) {
+        if self.config.style.extended.is_none() {
+            return;
+        }
+        for &(start, end) in runs {
+            for k in 1..=end.0 - start.0 {
+                let pos = start + Pos(k, k);
+                if pos <= self.target {
+                    self.expanded.push((Extended, ExpandPos::Single(pos), 0, 0));
+                }
+            }
+        }
+    }
+
     fn expand_blocks<'a, HI: HeuristicInstance<'a>>(
         &mut self,
         poss: [Pos; 4],
@@ -313,7 +372,16 @@ impl VisualizerInstance for Visualizer {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// Which state to draw when `downscaler > 1` maps multiple states to one cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellRepresentative {
+    /// The state with the largest f. Ties go to the most recent one.
+    MaxF,
+    /// The most recently expanded state.
+    Latest,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Gradient {
     Fixed(Color),
     Gradient(Range<Color>),
@@ -371,6 +439,8 @@ pub struct Style {
     pub path: Option<Color>,
     /// None to draw cells.
     pub path_width: Option<usize>,
+    /// None to let the drawing order decide which state is visible in a downscaled cell.
+    pub cell_representative: Option<CellRepresentative>,
 
     /// None to disable
     pub tree: Option<Color>,
@@ -388,6 +458,8 @@ pub struct Style {
     pub draw_matches: bool,
     pub draw_parents: bool,
     pub draw_dt: bool,
+    /// Colors for DT states in the M, I, and D layers, instead of the `expanded` gradient.
+    pub dt_layer_colors: Option<[Color; 3]>,
     pub draw_f: bool,
     pub draw_h_calls: bool,
     pub draw_f_calls: bool,
@@ -395,6 +467,11 @@ pub struct Style {
     pub draw_fixed_h: bool,
     pub h_call: Color,
     pub draw_labels: bool,
+    /// Draw a gradient bar for the colors of expanded states in the right margin of the NW panel.
+    pub draw_legend: bool,
+    /// Draw a line chart of the number of expanded states per layer in the bottom-right corner.
+    /// Requires `Config::layer_drawing`.
+    pub draw_layer_chart: bool,
     pub heuristic: Gradient,
     pub layer: Gradient,
     pub max_heuristic: Option<I>,
@@ -417,6 +494,7 @@ impl When {
             When::All => is_last || !new_layer,
             When::Layers => is_last || new_layer,
             When::Frames(v) => v.contains(&frame) || (is_last && v.contains(&usize::MAX)),
+            When::Range(start, end) => (*start..*end).contains(&frame),
             When::StepBy(step) => is_last || frame % step == 0,
             When::LayersStepBy(step) => is_last || (new_layer && layer % step == 0),
         }
@@ -424,6 +502,8 @@ impl When {
 }
 
 const CANVAS_HEIGHT: I = 1000;
+/// The maximal zoom factor of the NW panel.
+const MAX_ZOOM: I = 64;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Config {
@@ -435,18 +515,37 @@ pub struct Config {
     pub filepath: PathBuf,
     pub draw: When,
     /// Used in wasm rendering: the entire alignment is run and only this
-    /// single frame is drawn.
+    /// single frame is drawn, counting only frames where `draw` triggers.
+    /// Saving is not affected.
     pub draw_single_frame: Option<usize>,
     pub delay: Duration,
     pub paused: bool,
     pub save: When,
     pub save_last: bool,
+    /// When set, `When::All` and `When::StepBy` are replaced by a step such
+    /// that at most this many frames are drawn or saved. The step is derived
+    /// from the number of states, assuming each frame expands at least one.
+    /// Since states can be explored more than once, the drawn and saved frames
+    /// are also counted, and frames beyond the cap are dropped, except the last.
+    pub max_frames: Option<usize>,
     pub style: Style,
     pub transparent_bmp: bool,
+    /// With `transparent_bmp`, make the background of the NW panel transparent.
+    pub transparent_nw: bool,
+    /// With `transparent_bmp`, make the background of the DT panel transparent.
+    pub transparent_dt: bool,
     pub draw_old_on_top: bool,
     pub layer_drawing: bool,
+    /// The number of layers the gradient is spread over, for `layer_drawing`.
+    /// Defaults to the current number of layers. Ignored in the last frame,
+    /// where the actual total number of layers is known.
     pub num_layers: Option<usize>,
     pub clear_after_meeting_point: bool,
+    /// When set, quitting the visualizer cancels this token instead of exiting the process.
+    pub cancel: Option<CancelToken>,
+    /// When set, the last frame also writes the expanded states to `filepath.npy`.
+    /// See `Visualizer::save_npy`.
+    pub save_npy: Option<NpyValue>,
 }
 
 impl Config {
@@ -456,6 +555,7 @@ impl Config {
             downscaler: 1,
             save: When::None,
             save_last: false,
+            max_frames: None,
             filepath: PathBuf::default(),
             draw: When::None,
             draw_single_frame: None,
@@ -471,6 +571,7 @@ impl Config {
                 bg_color: WHITE,
                 path: Some(BLACK),
                 path_width: Some(2),
+                cell_representative: None,
                 tree: None,
                 tree_substitution: None,
                 tree_match: None,
@@ -484,6 +585,7 @@ impl Config {
                 draw_matches: false,
                 draw_parents: false,
                 draw_dt: true,
+                dt_layer_colors: None,
                 draw_f: false,
                 draw_h_calls: false,
                 draw_f_calls: false,
@@ -491,6 +593,8 @@ impl Config {
                 draw_fixed_h: false,
                 h_call: RED,
                 draw_labels: true,
+                draw_legend: false,
+                draw_layer_chart: false,
                 heuristic: Gradient::Gradient((250, 250, 250, 0)..(180, 180, 180, 0)),
                 layer: Gradient::Gradient((250, 250, 250, 0)..(100, 100, 100, 0)),
                 max_heuristic: None,
@@ -507,7 +611,11 @@ impl Config {
             layer_drawing: false,
             num_layers: None,
             transparent_bmp: true,
+            transparent_nw: true,
+            transparent_dt: true,
             clear_after_meeting_point: true,
+            cancel: None,
+            save_npy: None,
         };
 
         match style {
@@ -595,9 +703,10 @@ impl VisualizerT for Config {
     fn build(&self, a: Seq, b: Seq) -> Self::Instance {
         Visualizer::new::<crate::sdl::SdlCanvasFactory>(self.clone(), a, b)
     }
+    /// Without a canvas, the visualizer can only collect states, e.g. for `cost_landscape`.
     #[cfg(not(feature = "sdl"))]
-    fn build(&self, _a: Seq, _b: Seq) -> Self::Instance {
-        unimplemented!("Enable the pa_vis:sdl feature to use the default sdl canvas.");
+    fn build(&self, a: Seq, b: Seq) -> Self::Instance {
+        Visualizer::new::<NoCanvasFactory>(self.clone(), a, b)
     }
 
     fn build_from_factory<CF: CanvasFactory>(&self, a: Seq, b: Seq) -> Self::Instance {
@@ -605,6 +714,18 @@ impl VisualizerT for Config {
     }
 }
 
+/// Used without the `sdl` feature. Panics when a canvas is needed, i.e. when
+/// `draw`, `save`, or `save_last` is set.
+#[cfg(not(feature = "sdl"))]
+struct NoCanvasFactory;
+
+#[cfg(not(feature = "sdl"))]
+impl CanvasFactory for NoCanvasFactory {
+    fn new(_w: usize, _h: usize, _title: &str) -> Box<dyn Canvas> {
+        unimplemented!("Enable the pa_vis:sdl feature to use the default sdl canvas.");
+    }
+}
+
 struct Region {
     /// Start position on canvas
     start: CPos,
@@ -650,6 +771,18 @@ impl Visualizer {
             let ds = config.downscaler;
             config.cell_size = max(1, CANVAS_HEIGHT / (grid_height.div_ceil(ds)));
         }
+
+        if let Some(max_frames) = config.max_frames {
+            let states = grid_width as usize * grid_height as usize;
+            let step = max(1, states.div_ceil(max(max_frames, 1)));
+            for when in [&mut config.draw, &mut config.save] {
+                match *when {
+                    When::All if step > 1 => *when = When::StepBy(step),
+                    When::StepBy(s) => *when = When::StepBy(max(s, step)),
+                    _ => {}
+                }
+            }
+        }
         let nw = Region {
             start: CPos(0, 0),
             _cs: config.cell_size,
@@ -693,6 +826,7 @@ impl Visualizer {
             },
             config: config.clone(),
             expanded: vec![],
+            dt_affine: vec![],
             preprune: vec![],
             trace: vec![],
             h_calls: vec![],
@@ -706,9 +840,14 @@ impl Visualizer {
             layer_number: 0,
             file_number: 0,
             drawn_frame_number: 0,
+            screenshot_number: 0,
+            is_last_frame: false,
             layer: if config.layer_drawing { Some(0) } else { None },
             expanded_layers: vec![],
             meeting_points: vec![],
+            last_cigar: None,
+            zoom: 1,
+            pan: CPos(0, 0),
 
             canvas_size,
             nw,
@@ -717,37 +856,74 @@ impl Visualizer {
         }
     }
 
+    /// Apply the zoom and pan of the viewport to an NW panel position.
+    fn to_view(&self, CPos(x, y): CPos) -> CPos {
+        let CPos(px, py) = self.pan;
+        CPos((x - px) * self.zoom as i32, (y - py) * self.zoom as i32)
+    }
+
+    /// The size of a cell on the canvas, after zooming.
+    fn view_cell_size(&self) -> I {
+        self.config.cell_size * self.zoom
+    }
+
     fn cell_begin(&self, Pos(i, j): Pos) -> CPos {
-        CPos(
+        self.to_view(CPos(
             (i / self.config.downscaler * self.config.cell_size) as i32,
             (j / self.config.downscaler * self.config.cell_size) as i32,
-        )
+        ))
     }
 
-    fn cell_center(&self, Pos(i, j): Pos) -> CPos {
-        CPos(
-            (i / self.config.downscaler * self.config.cell_size + self.config.cell_size / 2) as i32,
-            (j / self.config.downscaler * self.config.cell_size + self.config.cell_size / 2) as i32,
-        )
+    fn cell_center(&self, pos: Pos) -> CPos {
+        let cs = self.view_cell_size() as i32;
+        self.cell_begin(pos) + CPos(cs / 2, cs / 2)
     }
 
-    fn cell_end(&self, Pos(i, j): Pos) -> CPos {
-        CPos(
-            (i / self.config.downscaler * self.config.cell_size + self.config.cell_size) as i32,
-            (j / self.config.downscaler * self.config.cell_size + self.config.cell_size) as i32,
-        )
+    fn cell_end(&self, pos: Pos) -> CPos {
+        let cs = self.view_cell_size() as i32;
+        self.cell_begin(pos) + CPos(cs, cs)
+    }
+
+    /// For each state in `expanded`, whether it should be drawn.
+    /// Of all expanded and extended states in the same downscaled cell, only
+    /// one is kept, so that the result does not depend on the drawing order.
+    fn cell_representatives(&self) -> Option<Vec<bool>> {
+        let representative = self.config.style.cell_representative?;
+        let ds = self.config.downscaler;
+        if ds <= 1 {
+            return None;
+        }
+        let mut keep = vec![true; self.expanded.len()];
+        let mut best = HashMap::<Pos, usize>::default();
+        for (i, (t, pos, _, f)) in self.expanded.iter().enumerate() {
+            let ExpandPos::Single(pos) = pos else {
+                continue;
+            };
+            if *t == Type::Explored {
+                continue;
+            }
+            keep[i] = false;
+            let cell = Pos(pos.0 / ds, pos.1 / ds);
+            let b = best.entry(cell).or_insert(i);
+            if match representative {
+                CellRepresentative::MaxF => *f >= self.expanded[*b].3,
+                CellRepresentative::Latest => true,
+            } {
+                *b = i;
+            }
+        }
+        for i in best.into_values() {
+            keep[i] = true;
+        }
+        Some(keep)
     }
 
     fn draw_pixel(&self, canvas: &mut CanvasBox, pos: Pos, color: Color) {
-        if self.config.cell_size == 1 {
+        let cs = self.view_cell_size();
+        if cs == 1 {
             canvas.draw_point(self.cell_begin(pos), color);
         } else {
-            canvas.fill_rect(
-                self.cell_begin(pos),
-                self.config.cell_size,
-                self.config.cell_size,
-                color,
-            );
+            canvas.fill_rect(self.cell_begin(pos), cs, cs, color);
         }
     }
 
@@ -757,8 +933,8 @@ impl Visualizer {
             .map(|p| {
                 (
                     self.cell_begin(*p),
-                    self.config.cell_size,
-                    self.config.cell_size,
+                    self.view_cell_size(),
+                    self.view_cell_size(),
                 )
             })
             .collect_vec();
@@ -867,8 +1043,18 @@ impl Visualizer {
             dir.set_extension(extension);
             dir
         };
-        if self.config.transparent_bmp {
+        if self.config.transparent_bmp && self.config.transparent_nw && self.config.transparent_dt {
             canvas.save_transparent(&path, self.config.style.bg_color);
+        } else if self.config.transparent_bmp {
+            // Only make the selected panels transparent.
+            let mut rects = vec![];
+            if self.config.transparent_nw {
+                rects.push((self.nw.start, self.nw.size.0, self.nw.size.1));
+            }
+            if self.config.transparent_dt && self.config.style.draw_dt {
+                rects.push((self.dt.start, self.dt.size.0, self.dt.size.1));
+            }
+            canvas.save_transparent_rects(&path, self.config.style.bg_color, &rects);
         } else {
             canvas.save(&path);
         }
@@ -882,34 +1068,60 @@ impl Visualizer {
         h: Option<&H>,
         parent: ParentFn,
     ) {
+        self.is_last_frame = is_last;
         self.frame_number += 1;
         if is_new_layer {
             self.layer_number += 1;
         }
-        if !self
-            .config
-            .draw
-            .is_active(self.frame_number, self.layer_number, is_last, is_new_layer)
-            && !self.config.save.is_active(
-                self.frame_number,
-                self.layer_number,
-                is_last,
-                is_new_layer,
-            )
-            && !(is_last && self.config.save_last)
-        {
+        // One frame is kept for the last one.
+        let below_max_frames =
+            |count: usize| is_last || self.config.max_frames.is_none_or(|m| count + 1 < m);
+        let mut draw =
+            self.config
+                .draw
+                .is_active(self.frame_number, self.layer_number, is_last, is_new_layer)
+                && below_max_frames(self.drawn_frame_number);
+        let save =
+            self.config
+                .save
+                .is_active(self.frame_number, self.layer_number, is_last, is_new_layer)
+                && below_max_frames(self.file_number);
+        let save_last = is_last && self.config.save_last;
+
+        if draw {
+            // Filter out non-target frames if only drawing a single frame.
+            if let Some(target_frame) = self.config.draw_single_frame {
+                draw = self.drawn_frame_number == target_frame;
+            }
+            self.drawn_frame_number += 1;
+        }
+        if !draw && !save && !save_last {
             return;
         }
 
-        // Filter out non-target frames if only drawing a single frame.
-        if let Some(target_frame) = self.config.draw_single_frame
-            && self.drawn_frame_number != target_frame
-        {
-            self.drawn_frame_number += 1;
-            return;
+        self.render(cigar, h, parent);
+        self.save(save, save_last);
+        if draw {
+            self.show(is_last, cigar, h, parent);
+        }
+    }
+
+    /// The number of layers to spread the gradient over, when currently at `layer`.
+    fn num_layers(&self, layer: usize) -> usize {
+        if self.is_last_frame {
+            layer
+        } else {
+            self.config.num_layers.unwrap_or(layer)
         }
-        self.drawn_frame_number += 1;
+    }
 
+    /// Draw the current state to the canvas, without presenting or saving it.
+    fn render<'a, H: HeuristicInstance<'a>>(
+        &mut self,
+        cigar: Option<&AffineCigar>,
+        h: Option<&H>,
+        parent: ParentFn,
+    ) {
         // DRAW
         {
             // Draw background.
@@ -932,7 +1144,7 @@ impl Visualizer {
             {
                 let mut hint = Default::default();
                 let h_max = self.config.style.max_heuristic.unwrap_or(h.h(Pos(0, 0)));
-                let mut value_pos_map = HashMap::<I, Vec<Pos>>::default();
+                let mut value_pos_map = DrawMap::<I, Vec<Pos>>::default();
                 for i in 0..=self.target.0 {
                     hint = h.h_with_hint(Pos(i, 0), hint).1;
                     let mut hint = hint;
@@ -965,7 +1177,7 @@ impl Visualizer {
                     if let Some(m) = self.config.style.max_layer {
                         l_max = m;
                     }
-                    let mut value_pos_map = HashMap::<I, Vec<Pos>>::default();
+                    let mut value_pos_map = DrawMap::<I, Vec<Pos>>::default();
                     for i in 0..=self.target.0 {
                         hint = h.layer_with_hint(Pos(i, 0), hint).unwrap().1;
                         let mut hint = hint;
@@ -1010,24 +1222,30 @@ impl Visualizer {
             if self.config.style.draw_parents
                 && let Some(h) = h
             {
-                let mut parent_pos_map = HashMap::<Pos, Vec<Pos>>::default();
+                // `Pos` is only partially ordered, so key by the tuple.
+                let mut parent_pos_map = DrawMap::<(I, I), Vec<Pos>>::default();
                 for i in 0..=self.target.0 {
                     for j in 0..=self.target.1 {
                         let pos = Pos(i, j);
                         let (_h, parent) = h.h_with_parent(pos);
-                        parent_pos_map.entry(parent).or_default().push(pos);
+                        parent_pos_map
+                            .entry((parent.0, parent.1))
+                            .or_default()
+                            .push(pos);
                     }
                 }
 
-                for (_i, (parent, poss)) in parent_pos_map.iter().enumerate() {
+                for (_i, (&(pi, pj), poss)) in parent_pos_map.iter().enumerate() {
                     self.draw_pixels(
                         &mut canvas,
                         poss,
-                        color_for_pos(parent), //self.config.style.heuristic.color(i as f64 / parent_pos_map.len() as f64),
+                        color_for_pos(&Pos(pi, pj)), //self.config.style.heuristic.color(i as f64 / parent_pos_map.len() as f64),
                     );
                 }
             }
 
+            let keep = self.cell_representatives();
+            let skip = |i: usize| keep.as_ref().is_some_and(|keep| !keep[i]);
             let mut draw_pos = |pos: &ExpandPos, color: Color| match pos {
                 ExpandPos::Single(pos) => self.draw_pixel(&mut canvas, *pos, color),
                 ExpandPos::Block(s, t) => self.draw_box(&mut canvas, *s, *t, color),
@@ -1049,7 +1267,9 @@ impl Visualizer {
                     match *t {
                         Type::Explored => continue,
                         Type::Extended => {
-                            if let Some(c) = self.config.style.extended {
+                            if let Some(c) = self.config.style.extended
+                                && !skip(i)
+                            {
                                 draw_pos(pos, c);
                             }
                         }
@@ -1064,11 +1284,13 @@ impl Visualizer {
                                 self.config
                                     .style
                                     .expanded
-                                    .color(current_layer, self.config.num_layers.unwrap_or(layer))
+                                    .color(current_layer, self.num_layers(layer))
                             } else {
                                 self.config.style.expanded.color(i, self.expanded.len())
                             };
-                            draw_pos(pos, color);
+                            if !skip(i) {
+                                draw_pos(pos, color);
+                            }
                         }
                     }
                 }
@@ -1087,7 +1309,9 @@ impl Visualizer {
                     match *t {
                         Type::Explored => continue,
                         Type::Extended => {
-                            if let Some(color) = self.config.style.extended {
+                            if let Some(color) = self.config.style.extended
+                                && !skip(i)
+                            {
                                 draw_pos(pos, color);
                             }
                         }
@@ -1102,11 +1326,13 @@ impl Visualizer {
                                 self.config
                                     .style
                                     .expanded
-                                    .color(current_layer, self.config.num_layers.unwrap_or(layer))
+                                    .color(current_layer, self.num_layers(layer))
                             } else {
                                 self.config.style.expanded.color(i, self.expanded.len())
                             };
-                            draw_pos(pos, color);
+                            if !skip(i) {
+                                draw_pos(pos, color);
+                            }
                         }
                     }
                 }
@@ -1363,8 +1589,21 @@ impl Visualizer {
             if let Some(parent) = parent
                 && let Some(tree_color) = self.config.style.tree
             {
-                for (_t, u, _, _) in &self.expanded {
-                    let u = u.pos();
+                // Blocks are traced from each cell in their last column.
+                let block_end = |&(pos, size): &(Pos, Pos)| {
+                    let i = pos.0 + size.0;
+                    (pos.1..=min(pos.1 + size.1, self.target.1)).map(move |j| Pos(i, j))
+                };
+                let starts = self
+                    .expanded
+                    .iter()
+                    .flat_map(|(_t, u, _, _)| match u {
+                        ExpandPos::Single(u) => vec![*u],
+                        ExpandPos::Block(pos, size) => block_end(&(*pos, *size)).collect(),
+                        ExpandPos::Blocks(blocks) => blocks.iter().flat_map(block_end).collect(),
+                    })
+                    .collect_vec();
+                for &u in &starts {
                     if self.config.style.tree_fr_only {
                         // Only trace if u is the furthest point on this diagonal.
                         let mut v = u;
@@ -1374,13 +1613,7 @@ impl Visualizer {
                             if !(v <= self.target) {
                                 break;
                             }
-                            if self
-                                .expanded
-                                .iter()
-                                .filter(|(_, u, _, _)| u.pos() == v)
-                                .count()
-                                > 0
-                            {
+                            if starts.contains(&v) {
                                 skip = true;
                                 break;
                             }
@@ -1458,6 +1691,15 @@ impl Visualizer {
                 }
             } // draw tree
 
+            // Clip the zoomed NW panel by clearing everything right of and below it.
+            if self.zoom > 1 {
+                let CPos(x, y) = self.nw.start + self.nw.size;
+                let CPos(w, h) = CPos(self.canvas_size.0, self.canvas_size.1);
+                let bg = self.config.style.bg_color;
+                canvas.fill_rect(CPos(x, 0), (w - x) as I, h as I, bg);
+                canvas.fill_rect(CPos(0, y), x as I, (h - y) as I, bg);
+            }
+
             // Draw labels
             if self.config.style.draw_labels {
                 let mut row = 0;
@@ -1531,11 +1773,23 @@ impl Visualizer {
                     GRAY,
                 );
             }
+
+            // Draw legend
+            if self.config.style.draw_legend && !self.expanded.is_empty() {
+                self.draw_legend(&mut canvas);
+            }
+            // Draw expanded states per layer.
+            if self.config.style.draw_layer_chart && !self.expanded_layers.is_empty() {
+                self.draw_layer_chart(&mut canvas);
+            }
         }
 
         self.draw_dt(cigar);
         self.draw_f(cigar, h);
+    }
 
+    /// Save the frame drawn by `render`, as configured.
+    fn save(&mut self, save: bool, save_last: bool) {
         let Some(canvas) = &self.canvas else {
             return;
         };
@@ -1543,11 +1797,7 @@ impl Visualizer {
 
         // SAVE
 
-        if self
-            .config
-            .save
-            .is_active(self.frame_number, self.layer_number, is_last, is_new_layer)
-        {
+        if save {
             self.save_canvas(&mut canvas, false, None);
             self.file_number += 1;
         }
@@ -1558,50 +1808,191 @@ impl Visualizer {
         }
 
         // Save the final frame separately if needed.
-        if is_last && self.config.save_last {
+        if save_last {
             self.save_canvas(&mut canvas, true, None);
         }
+    }
 
+    /// Show the frame drawn by `render`, and handle keyboard and mouse events.
+    fn show<'a, H: HeuristicInstance<'a>>(
+        &mut self,
+        is_last: bool,
+        cigar: Option<&AffineCigar>,
+        h: Option<&H>,
+        parent: ParentFn,
+    ) {
         // SHOW
 
-        if !self
-            .config
-            .draw
-            .is_active(self.frame_number, self.layer_number, is_last, is_new_layer)
-        {
+        //Keyboard events
+        loop {
+            let key = {
+                let mut canvas = self.canvas.as_ref().unwrap().borrow_mut();
+                canvas.present();
+                canvas.wait(if self.config.paused || is_last {
+                    Duration::MAX
+                } else {
+                    self.config.delay
+                })
+            };
+            match key {
+                KeyboardAction::Next => {}
+                KeyboardAction::Prev => {
+                    unimplemented!()
+                }
+                KeyboardAction::PausePlay => {
+                    self.config.paused = !self.config.paused;
+                }
+                KeyboardAction::Faster => {
+                    self.config.delay = self.config.delay.mul_f32(0.8);
+                }
+                KeyboardAction::Slower => {
+                    self.config.delay = self.config.delay.div_f32(0.8);
+                }
+                KeyboardAction::ToEnd => {
+                    self.config.draw = When::Last;
+                }
+                KeyboardAction::Exit => {
+                    eprintln!("Running aborted by user!");
+                    let Some(cancel) = &self.config.cancel else {
+                        exit(1);
+                    };
+                    // Let the aligner stop at its next check, and stop drawing.
+                    cancel.cancel();
+                    self.config.draw = When::None;
+                    self.config.paused = false;
+                }
+                KeyboardAction::Screenshot => {
+                    // Saved next to `filepath`, independent of `config.save`.
+                    let mut canvas = self.canvas.as_ref().unwrap().borrow_mut();
+                    self.save_canvas(
+                        &mut canvas,
+                        true,
+                        Some(&format!("screenshot{}", self.screenshot_number)),
+                    );
+                    self.screenshot_number += 1;
+                    continue;
+                }
+                KeyboardAction::Zoom(delta) => {
+                    // Zoom around the center of the NW panel.
+                    let center = self.nw.size / 2;
+                    let old_zoom = self.zoom;
+                    let new_zoom = if delta > 0 {
+                        min(old_zoom * 2, MAX_ZOOM)
+                    } else {
+                        max(old_zoom / 2, 1)
+                    };
+                    self.pan = self.pan + center / old_zoom as i32 - center / new_zoom as i32;
+                    if new_zoom == 1 {
+                        self.pan = CPos(0, 0);
+                    }
+                    self.zoom = new_zoom;
+                    self.render(cigar, h, parent);
+                    continue;
+                }
+                KeyboardAction::Pan(delta) => {
+                    self.pan = self.pan - delta / self.zoom as i32;
+                    self.render(cigar, h, parent);
+                    continue;
+                }
+                KeyboardAction::None => {}
+            }
+            break;
+        }
+    }
+
+    /// Draw a small line chart of the number of expanded states in each layer so far.
+    fn draw_layer_chart(&self, canvas: &mut CanvasBox) {
+        const W: I = 200;
+        const H: I = 100;
+        const MARGIN: I = 30;
+        let bl = CPos(
+            self.nw.start.0 + self.nw.size.0 - MARGIN - W,
+            self.nw.start.1 + self.nw.size.1 - MARGIN,
+        );
+        if bl.0 < self.nw.start.0 || bl.1 - H < self.nw.start.1 {
             return;
         }
 
-        //Keyboard events
-        canvas.present();
-        let key = canvas.wait(if self.config.paused || is_last {
-            Duration::MAX
+        // Counts per completed layer, followed by the current layer.
+        let counts = std::iter::once(&0)
+            .chain(&self.expanded_layers)
+            .chain(std::iter::once(&self.expanded.len()))
+            .tuple_windows()
+            .map(|(s, e)| e - s)
+            .collect_vec();
+        let max_count = max(*counts.iter().max().unwrap(), 1);
+        let x = |l: usize| bl.0 + (l * W as usize / max(counts.len() - 1, 1)) as I;
+        let y = |c: usize| bl.1 - (c * H as usize / max_count) as I;
+
+        canvas.fill_rect(bl.up(H), W, H, self.config.style.bg_color);
+        canvas.draw_rect(bl.up(H), W, H, GRAY);
+        for (l, (&c0, &c1)) in counts.iter().tuple_windows().enumerate() {
+            canvas.draw_line(CPos(x(l), y(c0)), CPos(x(l + 1), y(c1)), BLACK);
+        }
+        canvas.write_text(
+            bl.up(H),
+            HAlign::Left,
+            VAlign::Bottom,
+            &make_label("expanded/layer, max ", max_count),
+            GRAY,
+        );
+    }
+
+    /// Draw a vertical gradient bar with the colors used for expanded states.
+    ///
+    /// When drawing by layer, the bar spans the layers.
+    /// Otherwise, it spans the expansion order, labelled by the `f` of the first and last expanded state.
+    fn draw_legend(&self, canvas: &mut CanvasBox) {
+        const WIDTH: I = 12;
+        const MARGIN: I = 30;
+        let height = self.nw.size.1 - 2 * MARGIN;
+        if height <= 0 {
+            return;
+        }
+        let x = self.nw.start.0 + self.nw.size.0 - MARGIN;
+        let y = self.nw.start.1 + MARGIN;
+
+        let (cnt, top_label, bot_label) = if let Some(layer) = self.layer
+            && layer != 0
+        {
+            let cnt = self.num_layers(layer);
+            (cnt, make_label("layer ", 0), make_label("layer ", cnt))
         } else {
-            self.config.delay
-        });
-        match key {
-            KeyboardAction::Next => {}
-            KeyboardAction::Prev => {
-                unimplemented!()
-            }
-            KeyboardAction::PausePlay => {
-                self.config.paused = !self.config.paused;
-            }
-            KeyboardAction::Faster => {
-                self.config.delay = self.config.delay.mul_f32(0.8);
-            }
-            KeyboardAction::Slower => {
-                self.config.delay = self.config.delay.div_f32(0.8);
-            }
-            KeyboardAction::ToEnd => {
-                self.config.draw = When::Last;
-            }
-            KeyboardAction::Exit => {
-                eprintln!("Running aborted by user!");
-                exit(1);
-            }
-            KeyboardAction::None => {}
+            let f = |t: &(Type, ExpandPos, Cost, Cost)| t.3;
+            let mut expanded = self.expanded.iter().filter(|(t, ..)| *t == Expanded);
+            let first = expanded.next().map_or(0, f);
+            let last = expanded.last().map_or(first, f);
+            (
+                self.expanded.len(),
+                make_label("f = ", first),
+                make_label("f = ", last),
+            )
+        };
+
+        for dy in 0..height {
+            let idx = (dy as usize * cnt) / height as usize;
+            canvas.fill_rect(
+                CPos(x, y + dy),
+                WIDTH,
+                1,
+                self.config.style.expanded.color(idx, max(cnt, 1)),
+            );
         }
+        canvas.draw_rect(CPos(x, y), WIDTH, height, BLACK);
+        canvas.write_text(
+            CPos(x - 4, y),
+            HAlign::Right,
+            VAlign::Top,
+            &top_label,
+            BLACK,
+        );
+        canvas.write_text(
+            CPos(x - 4, y + height),
+            HAlign::Right,
+            VAlign::Bottom,
+            &bot_label,
+            BLACK,
+        );
     }
 
     // Draw DT states to the top-right 1/3rd of the canvas.
@@ -1708,7 +2099,16 @@ impl Visualizer {
                 );
             };
 
-        if self.config.draw_old_on_top {
+        if let Some([m_color, i_color, d_color]) = self.config.style.dt_layer_colors {
+            // Affine layers below the main layer.
+            for &(pos, layer, g) in &self.dt_affine {
+                let color = if layer.is_insert() { i_color } else { d_color };
+                canvas.fill_rect(state_coords((pos, g)), dt_cell_size, dt_cell_size, color);
+            }
+            for st in &self.expanded {
+                draw_state(&mut canvas, st, m_color);
+            }
+        } else if self.config.draw_old_on_top {
             // Expanded
             let mut current_layer = self.layer.unwrap_or(0);
             for (i, st) in self.expanded.iter().enumerate().rev() {
@@ -1721,7 +2121,7 @@ impl Visualizer {
                     self.config
                         .style
                         .expanded
-                        .color(current_layer, self.config.num_layers.unwrap_or(layer))
+                        .color(current_layer, self.num_layers(layer))
                 } else {
                     self.config.style.expanded.color(i, self.expanded.len())
                 };
@@ -1740,7 +2140,7 @@ impl Visualizer {
                     self.config
                         .style
                         .expanded
-                        .color(current_layer, self.config.num_layers.unwrap_or(layer))
+                        .color(current_layer, self.num_layers(layer))
                 } else {
                     self.config.style.expanded.color(i, self.expanded.len())
                 };
@@ -1795,6 +2195,37 @@ impl Visualizer {
         }
     }
 
+    /// The maximal `f` of the expanded states per column `i`, and per front
+    /// `g`, both sorted. These are the series plotted by `draw_f`, and show
+    /// where the search gets expensive.
+    /// Blocks count for each column they cover.
+    pub fn cost_landscape(&self) -> (Vec<(I, Cost)>, Vec<(Cost, Cost)>) {
+        let mut per_column = BTreeMap::<I, Cost>::new();
+        let mut per_front = BTreeMap::<Cost, Cost>::new();
+        for (t, pos, g, f) in &self.expanded {
+            if *t != Expanded {
+                continue;
+            }
+            let columns: Vec<Range<I>> = match pos {
+                ExpandPos::Single(p) => vec![p.0..p.0 + 1],
+                ExpandPos::Block(p, size) => vec![p.0..p.0 + size.0],
+                ExpandPos::Blocks(blocks) => {
+                    blocks.iter().map(|(p, size)| p.0..p.0 + size.0).collect()
+                }
+            };
+            for i in columns.into_iter().flatten() {
+                let m = per_column.entry(i).or_insert(*f);
+                *m = max(*m, *f);
+            }
+            let m = per_front.entry(*g).or_insert(*f);
+            *m = max(*m, *f);
+        }
+        (
+            per_column.into_iter().collect(),
+            per_front.into_iter().collect(),
+        )
+    }
+
     fn draw_f<'a, H: HeuristicInstance<'a>>(&mut self, cigar: Option<&AffineCigar>, h: Option<&H>) {
         if !self.config.style.draw_f || self.expanded.is_empty() {
             return;
@@ -1839,13 +2270,13 @@ impl Visualizer {
             .map(|st| st.3)
             .min()
             .unwrap();
-        let f_max = self
-            .expanded
+        let (per_column, per_front) = self.cost_landscape();
+        let f_max = per_column
             .iter()
-            .filter(|st| st.0 == Expanded)
-            .map(|st| st.3)
+            .chain(&per_front)
+            .map(|&(_, f)| f)
             .max()
-            .unwrap();
+            .unwrap_or(f_min);
         let f_y = |f| {
             (self.canvas_size.1 as i32).saturating_sub(
                 ((f as f32 - f_min as f32) / max(f_max - f_min, 1) as f32
@@ -1904,6 +2335,26 @@ impl Visualizer {
             }
         }
 
+        // The maximal f per column and per front on top.
+        for (i, f) in per_column {
+            canvas.fill_rect(
+                CPos((i * self.config.cell_size) as i32, f_y(f)),
+                self.config.cell_size,
+                1,
+                BLACK,
+            );
+        }
+        if self.config.style.draw_dt {
+            for (g, f) in per_front {
+                canvas.fill_rect(
+                    CPos(self.nw.size.0 as i32 + (g * dt_cell_size) as i32, f_y(f)),
+                    dt_cell_size,
+                    1,
+                    BLACK,
+                );
+            }
+        }
+
         // Horizontal line at final cost when path is given.
         let mut cost = None;
         if let Some(cigar) = cigar {
@@ -1957,3 +2408,125 @@ impl Visualizer {
         );
     }
 }
+
+/// Golden-image tests: render a fixed input and compare a hash of the saved
+/// image against `golden/visualizer.txt`.
+///
+/// Uses the SDL `offscreen` video driver, so no display is needed. Labels and
+/// the DT panel are disabled, so that the output does not depend on the
+/// installed font.
+/// After an intended change to the rendering, update the golden hashes with
+/// `PA_VIS_BLESS=1 cargo test -p pa-vis --features sdl golden`.
+#[cfg(all(test, feature = "sdl"))]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/visualizer.txt");
+
+    /// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions.
+    fn fnv1a(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf29ce484222325, |h, &c| {
+            (h ^ c as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Expand all states in column-major order with their edit distance as
+    /// `g`, and save only the last frame, including the path of `cigar`.
+    fn render(name: &str, mut config: Config, a: Seq, b: Seq, cigar: &AffineCigar) -> u64 {
+        let dir = std::env::temp_dir().join("pa-vis-golden");
+        config.filepath = dir.join(name);
+        config.draw = When::None;
+        config.save = When::None;
+        config.save_last = true;
+        config.transparent_bmp = false;
+        config.style.draw_labels = false;
+        config.style.draw_dt = false;
+
+        let mut vis = config.build(a, b);
+        let mut g = (0..=b.len() as Cost).collect_vec();
+        for i in 0..=a.len() {
+            if i > 0 {
+                let mut diag = g[0];
+                g[0] = i as Cost;
+                for j in 1..=b.len() {
+                    let sub = diag + (a[i - 1] != b[j - 1]) as Cost;
+                    diag = g[j];
+                    g[j] = min(sub, min(g[j], g[j - 1]) + 1);
+                }
+            }
+            for j in 0..=b.len() {
+                vis.expand::<!>(Pos(i as I, j as I), g[j], g[j], None);
+            }
+        }
+        vis.last_frame::<!>(Some(cigar), None, None);
+        drop(vis);
+
+        fnv1a(&std::fs::read(config.filepath.with_extension("bmp")).unwrap())
+    }
+
+    fn check(hashes: &[(&str, u64)]) {
+        if std::env::var_os("PA_VIS_BLESS").is_some() {
+            let golden = hashes
+                .iter()
+                .map(|(name, hash)| format!("{name} {hash:016x}\n"))
+                .collect::<String>();
+            std::fs::create_dir_all(Path::new(GOLDEN).parent().unwrap()).unwrap();
+            std::fs::write(GOLDEN, golden).unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(GOLDEN).unwrap_or_else(|e| {
+            panic!("Cannot read the golden hashes in {GOLDEN}: {e}. Generate them with PA_VIS_BLESS=1.")
+        });
+        let golden: HashMap<&str, u64> = golden
+            .lines()
+            .map(|line| {
+                let (name, hash) = line.split_once(' ').unwrap();
+                (name, u64::from_str_radix(hash, 16).unwrap())
+            })
+            .collect();
+        for (name, hash) in hashes {
+            assert_eq!(
+                golden.get(name),
+                Some(hash),
+                "Rendering of {name} changed. Rerun with PA_VIS_BLESS=1 if this is intended."
+            );
+        }
+    }
+
+    #[test]
+    fn golden() {
+        crate::sdl::use_video_driver("offscreen");
+
+        let a = b"ACGTACGTTGCA";
+        let b = b"ACGAACGTGCAA";
+        let mut cigar = AffineCigar::default();
+        for op in [
+            [AffineCigarOp::Match; 3].as_slice(),
+            &[AffineCigarOp::Sub],
+            &[AffineCigarOp::Match; 4],
+            &[AffineCigarOp::Del],
+            &[AffineCigarOp::Match; 3],
+            &[AffineCigarOp::Ins],
+        ]
+        .concat()
+        {
+            cigar.push_op(op);
+        }
+
+        let mut cells = Config::new(VisualizerStyle::Default);
+        cells.style.path_width = None;
+        let mut downscaled = Config::new(VisualizerStyle::Large);
+        downscaled.downscaler = 2;
+        downscaled.cell_size = 4;
+        downscaled.style.cell_representative = Some(CellRepresentative::MaxF);
+
+        let hashes = [
+            ("default", Config::new(VisualizerStyle::Default)),
+            ("cells", cells),
+            ("downscaled", downscaled),
+        ]
+        .map(|(name, config)| (name, render(name, config, a, b, &cigar)));
+        check(&hashes);
+    }
+}
@@ -0,0 +1,264 @@
+//! Save the state of a visualizer to a log file and re-render it later.
+//!
+//! The log is a plain text file with one record per line:
+//!
+//! ```text
+//! pa-vis-log 1
+//! target <i> <j>
+//! layers <idx> <idx> ...
+//! cigar <op> <cnt> <op> <cnt> ...
+//! <type> S <g> <f> <i> <j>
+//! <type> B <g> <f> <i> <j> <w> <h>
+//! <type> M <g> <f> <i> <j> <w> <h> <i> <j> <w> <h> ...
+//! ```
+//!
+//! `<type>` is one of `E` (expanded), `X` (explored) or `T` (extended).
+//! Cigar operations are `M`, `X`, `I`, `D`, and `i<l>`, `d<l>`, `o<l>`, `c<l>` for affine layer `l`.
+use super::*;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+/// Bump this whenever the format changes.
+const LOG_VERSION: usize = 1;
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+fn op_to_str(op: AffineCigarOp) -> String {
+    match op {
+        AffineCigarOp::Match => "M".into(),
+        AffineCigarOp::Sub => "X".into(),
+        AffineCigarOp::Ins => "I".into(),
+        AffineCigarOp::Del => "D".into(),
+        AffineCigarOp::AffineIns(l) => format!("i{l}"),
+        AffineCigarOp::AffineDel(l) => format!("d{l}"),
+        AffineCigarOp::AffineOpen(l) => format!("o{l}"),
+        AffineCigarOp::AffineClose(l) => format!("c{l}"),
+    }
+}
+
+fn op_from_str(s: &str) -> Result<AffineCigarOp> {
+    let layer = || {
+        s[1..]
+            .parse()
+            .map_err(|_| invalid(format!("Bad cigar op {s}")))
+    };
+    Ok(match s.chars().next() {
+        Some('M') => AffineCigarOp::Match,
+        Some('X') => AffineCigarOp::Sub,
+        Some('I') => AffineCigarOp::Ins,
+        Some('D') => AffineCigarOp::Del,
+        Some('i') => AffineCigarOp::AffineIns(layer()?),
+        Some('d') => AffineCigarOp::AffineDel(layer()?),
+        Some('o') => AffineCigarOp::AffineOpen(layer()?),
+        Some('c') => AffineCigarOp::AffineClose(layer()?),
+        _ => return Err(invalid(format!("Bad cigar op {s}"))),
+    })
+}
+
+/// Make an owned copy of a cigar, since `AffineCigar` is not `Clone`.
+pub(super) fn copy_cigar(cigar: &AffineCigar) -> AffineCigar {
+    let mut copy = AffineCigar::default();
+    for elem in cigar {
+        copy.push_elem(AffineCigarElem {
+            op: elem.op,
+            cnt: elem.cnt,
+        });
+    }
+    copy
+}
+
+impl Visualizer {
+    /// Write the expanded states, layers, target, and final cigar to `path`.
+    /// Use `Visualizer::from_log` to render them again with a different `Config`.
+    pub fn save_log(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut f = BufWriter::new(std::fs::File::create(path)?);
+        writeln!(f, "pa-vis-log {LOG_VERSION}")?;
+        writeln!(f, "target {} {}", self.target.0, self.target.1)?;
+        writeln!(f, "layers {}", self.expanded_layers.iter().join(" "))?;
+        if let Some(cigar) = &self.last_cigar {
+            let ops = cigar
+                .into_iter()
+                .map(|e| format!("{} {}", op_to_str(e.op), e.cnt))
+                .join(" ");
+            writeln!(f, "cigar {ops}")?;
+        }
+        for (t, pos, g, fv) in &self.expanded {
+            let t = match t {
+                Expanded => 'E',
+                Explored => 'X',
+                Extended => 'T',
+            };
+            match pos {
+                ExpandPos::Single(p) => writeln!(f, "{t} S {g} {fv} {} {}", p.0, p.1)?,
+                ExpandPos::Block(p, s) => {
+                    writeln!(f, "{t} B {g} {fv} {} {} {} {}", p.0, p.1, s.0, s.1)?
+                }
+                ExpandPos::Blocks(blocks) => writeln!(
+                    f,
+                    "{t} M {g} {fv} {}",
+                    blocks
+                        .iter()
+                        .map(|(p, s)| format!("{} {} {} {}", p.0, p.1, s.0, s.1))
+                        .join(" ")
+                )?,
+            }
+        }
+        f.flush()
+    }
+
+    /// Read a log written by `save_log` and render its last frame using `config`.
+    pub fn from_log<CF: CanvasFactory>(path: &Path, config: Config) -> Result<Self> {
+        let mut lines = BufReader::new(std::fs::File::open(path)?).lines();
+        let mut next_line = || -> Result<String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(invalid("Unexpected end of log")))
+        };
+
+        let header = next_line()?;
+        let version = header
+            .strip_prefix("pa-vis-log ")
+            .and_then(|v| v.parse::<usize>().ok())
+            .ok_or_else(|| invalid("Not a pa-vis log file"))?;
+        if version != LOG_VERSION {
+            return Err(invalid(format!(
+                "Unsupported log version {version}; expected {LOG_VERSION}"
+            )));
+        }
+
+        let num = |s: &str| -> Result<I> {
+            s.parse()
+                .map_err(|_| invalid(format!("Bad number {s} in log")))
+        };
+
+        let target = {
+            let line = next_line()?;
+            let v = line
+                .split_whitespace()
+                .skip(1)
+                .map(num)
+                .collect::<Result<Vec<_>>>()?;
+            let &[i, j] = &v[..] else {
+                return Err(invalid("Bad target line"));
+            };
+            Pos(i, j)
+        };
+
+        // Only the lengths of the sequences are used.
+        let a = vec![b'A'; target.0 as usize];
+        let b = vec![b'A'; target.1 as usize];
+        let mut vis = Visualizer::new::<CF>(config, &a, &b);
+
+        let mut cigar = None;
+        for line in lines {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let Some(key) = words.next() else {
+                continue;
+            };
+            match key {
+                "layers" => {
+                    vis.expanded_layers = words
+                        .map(|w| w.parse().map_err(|_| invalid("Bad layer index")))
+                        .collect::<Result<_>>()?;
+                    if vis.layer.is_some() {
+                        vis.layer = Some(vis.expanded_layers.len());
+                    }
+                }
+                "cigar" => {
+                    let mut c = AffineCigar::default();
+                    for (op, cnt) in words.tuples() {
+                        c.push_elem(AffineCigarElem {
+                            op: op_from_str(op)?,
+                            cnt: num(cnt)?,
+                        });
+                    }
+                    cigar = Some(c);
+                }
+                "E" | "X" | "T" => {
+                    let t = match key {
+                        "E" => Expanded,
+                        "X" => Explored,
+                        _ => Extended,
+                    };
+                    let kind = words.next().ok_or_else(|| invalid("Missing kind"))?;
+                    let v = words.map(num).collect::<Result<Vec<_>>>()?;
+                    if v.len() < 2 {
+                        return Err(invalid("Missing g and f"));
+                    }
+                    let (g, f) = (v[0], v[1]);
+                    let pos = match (kind, &v[2..]) {
+                        ("S", &[i, j]) => ExpandPos::Single(Pos(i, j)),
+                        ("B", &[i, j, w, h]) => ExpandPos::Block(Pos(i, j), Pos(w, h)),
+                        ("M", rest) if rest.len() % 4 == 0 => ExpandPos::Blocks(
+                            rest.chunks_exact(4)
+                                .map(|c| (Pos(c[0], c[1]), Pos(c[2], c[3])))
+                                .collect(),
+                        ),
+                        _ => return Err(invalid(format!("Bad expanded line: {line}"))),
+                    };
+                    vis.expanded.push((t, pos, g, f));
+                }
+                _ => return Err(invalid(format!("Unknown log line: {line}"))),
+            }
+        }
+
+        vis.draw::<!>(true, cigar.as_ref(), false, None, None);
+        vis.last_cigar = cigar;
+        Ok(vis)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Nothing is drawn or saved, so no canvas is ever created.
+    struct NoCanvas;
+    impl CanvasFactory for NoCanvas {
+        fn new(_w: usize, _h: usize, _title: &str) -> Box<dyn Canvas> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let a = b"ACGTACGT";
+        let b = b"ACGTTACGT";
+        let mut config = Config::new(VisualizerStyle::Default);
+        config.layer_drawing = true;
+        let mut vis = config.build(a, b);
+        vis.explore::<!>(Pos(0, 0), 0, 3, None);
+        vis.expand::<!>(Pos(0, 0), 0, 3, None);
+        vis.new_layer::<!>(None);
+        vis.expand_block::<!>(Pos(1, 0), Pos(2, 4), 1, 4, None);
+        vis.expand_blocks::<!>([Pos(3, 0); 4], [Pos(1, 2); 4], 2, 5, None);
+        vis.extend::<!>(Pos(5, 5), 2, 5, None);
+        let mut cigar = AffineCigar::default();
+        for (op, cnt) in [
+            (AffineCigarOp::Match, 4),
+            (AffineCigarOp::Ins, 1),
+            (AffineCigarOp::AffineDel(1), 2),
+        ] {
+            cigar.push_elem(AffineCigarElem { op, cnt });
+        }
+        vis.last_frame::<!>(Some(&cigar), None, None);
+
+        let path = std::env::temp_dir()
+            .join(format!("pa-vis-log-{}", std::process::id()))
+            .join("round_trip.log");
+        vis.save_log(&path).unwrap();
+        let read = Visualizer::from_log::<NoCanvas>(&path, config).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(read.target, vis.target);
+        assert_eq!(read.expanded_layers, vis.expanded_layers);
+        assert!(read.expanded == vis.expanded);
+        assert_eq!(read.last_cigar, Some(cigar));
+    }
+}
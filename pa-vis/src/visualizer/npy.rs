@@ -0,0 +1,118 @@
+//! Save the expanded states as a NumPy `.npy` array, for analysis outside of the images.
+//!
+//! The array has shape `(a.len()+1, b.len()+1)` and dtype `<i8`, and is
+//! indexed as `[i][j]`. States that were never expanded or extended are `-1`.
+use super::*;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+/// The value stored for each state in the `.npy` array.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, ValueEnum, Serialize, Deserialize)]
+pub enum NpyValue {
+    /// The index of the (last) expansion of the state, counting from 0.
+    Order,
+    /// The f-value of the (last) expansion of the state.
+    F,
+}
+
+impl Visualizer {
+    /// Write a 2D array with the expansion order or f-value of each state to `path`.
+    pub fn save_npy(&self, path: &Path, value: NpyValue) -> Result<()> {
+        let rows = self.target.0 as usize + 1;
+        let cols = self.target.1 as usize + 1;
+        let mut grid = vec![-1i64; rows * cols];
+        for (idx, (t, pos, _g, f)) in self.expanded.iter().enumerate() {
+            if *t == Explored {
+                continue;
+            }
+            let v = match value {
+                NpyValue::Order => idx as i64,
+                NpyValue::F => *f as i64,
+            };
+            let mut fill = |p: Pos, size: Pos| {
+                for i in p.0..min(p.0 + size.0, self.target.0 + 1) {
+                    for j in p.1..min(p.1 + size.1, self.target.1 + 1) {
+                        grid[i as usize * cols + j as usize] = v;
+                    }
+                }
+            };
+            // The same states as drawn by `draw_box` and `draw_boxes`.
+            match pos {
+                ExpandPos::Single(p) => fill(*p, Pos(1, 1)),
+                ExpandPos::Block(p, size) => fill(*p + Pos(1, 0), *size + Pos(0, 1)),
+                ExpandPos::Blocks(blocks) => {
+                    for (p, size) in blocks {
+                        fill(*p, *size);
+                    }
+                }
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut f = BufWriter::new(std::fs::File::create(path)?);
+        // Version 1.0 header: magic, version, header length, and a python dict
+        // padded with spaces so that the payload is 64-byte aligned.
+        let mut header =
+            format!("{{'descr': '<i8', 'fortran_order': False, 'shape': ({rows}, {cols}), }}");
+        let len = 10 + header.len() + 1;
+        header.extend(std::iter::repeat_n(' ', len.next_multiple_of(64) - len));
+        header.push('\n');
+        f.write_all(b"\x93NUMPY\x01\x00")?;
+        f.write_all(&(header.len() as u16).to_le_bytes())?;
+        f.write_all(header.as_bytes())?;
+        for v in grid {
+            f.write_all(&v.to_le_bytes())?;
+        }
+        f.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Read back the payload of a `.npy` file written by `save_npy`.
+    fn read_npy(path: &Path) -> Vec<i64> {
+        let data = std::fs::read(path).unwrap();
+        assert_eq!(&data[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([data[8], data[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        data[10 + header_len..]
+            .chunks_exact(8)
+            .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn block_matches_draw_box() {
+        let a = b"ACGTACGT";
+        let b = b"ACGTACGT";
+        let mut vis = Config::new(VisualizerStyle::Default).build(a, b);
+        vis.expand::<!>(Pos(0, 0), 0, 5, None);
+        // Like `draw_box`, this covers columns `3..6` and rows `0..=4`.
+        vis.expand_block::<!>(Pos(2, 0), Pos(3, 4), 0, 7, None);
+
+        let path = std::env::temp_dir()
+            .join(format!("pa-vis-npy-{}", std::process::id()))
+            .join("block.npy");
+        vis.save_npy(&path, NpyValue::F).unwrap();
+        let grid = read_npy(&path);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let cols = b.len() + 1;
+        assert_eq!(grid.len(), (a.len() + 1) * cols);
+        let at = |i: usize, j: usize| grid[i * cols + j];
+        assert_eq!(at(0, 0), 5);
+        for i in 0..=a.len() {
+            for j in 0..=b.len() {
+                if (i, j) == (0, 0) {
+                    continue;
+                }
+                let in_block = (3..6).contains(&i) && j <= 4;
+                assert_eq!(at(i, j), if in_block { 7 } else { -1 }, "({i}, {j})");
+            }
+        }
+    }
+}